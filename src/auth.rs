@@ -1,13 +1,14 @@
 use crate::backend::{
-    database::{PrimaryKey, Record},
-    user::User,
+    audit::AuditEntry,
+    database::{Database, DatabaseEntry, PrimaryKey, Record, SelectableByPrimaryKey},
+    user::{Metadata, PasswordHash, User},
 };
 use rocket::{
     form::{Form, Strict},
-    http::{Cookie, CookieJar, Status},
+    http::{Cookie, CookieJar, SameSite, Status},
     outcome::{IntoOutcome, Outcome},
-    response::Redirect,
-    serde::json,
+    response::{status::NoContent, Redirect},
+    serde::json::{self, Json},
     State,
 };
 use rocket_dyn_templates::{context, Template};
@@ -28,12 +29,57 @@ impl Credentials {
     }
 }
 
+/// The password change of a logged-in user.
+#[derive(Debug, Clone, FromForm)]
+pub struct PasswordChange {
+    pub password: String,
+    pub new_password: String,
+}
+
+/// A new password for another user, set by an administrator.
+#[derive(Debug, Clone, FromForm)]
+pub struct PasswordReset {
+    pub new_password: String,
+    /// Whether the user has to choose another password after the next login, which is not required by default.
+    pub must_change_password: Option<bool>,
+}
+
+/// The logged-in user and what they are allowed to do.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CurrentUser {
+    #[serde(flatten)]
+    pub user: Metadata,
+    pub can_create: bool,
+    pub can_delete: bool,
+}
+
+/// The content of the login cookie, which expires after the configured maximum age.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+struct Session {
+    user: PrimaryKey<User>,
+    issued_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Session {
+    /// Check whether the session is older than the maximum age.
+    fn is_expired(&self, now: chrono::DateTime<chrono::Utc>, max_age: std::time::Duration) -> bool {
+        match chrono::Duration::from_std(max_age) {
+            Ok(max_age) => now - self.issued_at > max_age,
+            // A maximum age beyond the range of dates never expires.
+            Err(_) => false,
+        }
+    }
+}
+
 /// The strategy how to proced in cases of missing authorization.
 pub trait Strategy: Default {
     /// Convert to object to an appropiated outcome
     fn to_outcome(
         value: Option<AuthenticatedUser<Self>>,
     ) -> Outcome<AuthenticatedUser<Self>, (Status, ()), Status>;
+
+    /// The outcome for a logged-in user who is not allowed to proceed.
+    fn forbidden() -> Outcome<AuthenticatedUser<Self>, (Status, ()), Status>;
 }
 
 /// Forward to the next possible route or return 'Unauthorized'.
@@ -46,6 +92,10 @@ impl Strategy for Forward {
     ) -> Outcome<AuthenticatedUser<Self>, (Status, ()), Status> {
         value.or_forward(Status::Unauthorized)
     }
+
+    fn forbidden() -> Outcome<AuthenticatedUser<Self>, (Status, ()), Status> {
+        Outcome::Forward(Status::Forbidden)
+    }
 }
 
 /// Fail fast and return 'Unauthorized'.
@@ -58,32 +108,106 @@ impl Strategy for Fail {
     ) -> Outcome<AuthenticatedUser<Self>, (Status, ()), Status> {
         value.or_error((Status::Unauthorized, ()))
     }
+
+    fn forbidden() -> Outcome<AuthenticatedUser<Self>, (Status, ()), Status> {
+        Outcome::Error((Status::Forbidden, ()))
+    }
+}
+
+/// The attributes of the cookie storing the login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieSettings {
+    pub name: String,
+    pub same_site: SameSite,
+    pub secure: bool,
+}
+
+impl CookieSettings {
+    /// Create the settings and reject combinations browsers would refuse.
+    pub fn new(name: String, same_site: SameSite, secure: bool) -> Option<Self> {
+        // Browsers reject cookies with 'SameSite=None' unless they are secure.
+        if name.is_empty() || (same_site == SameSite::None && !secure) {
+            return None;
+        }
+        Some(CookieSettings {
+            name,
+            same_site,
+            secure,
+        })
+    }
+
+    /// Parse a SameSite policy like "strict".
+    pub fn parse_same_site(value: &str) -> Option<SameSite> {
+        match value {
+            value if value.eq_ignore_ascii_case("strict") => Some(SameSite::Strict),
+            value if value.eq_ignore_ascii_case("lax") => Some(SameSite::Lax),
+            value if value.eq_ignore_ascii_case("none") => Some(SameSite::None),
+            _ => None,
+        }
+    }
+}
+
+impl Default for CookieSettings {
+    fn default() -> Self {
+        CookieSettings {
+            name: String::from(AuthenticatedUser::<Fail>::AUTH_COOKIE_NAME),
+            same_site: SameSite::Lax,
+            secure: false,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct AuthenticatedUser<T = Fail> {
     pub user: PrimaryKey<User>,
     strategy: T,
+    record: std::sync::OnceLock<Record<User>>,
 }
 
 impl<T: Strategy> AuthenticatedUser<T> {
-    /// The name of the cookie used to store the ID
+    /// The default name of the cookie used to store the ID
     pub const AUTH_COOKIE_NAME: &'static str = "shelby_auth";
 
+    /// The only path users who have to change their password are allowed to access.
+    pub const CHANGE_PASSWORD_PATH: &'static str = "/users/password";
+
     /// Login the given user.
-    pub fn login(cookies: &CookieJar, user: &Record<User>) {
+    pub fn login(cookies: &CookieJar, settings: &CookieSettings, user: &Record<User>) {
         cookies.add_private(
             Cookie::build((
-                Self::AUTH_COOKIE_NAME,
-                rocket::serde::json::to_string(&user.identifier).expect("valid serialized element"),
+                settings.name.clone(),
+                rocket::serde::json::to_string(&Session {
+                    user: user.identifier,
+                    issued_at: chrono::Utc::now(),
+                })
+                .expect("valid serialized element"),
             ))
-            .same_site(rocket::http::SameSite::Lax),
+            .same_site(settings.same_site)
+            .secure(settings.secure),
         );
     }
 
     /// Logout any registered user.
-    pub fn logout(cookies: &CookieJar) {
-        cookies.remove(Self::AUTH_COOKIE_NAME);
+    pub fn logout(cookies: &CookieJar, settings: &CookieSettings) {
+        cookies.remove(settings.name.clone());
+    }
+
+    /// Load the full record of the user, which is queried at most once per request. Inactive users are rejected.
+    pub fn record(&self, database: &Database) -> Result<&Record<User>, Error> {
+        if let Some(record) = self.record.get() {
+            return Ok(record);
+        }
+
+        let record = User::select_record(database, self.user)?.ok_or(Error::NotFound)?;
+        if !record.active {
+            return Err(Error::InactiveUser);
+        }
+        Ok(self.record.get_or_init(|| record))
+    }
+
+    /// Check whether the user is an administrator.
+    pub fn is_admin(&self, database: &Database) -> Result<bool, Error> {
+        Ok(self.record(database)?.is_admin)
     }
 }
 
@@ -94,46 +218,303 @@ impl<'r, T: Strategy> rocket::request::FromRequest<'r> for AuthenticatedUser<T>
     async fn from_request(
         request: &'r rocket::Request<'_>,
     ) -> Outcome<Self, (Status, Self::Error), Status> {
-        T::to_outcome(
-            request
-                .cookies()
-                .get_private(Self::AUTH_COOKIE_NAME)
-                .and_then(|cookie| json::from_str(cookie.value()).ok())
-                .map(|primary_key| AuthenticatedUser {
-                    user: primary_key,
-                    strategy: T::default(),
-                }),
-        )
+        let config = request.rocket().state::<Config>();
+        let cookie_name = match config {
+            Some(config) => config.cookie_settings().name.as_str(),
+            None => Self::AUTH_COOKIE_NAME,
+        };
+
+        let max_age = match config {
+            Some(config) => config.session_max_age(),
+            None => Config::DEFAULT_SESSION_MAX_AGE,
+        };
+
+        // Expired sessions are treated like missing ones.
+        let user: Option<PrimaryKey<User>> = request
+            .cookies()
+            .get_private(cookie_name)
+            .and_then(|cookie| json::from_str::<Session>(cookie.value()).ok())
+            .filter(|session| !session.is_expired(chrono::Utc::now(), max_age))
+            .map(|session| session.user);
+
+        // Users with a pending password change may only change it.
+        if let (Some(user), Some(config)) = (user, config) {
+            let database = match config.read_only_database() {
+                Ok(database) => database,
+                Err(_) => return Outcome::Error((Status::ServiceUnavailable, ())),
+            };
+            if request.uri().path() != Self::CHANGE_PASSWORD_PATH
+                && User::must_change_password(&database, user).unwrap_or(true)
+            {
+                return T::forbidden();
+            }
+        }
+
+        T::to_outcome(user.map(|primary_key| AuthenticatedUser {
+            user: primary_key,
+            strategy: T::default(),
+            record: std::sync::OnceLock::new(),
+        }))
     }
 }
 
-#[post("/users/login", data = "<credentials>")]
+/// A logged-in administrator, who may manage users and remove entries.
+pub struct AdminUser(pub AuthenticatedUser);
+
+#[rocket::async_trait]
+impl<'r> rocket::request::FromRequest<'r> for AdminUser {
+    type Error = ();
+
+    async fn from_request(
+        request: &'r rocket::Request<'_>,
+    ) -> Outcome<Self, (Status, Self::Error), Status> {
+        let user = rocket::outcome::try_outcome!(request.guard::<AuthenticatedUser>().await);
+        let config = match request.rocket().state::<Config>() {
+            Some(config) => config,
+            None => return Outcome::Error((Status::InternalServerError, ())),
+        };
+
+        // Other than a missing login, a lack of rights is not solved by logging in again.
+        let is_admin = match config.read_only_database() {
+            Ok(database) => user.is_admin(&database),
+            Err(_) => return Outcome::Error((Status::ServiceUnavailable, ())),
+        };
+        match is_admin {
+            Ok(true) => Outcome::Success(AdminUser(user)),
+            Ok(false) => Outcome::Error((Status::Forbidden, ())),
+            Err(Error::NotFound | Error::InactiveUser) => {
+                Outcome::Error((Status::Unauthorized, ()))
+            }
+            Err(_) => Outcome::Error((Status::InternalServerError, ())),
+        }
+    }
+}
+
+/// Check the target of a redirect is a path on this server, such that links could not lead to other sites.
+fn is_local_path(target: &str) -> bool {
+    // Browsers treat both "//example.com" and "/\example.com" as links to another host.
+    !target.starts_with("//")
+        && !target.contains('\\')
+        && rocket::http::uri::Origin::parse(target).is_ok()
+}
+
+/// Log in and redirect to `next`, which must be a local path.
+#[post("/users/login?<next>", data = "<credentials>")]
 pub fn login(
     state: &State<Config>,
     credentials: Form<Strict<Credentials>>,
     cookies: &CookieJar,
+    next: Option<&str>,
 ) -> Result<Redirect, Error> {
-    match User::select_by_name(&state.database(), &credentials.user) {
-        Ok(Some(user)) if credentials.matches(&user) => {
-            AuthenticatedUser::<Fail>::login(cookies, &user);
-            Ok(Redirect::to(uri!("/")))
+    let target = match next {
+        Some(next) if is_local_path(next) => Redirect::to(next.to_owned()),
+        Some(_) => {
+            return Err(Error::InvalidValue(String::from(
+                "the redirect target must be a local path",
+            )))
         }
-        Ok(Some(_)) => {
-            // Wrong password!
+        None => Redirect::to(uri!("/")),
+    };
+
+    // Unknown users and wrong passwords are indistinguishable, so usernames could not be enumerated.
+    let database = state.database()?;
+    match User::select_by_name(&database, &credentials.user) {
+        Ok(Some(user)) if credentials.matches(&user) => match user.active {
+            true => {
+                User::record_login(&database, user.identifier)?;
+                AuthenticatedUser::<Fail>::login(cookies, state.cookie_settings(), &user);
+                Ok(target)
+            }
+            // Only revealed to someone knowing the password
+            false => Err(Error::InactiveUser),
+        },
+        Ok(Some(_)) => Err(Error::WrongPassword),
+        Ok(None) => {
+            PasswordHash::mismatch(&credentials.user, &credentials.password);
             Err(Error::WrongPassword)
         }
-        Ok(None) => Err(Error::NotFound),
         Err(err) => Err(err.into()),
     }
 }
 
-#[get("/users/login")]
-pub async fn login_html() -> Template {
-    Template::render("login", context! { version: crate::frontend::VERSION })
+/// Change the password of the logged-in user, which is required after being flagged.
+#[post("/users/password", data = "<change>")]
+pub fn change_password(
+    state: &State<Config>,
+    user: AuthenticatedUser,
+    change: Form<Strict<PasswordChange>>,
+) -> Result<NoContent, Error> {
+    let database = state.database()?;
+    let record = user.record(&database)?;
+    if !record
+        .password_hash
+        .matches(&record.username, &change.password)
+    {
+        return Err(Error::WrongPassword);
+    }
+
+    let password_hash = PasswordHash::new(&record.username, &change.new_password);
+    let changed = database.transaction(false, |database| {
+        let changed = User::change_password(database, user.user, &password_hash)?;
+        if changed {
+            AuditEntry::record_details(
+                database,
+                user.user,
+                User::TABLE_NAME,
+                user.user.raw_index(),
+                AuditEntry::UPDATE,
+                "changed the password",
+            )?;
+        }
+        Ok(changed)
+    })?;
+    match changed {
+        true => Ok(NoContent),
+        false => Err(Error::NotFound),
+    }
+}
+
+/// Replace the password of any user, i.e. one who forgot it. This is restricted to administrators.
+#[post("/users/<id>/reset-password", data = "<reset>")]
+pub fn reset_password(
+    state: &State<Config>,
+    user: AdminUser,
+    id: i64,
+    reset: Form<Strict<PasswordReset>>,
+) -> Result<NoContent, Error> {
+    let database = state.database()?;
+    let identifier = PrimaryKey::from(id);
+    let record = User::select_record(&database, identifier)?.ok_or(Error::NotFound)?;
+
+    let password_hash = PasswordHash::new(&record.username, &reset.new_password);
+    database.transaction(false, |database| {
+        User::change_password(database, identifier, &password_hash)?;
+        User::require_password_change(
+            database,
+            identifier,
+            reset.must_change_password.unwrap_or(false),
+        )?;
+        AuditEntry::record_details(
+            database,
+            user.0.user,
+            User::TABLE_NAME,
+            id,
+            AuditEntry::UPDATE,
+            "reset the password",
+        )
+    })?;
+    Ok(NoContent)
+}
+
+/// Describe the logged-in user and the capabilities derived from their role. Only administrators may create every kind of entry, including users, and delete entries.
+#[get("/users/me")]
+pub fn current_user(
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Json<CurrentUser>, Error> {
+    let database = state.read_only_database()?;
+    let user = User::try_select(&database, user.user.raw_index())?.ok_or(Error::NotFound)?;
+    Ok(Json(CurrentUser {
+        can_create: user.is_admin,
+        can_delete: user.is_admin,
+        user,
+    }))
+}
+
+#[get("/users/login?<next>")]
+pub async fn login_html(next: Option<&str>) -> Template {
+    Template::render(
+        "login",
+        context! { version: crate::frontend::VERSION, next: next.filter(|next| is_local_path(next)) },
+    )
 }
 
 #[get("/users/logout")]
-pub fn logout(cookies: &CookieJar) -> Redirect {
-    AuthenticatedUser::<Forward>::logout(cookies);
+pub fn logout(state: &State<Config>, cookies: &CookieJar) -> Redirect {
+    AuthenticatedUser::<Forward>::logout(cookies, state.cookie_settings());
     Redirect::to(uri!("/"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_local_path, AuthenticatedUser, Fail, Session};
+    use crate::{
+        backend::{
+            database::{Database, DefaultGenerator, Insertable, PrimaryKey},
+            user::User,
+        },
+        Error,
+    };
+
+    #[test]
+    fn test_record() {
+        let database = Database::in_memory().expect("valid database");
+        let user = User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        let authenticated = AuthenticatedUser {
+            user,
+            strategy: Fail,
+            record: std::sync::OnceLock::new(),
+        };
+
+        let username = authenticated
+            .record(&database)
+            .map(|record| record.username.clone())
+            .expect("valid record");
+
+        // The second call is served from the cache, so it does not notice the user being removed.
+        database
+            .connection
+            .execute("DELETE FROM users WHERE id = ?", (user,))
+            .expect("valid deletion");
+        assert_eq!(
+            authenticated
+                .record(&database)
+                .map(|record| record.username.clone()),
+            Ok(username)
+        );
+    }
+
+    #[test]
+    fn test_record_inactive() {
+        let database = Database::in_memory().expect("valid database");
+        let mut user = User::create_default(&database);
+        user.active = false;
+        let authenticated = AuthenticatedUser {
+            user: user.insert(&database).expect("valid user"),
+            strategy: Fail,
+            record: std::sync::OnceLock::new(),
+        };
+
+        assert_eq!(
+            authenticated.record(&database).map(|_| ()),
+            Err(Error::InactiveUser)
+        );
+    }
+
+    #[test]
+    fn test_is_local_path() {
+        assert!(is_local_path("/"));
+        assert!(is_local_path("/persons?limit=5"));
+        assert!(!is_local_path("https://example.com"));
+        assert!(!is_local_path("//example.com"));
+        assert!(!is_local_path("/\\example.com"));
+        assert!(!is_local_path("persons"));
+    }
+
+    #[test]
+    fn test_session_expiry() {
+        let issued_at = chrono::Utc::now();
+        let session = Session {
+            user: PrimaryKey::from(1),
+            issued_at,
+        };
+        let max_age = std::time::Duration::from_secs(60);
+
+        assert!(!session.is_expired(issued_at, max_age));
+        assert!(!session.is_expired(issued_at + chrono::Duration::seconds(60), max_age));
+        assert!(session.is_expired(issued_at + chrono::Duration::seconds(61), max_age));
+        assert!(!session.is_expired(issued_at, std::time::Duration::MAX));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::backend::{
+    accounting::{Account, CostCenter},
+    database::{Database, DatabaseEntry, Error, PrimaryKey},
+};
+
+/// An account which may be booked on a cost center. As long as no pairing is stored, all accounts are allowed everywhere.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct AccountCostCenter {
+    pub account: PrimaryKey<Account>,
+    pub cost_center: PrimaryKey<CostCenter>,
+}
+
+impl DatabaseEntry for AccountCostCenter {
+    type DependsOn = (Account, CostCenter);
+
+    const TABLE_NAME: &'static str = "account_cost_centers";
+    const STATEMENT_CREATE_TABLE: &'static str = std::concat!(
+        "CREATE TABLE IF NOT EXISTS account_cost_centers (
+            account INTEGER NOT NULL, cost_center INTEGER NOT NULL,
+            PRIMARY KEY (account, cost_center),
+            FOREIGN KEY (account) REFERENCES accounts(id),
+            FOREIGN KEY (cost_center) REFERENCES cost_centers(id)
+        )"
+    );
+}
+
+impl AccountCostCenter {
+    /// Allow booking the account on the cost center.
+    pub fn insert(&self, database: &Database) -> Result<(), Error> {
+        database.connection.execute(
+            "INSERT INTO account_cost_centers (account, cost_center) VALUES (?, ?)",
+            (self.account, self.cost_center),
+        )?;
+        Ok(())
+    }
+
+    /// Remove a pairing from the allow-list.
+    pub fn remove(
+        account: PrimaryKey<Account>,
+        cost_center: PrimaryKey<CostCenter>,
+        database: &Database,
+    ) -> Result<usize, Error> {
+        Ok(database.connection.execute(
+            "DELETE FROM account_cost_centers WHERE account = ? AND cost_center = ?",
+            (account, cost_center),
+        )?)
+    }
+
+    /// Check whether the account may be booked on the cost center, which is always the case for an empty allow-list.
+    pub fn is_allowed(
+        database: &Database,
+        account: PrimaryKey<Account>,
+        cost_center: PrimaryKey<CostCenter>,
+    ) -> Result<bool, Error> {
+        const IS_ALLOWED_QUERY: &str = "SELECT NOT EXISTS(SELECT 1 FROM account_cost_centers) OR EXISTS(SELECT 1 FROM account_cost_centers WHERE account = ? AND cost_center = ?)";
+        Ok(database
+            .connection
+            .query_row(IS_ALLOWED_QUERY, (account, cost_center), |row| row.get(0))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AccountCostCenter;
+    use crate::backend::{
+        accounting::{Account, CostCenter},
+        database::{Database, DefaultGenerator, Insertable},
+    };
+
+    #[test]
+    fn test_is_allowed() {
+        let database = Database::in_memory().expect("valid database");
+        let account = Account::create_default(&database)
+            .insert(&database)
+            .expect("valid account");
+        let other_account = Account::create_default(&database)
+            .insert(&database)
+            .expect("valid account");
+        let cost_center = CostCenter::default()
+            .insert(&database)
+            .expect("valid cost center");
+
+        // Without any pairing, everything is allowed.
+        assert_eq!(
+            AccountCostCenter::is_allowed(&database, other_account, cost_center),
+            Ok(true)
+        );
+
+        AccountCostCenter {
+            account,
+            cost_center,
+        }
+        .insert(&database)
+        .expect("valid pairing");
+        assert_eq!(
+            AccountCostCenter::is_allowed(&database, account, cost_center),
+            Ok(true)
+        );
+        assert_eq!(
+            AccountCostCenter::is_allowed(&database, other_account, cost_center),
+            Ok(false)
+        );
+
+        assert_eq!(
+            AccountCostCenter::remove(account, cost_center, &database),
+            Ok(1)
+        );
+        assert_eq!(
+            AccountCostCenter::is_allowed(&database, other_account, cost_center),
+            Ok(true)
+        );
+    }
+}
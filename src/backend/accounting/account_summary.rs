@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -6,30 +8,60 @@ pub struct AccountSummary {
     pub cost_center: String,
     pub category: String,
     pub amount: super::Amount,
+    pub currency: super::Currency,
 }
 
 impl AccountSummary {
-    /// Load the summaries.
+    /// Load the summaries, attributing split entries to the cost centers of their allocations. The sign of each amount follows the kind of the corresponding account.
+    /// Entries in different currencies are summarized separately.
     pub fn load_all(
         database: &crate::backend::database::Database,
     ) -> Result<Vec<Self>, crate::backend::database::Error> {
-        const QUERY: &'static str = r#"
-            SELECT SUM(amount), accounts.description, cost_centers.description, categories.description FROM entries 
+        const QUERY: &'static str = const_format::concatcp!(
+            "SELECT SUM(amount), accounts.description, cost_centers.description, categories.description, accounts.kind, entries.currency FROM (",
+            super::entry_allocation::ALLOCATED_ENTRIES,
+            r#") AS entries
             INNER JOIN cost_centers ON cost_centers.id = cost_center 
             INNER JOIN accounts ON accounts.id = account 
             INNER JOIN categories ON categories.id = accounts.category 
-            GROUP BY account, cost_center ORDER BY cost_center, categories.id, account"#;
+            GROUP BY account, cost_center, entries.currency ORDER BY cost_center, categories.id, account, entries.currency"#
+        );
         let mut stmt = database.connection.prepare(QUERY)?;
         let iterator = stmt.query_map((), |row| {
-            <(super::Amount, String, String, String)>::try_from(row).map(|value| AccountSummary {
+            <(
+                super::Amount,
+                String,
+                String,
+                String,
+                super::AccountKind,
+                super::Currency,
+            )>::try_from(row)
+            .map(|value| AccountSummary {
                 account: value.1,
                 cost_center: value.2,
-                amount: value.0,
+                amount: value.4.apply_sign(value.0),
                 category: value.3,
+                currency: value.5,
             })
         })?;
         Ok(iterator.filter_map(|value| value.ok()).collect())
     }
+
+    /// Calculate the grand totals across all the summaries for each currency, respecting the sign of their account kinds.
+    pub fn net_totals(
+        summaries: &[Self],
+    ) -> Result<BTreeMap<super::Currency, super::Amount>, super::AmountError> {
+        let mut totals = BTreeMap::new();
+        for summary in summaries {
+            let total = totals
+                .entry(summary.currency)
+                .or_insert(super::Amount::ZERO);
+            *total = total
+                .checked_add(summary.amount)
+                .ok_or(super::AmountError::Overflow)?;
+        }
+        Ok(totals)
+    }
 }
 
 #[cfg(test)]
@@ -45,6 +77,7 @@ mod tests {
         let category_name = String::from("Category 1");
         let category_1 = Category {
             description: category_name.clone(),
+            parent: None,
         }
         .insert(&database)
         .expect("insert category failed");
@@ -59,6 +92,7 @@ mod tests {
                 let description = String::from(name);
                 let center = CostCenter {
                     description: description.clone(),
+                    parent: None,
                 }
                 .insert(&database)
                 .expect("insert cost center failed");
@@ -74,6 +108,7 @@ mod tests {
                     code: 1,
                     category: category_1,
                     description: description.clone(),
+                    kind: AccountKind::Asset,
                 }
                 .insert(&database)
                 .expect("insert account failed");
@@ -89,6 +124,8 @@ mod tests {
                 cost_center: cost_center_1,
                 amount: Amount::from(amount),
                 description: String::new(),
+                reference_number: None,
+                currency: Currency::default(),
             }
             .insert(&database)
             .expect("insert entry failed");
@@ -101,6 +138,8 @@ mod tests {
                 cost_center: cost_center_2,
                 amount: Amount::from(amount),
                 description: String::new(),
+                reference_number: None,
+                currency: Currency::default(),
             }
             .insert(&database)
             .expect("insert entry failed");
@@ -114,27 +153,162 @@ mod tests {
                     account: account_1_name.clone(),
                     cost_center: cost_center_1_name.clone(),
                     amount: Amount::from(300),
-                    category: category_name.clone()
+                    category: category_name.clone(),
+                    currency: Currency::Eur
                 },
                 AccountSummary {
                     account: account_2_name.clone(),
                     cost_center: cost_center_1_name.clone(),
                     amount: Amount::from(140),
-                    category: category_name.clone()
+                    category: category_name.clone(),
+                    currency: Currency::Eur
                 },
                 AccountSummary {
                     account: account_1_name.clone(),
                     cost_center: cost_center_2_name.clone(),
                     amount: Amount::from(130),
-                    category: category_name.clone()
+                    category: category_name.clone(),
+                    currency: Currency::Eur
                 },
                 AccountSummary {
                     account: account_2_name.clone(),
                     cost_center: cost_center_2_name.clone(),
                     amount: Amount::from(300),
-                    category: category_name.clone()
+                    category: category_name.clone(),
+                    currency: Currency::Eur
                 }
             ]
         );
     }
+
+    #[test]
+    fn test_loading_sign_convention() {
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+
+        let category = Category::default()
+            .insert(&database)
+            .expect("insert category failed");
+        let cost_center = CostCenter::default()
+            .insert(&database)
+            .expect("insert cost center failed");
+        let evidence = crate::backend::document::Document::create_default(&database)
+            .insert(&database)
+            .expect("inserting document failed");
+
+        // Both accounts recieve the very same raw amounts.
+        for (description, kind) in [
+            ("Bank", AccountKind::Asset),
+            ("Loan", AccountKind::Liability),
+        ] {
+            let account = Account {
+                code: 1,
+                category,
+                description: String::from(description),
+                kind,
+            }
+            .insert(&database)
+            .expect("insert account failed");
+
+            for amount in [100, 20] {
+                Entry {
+                    evidence,
+                    account,
+                    cost_center,
+                    amount: Amount::from(amount),
+                    description: String::new(),
+                    reference_number: None,
+                    currency: Currency::default(),
+                }
+                .insert(&database)
+                .expect("insert entry failed");
+            }
+        }
+
+        let summaries = AccountSummary::load_all(&database).expect("loading summary failed");
+        let amounts: Vec<_> = summaries
+            .iter()
+            .map(|summary| (summary.account.as_str(), summary.amount))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![("Bank", Amount::from(120)), ("Loan", Amount::from(-120))]
+        );
+
+        // The liability is owed on the asset, so nothing is left in total.
+        assert_eq!(
+            AccountSummary::net_totals(&summaries)
+                .expect("valid totals")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![(Currency::Eur, Amount::from(0))]
+        );
+    }
+
+    #[test]
+    fn test_loading_currencies() {
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        let entry = Entry::create_default(&database);
+        for (amount, currency) in [
+            (100, Currency::Eur),
+            (40, Currency::Usd),
+            (20, Currency::Eur),
+        ] {
+            Entry {
+                amount: Amount::from(amount),
+                currency,
+                ..entry.clone()
+            }
+            .insert(&database)
+            .expect("insert entry failed");
+        }
+
+        let summaries = AccountSummary::load_all(&database).expect("loading summary failed");
+        let amounts: Vec<_> = summaries
+            .iter()
+            .map(|summary| (summary.currency, summary.amount))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                (Currency::Eur, Amount::from(120)),
+                (Currency::Usd, Amount::from(40))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_net_totals() {
+        let summaries = [
+            (300, "Bank", Currency::Eur),
+            (-120, "Loan", Currency::Eur),
+            (45, "Cash", Currency::Chf),
+        ]
+        .map(|(amount, account, currency)| AccountSummary {
+            account: String::from(account),
+            cost_center: String::from("Cost Center"),
+            category: String::from("Category"),
+            amount: Amount::from(amount),
+            currency,
+        });
+
+        assert_eq!(
+            AccountSummary::net_totals(&summaries)
+                .expect("valid totals")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![
+                (Currency::Eur, Amount::from(180)),
+                (Currency::Chf, Amount::from(45))
+            ]
+        );
+        assert_eq!(AccountSummary::net_totals(&[]), Ok(Default::default()));
+
+        // Totals too large to be stored are reported instead of wrapping around.
+        let mut large = summaries[0].clone();
+        large.amount = Amount::from(i64::MAX / 100);
+        assert_eq!(
+            AccountSummary::net_totals(&[large.clone(), large]),
+            Err(AmountError::Overflow)
+        );
+    }
 }
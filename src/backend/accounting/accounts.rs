@@ -1,5 +1,5 @@
 use crate::backend::{
-    accounting::Category,
+    accounting::{Amount, Category},
     database::{DefaultGenerator, Insertable, PrimaryKey},
 };
 
@@ -7,11 +7,14 @@ crate::backend::database::make_struct!(
     #[derive(serde::Serialize, serde::Deserialize)]
     #[table("accounts")]
     #[dependencies(Category)]
-    #[impl_select(true, testing: true, description: "description")]
+    #[impl_select(true, testing: true, description: "description", archivable: true, timestamps: true)]
     Account {
+        #[serde(deserialize_with = "deserialize_code")]
         code: u32,
         category: PrimaryKey<Category>,
-        description: String
+        description: String,
+        #[serde(default)]
+        kind: AccountKind
     }
 );
 
@@ -24,6 +27,155 @@ impl DefaultGenerator for Account {
             code: 1800,
             category,
             description: String::from("Example account"),
+            kind: AccountKind::default(),
         }
     }
 }
+
+/// Parse an account code, which may contain grouping separators like "4.200".
+pub fn parse_code(value: &str) -> Option<u32> {
+    let mut groups = value.trim().split(['.', ',', ' ', '\'']);
+    let first = groups.next().filter(|group| !group.is_empty())?;
+    let mut digits = String::from(first);
+    for group in groups {
+        // Separators only group thousands and are never used for decimals.
+        if first.len() > 3 || group.len() != 3 {
+            return None;
+        }
+        digits.push_str(group);
+    }
+
+    match digits.bytes().all(|digit| digit.is_ascii_digit()) {
+        true => digits.parse().ok(),
+        false => None,
+    }
+}
+
+/// Accept the account code either as a number or as a string with grouping separators.
+fn deserialize_code<'de, D>(deserializer: D) -> Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum RawCode {
+        Number(u32),
+        Text(String),
+    }
+
+    match <RawCode as serde::Deserialize>::deserialize(deserializer)? {
+        RawCode::Number(code) => Ok(code),
+        RawCode::Text(code) => parse_code(&code).ok_or_else(|| {
+            serde::de::Error::invalid_value(serde::de::Unexpected::Str(&code), &"an account code")
+        }),
+    }
+}
+
+/// The kind of an account, which determines whether a positive amount increases or decreases its balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccountKind {
+    /// Positive amounts increase the balance.
+    #[default]
+    Asset,
+    /// Positive amounts decrease the balance.
+    Liability,
+}
+
+impl AccountKind {
+    /// Apply the sign convention of this kind on a raw amount.
+    pub fn apply_sign(&self, amount: Amount) -> Amount {
+        match self {
+            AccountKind::Asset => amount,
+            AccountKind::Liability => -amount,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            AccountKind::Asset => "asset",
+            AccountKind::Liability => "liability",
+        }
+    }
+}
+
+impl std::fmt::Display for AccountKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl rusqlite::ToSql for AccountKind {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(rusqlite::types::ToSqlOutput::Borrowed(
+            rusqlite::types::ValueRef::Text(self.as_str().as_bytes()),
+        ))
+    }
+}
+
+impl rusqlite::types::FromSql for AccountKind {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value.as_str()? {
+            "asset" => Ok(AccountKind::Asset),
+            "liability" => Ok(AccountKind::Liability),
+            _ => Err(rusqlite::types::FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl crate::backend::database::DatabaseType for AccountKind {
+    const RAW_COLUMN_VALUE: &'static str = "TEXT";
+    const COLUMN_VALUE: &'static str = "TEXT NOT NULL";
+    const IS_SORTABLE: bool = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_code, Account, AccountKind, Amount};
+
+    #[test]
+    fn test_apply_sign() {
+        assert_eq!(
+            AccountKind::Asset.apply_sign(Amount::from(42)),
+            Amount::from(42)
+        );
+        assert_eq!(
+            AccountKind::Liability.apply_sign(Amount::from(42)),
+            Amount::from(-42)
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        assert_eq!(
+            serde_json::to_string(&AccountKind::Liability).expect("valid json"),
+            r#""liability""#
+        );
+    }
+
+    #[test]
+    fn test_parse_code() {
+        assert_eq!(parse_code("4200"), Some(4200));
+        assert_eq!(parse_code("4.200"), Some(4200));
+        assert_eq!(parse_code("1,000.000"), Some(1000000));
+        for invalid in ["", "42.00", "4200.000", "4.2a0", "-4200", ".200"] {
+            assert_eq!(parse_code(invalid), None, "{} should be invalid", invalid);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_code() {
+        for code in [r#"4200"#, r#""4200""#, r#""4.200""#] {
+            let account: Account = serde_json::from_str(&format!(
+                r#"{{"code": {}, "category": "/categories/1", "description": ""}}"#,
+                code
+            ))
+            .expect("valid account");
+            assert_eq!(account.code, 4200);
+        }
+        assert!(serde_json::from_str::<Account>(
+            r#"{"code": "42.00", "category": "/categories/1", "description": ""}"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use rusqlite::OptionalExtension;
+
+use crate::backend::{
+    accounting::{Amount, CostCenter, Currency},
+    database::{Database, DefaultGenerator, Error, Insertable, PrimaryKey},
+};
+
+crate::backend::database::make_struct!(
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[table("budgets")]
+    #[dependencies(CostCenter)]
+    #[impl_select(true, testing: true, timestamps: true)]
+    Budget {
+        cost_center: PrimaryKey<CostCenter>,
+        year: u32,
+        target: Amount,
+        #[serde(default)]
+        currency: Currency
+    } ("FOREIGN KEY(cost_center) REFERENCES cost_centers(id), UNIQUE(cost_center, year)")
+);
+
+impl Budget {
+    /// Compare the budget of a cost center in a year with the sum of its entries, using the date the evidence was processed.
+    /// Only entries in the currency of the budget count. Returns `None` if no budget was planned for that year.
+    pub fn compare(
+        database: &Database,
+        cost_center: PrimaryKey<CostCenter>,
+        year: u32,
+    ) -> Result<Option<BudgetComparison>, Error> {
+        const SELECT_TARGET_QUERY: &str =
+            "SELECT target, currency FROM budgets WHERE cost_center = ? AND year = ?";
+        const ACTUAL_QUERY: &str = const_format::concatcp!(
+            "SELECT COALESCE(SUM(entries.amount), 0) FROM (",
+            super::entry_allocation::ALLOCATED_ENTRIES,
+            r#") AS entries
+            INNER JOIN documents ON documents.id = entries.evidence
+            WHERE entries.cost_center = ? AND strftime('%Y', documents.processed) = ? AND entries.currency = ?"#
+        );
+
+        let (target, currency) = match database
+            .connection
+            .query_row(SELECT_TARGET_QUERY, (cost_center, year), |row| {
+                <(Amount, Currency)>::try_from(row)
+            })
+            .optional()?
+        {
+            Some(budget) => budget,
+            None => return Ok(None),
+        };
+        let actual: Amount = database.connection.query_row(
+            ACTUAL_QUERY,
+            (cost_center, format!("{:04}", year), currency),
+            |row| row.get(0),
+        )?;
+
+        Ok(Some(BudgetComparison {
+            cost_center,
+            year,
+            currency,
+            target,
+            actual,
+            variance: target - actual,
+            utilization: Percentage::of(actual, target),
+        }))
+    }
+}
+
+impl DefaultGenerator for Budget {
+    fn create_default(database: &Database) -> Self {
+        let cost_center = CostCenter::default()
+            .insert(database)
+            .expect("valid cost center");
+
+        Budget {
+            cost_center,
+            year: 2024,
+            target: 1000i64.into(),
+            currency: Currency::default(),
+        }
+    }
+}
+
+/// The planned budget of a cost center compared to the amount actually spent.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct BudgetComparison {
+    pub cost_center: PrimaryKey<CostCenter>,
+    pub year: u32,
+    pub currency: Currency,
+    pub target: Amount,
+    pub actual: Amount,
+    /// The part of the budget which is left, i.e. negative if the budget was exceeded.
+    pub variance: Amount,
+    /// The part of the budget which was spent. Not available if the budget is zero.
+    pub utilization: Option<Percentage>,
+}
+
+/// A percentage with two digits after the comma. Like [Amount], it is stored as an integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Percentage(i64);
+
+impl Percentage {
+    /// Calculate how much of the whole the part is, rounded to two digits after the comma.
+    pub fn of(part: Amount, whole: Amount) -> Option<Self> {
+        let (part, whole) = (part.0 as i128 * 10000, whole.0 as i128);
+        if whole == 0 {
+            return None;
+        }
+
+        let (quotient, remainder) = (part / whole, part % whole);
+        let rounding = match 2 * remainder.abs() >= whole.abs() {
+            true => part.signum() * whole.signum(),
+            false => 0,
+        };
+        i64::try_from(quotient + rounding).ok().map(Percentage)
+    }
+}
+
+impl std::fmt::Display for Percentage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        write!(
+            f,
+            "{}{}.{:0>2}%",
+            sign,
+            self.0.abs() / 100,
+            self.0.abs() % 100
+        )
+    }
+}
+
+impl serde::Serialize for Percentage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Budget, Percentage};
+    use crate::backend::{
+        accounting::{Amount, CostCenter, Currency, Entry},
+        database::{Database, DefaultGenerator, Insertable},
+        document::Document,
+        util::Date,
+    };
+
+    #[test]
+    fn test_percentage() {
+        assert_eq!(
+            Percentage::of(Amount(750), Amount(1000)).map(|value| value.to_string()),
+            Some(String::from("75.00%"))
+        );
+        assert_eq!(
+            Percentage::of(Amount(1), Amount(3)).map(|value| value.to_string()),
+            Some(String::from("33.33%"))
+        );
+        assert_eq!(
+            Percentage::of(Amount(-2), Amount(3)).map(|value| value.to_string()),
+            Some(String::from("-66.67%"))
+        );
+        assert_eq!(Percentage::of(Amount(1), Amount(0)), None);
+    }
+
+    #[test]
+    fn test_budget_vs_actual() {
+        let database = Database::in_memory().expect("valid database");
+        let cost_center = CostCenter::default()
+            .insert(&database)
+            .expect("valid cost center");
+        Budget {
+            cost_center,
+            year: 2024,
+            target: 1000i64.into(),
+            currency: Currency::Eur,
+        }
+        .insert(&database)
+        .expect("valid budget");
+
+        for (date, amount, currency) in [
+            ("2024-01-15", Amount(50000), Currency::Eur),
+            ("2024-06-20", Amount(30050), Currency::Eur),
+            ("2023-02-03", Amount(9999), Currency::Eur),
+            // Entries in other currencies must not count.
+            ("2024-03-01", Amount(70000), Currency::Usd),
+        ] {
+            let mut evidence = Document::create_default(&database);
+            evidence.processed = Date::try_from(date).expect("valid date");
+
+            let mut entry = Entry::create_default(&database);
+            entry.evidence = evidence.insert(&database).expect("valid evidence");
+            entry.cost_center = cost_center;
+            entry.amount = amount;
+            entry.currency = currency;
+            entry.insert(&database).expect("valid entry");
+        }
+        // Entries of other cost centers must not count.
+        Entry::create_default(&database)
+            .insert(&database)
+            .expect("valid entry");
+
+        let comparison = Budget::compare(&database, cost_center, 2024)
+            .expect("valid query")
+            .expect("budget exists");
+        assert_eq!(comparison.currency, Currency::Eur);
+        assert_eq!(comparison.actual, Amount(80050));
+        assert_eq!(comparison.variance, Amount(19950));
+        assert_eq!(
+            comparison.utilization.map(|value| value.to_string()),
+            Some(String::from("80.05%"))
+        );
+
+        assert_eq!(
+            Budget::compare(&database, cost_center, 2023).expect("valid query"),
+            None
+        );
+    }
+}
@@ -1,9 +1,13 @@
+use crate::backend::database::PrimaryKey;
+
 crate::backend::database::make_struct!(
     #[derive(Default, serde::Serialize, serde::Deserialize)]
     #[table("categories")]
     #[dependencies(())]
-    #[impl_select(true, testing: true, description: "description")]
+    #[impl_select(true, testing: true, description: "description", timestamps: true)]
     Category {
-        description: String
-    }
+        description: String,
+        #[doc = "The entry this one is nested below, if any."]
+        parent: Option<PrimaryKey<Category>>
+    } ("FOREIGN KEY(parent) REFERENCES categories(id)")
 );
@@ -0,0 +1,83 @@
+/// The currency of an entry. Amounts of different currencies are never added up.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Eur,
+    Usd,
+    Gbp,
+    Chf,
+}
+
+impl Currency {
+    /// The ISO 4217 code of the currency.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Currency::Eur => "EUR",
+            Currency::Usd => "USD",
+            Currency::Gbp => "GBP",
+            Currency::Chf => "CHF",
+        }
+    }
+}
+
+impl std::fmt::Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl rusqlite::ToSql for Currency {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(rusqlite::types::ToSqlOutput::Borrowed(
+            rusqlite::types::ValueRef::Text(self.as_str().as_bytes()),
+        ))
+    }
+}
+
+impl rusqlite::types::FromSql for Currency {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        match value.as_str()? {
+            "EUR" => Ok(Currency::Eur),
+            "USD" => Ok(Currency::Usd),
+            "GBP" => Ok(Currency::Gbp),
+            "CHF" => Ok(Currency::Chf),
+            _ => Err(rusqlite::types::FromSqlError::InvalidType),
+        }
+    }
+}
+
+impl crate::backend::database::DatabaseType for Currency {
+    const RAW_COLUMN_VALUE: &'static str = "TEXT";
+    const COLUMN_VALUE: &'static str = "TEXT NOT NULL";
+    const IS_SORTABLE: bool = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Currency;
+
+    #[test]
+    fn test_serde() {
+        assert_eq!(
+            serde_json::to_string(&Currency::Chf).expect("valid json"),
+            "\"CHF\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Currency>("\"USD\"").expect("valid currency"),
+            Currency::Usd
+        );
+        assert!(serde_json::from_str::<Currency>("\"JPY\"").is_err());
+    }
+}
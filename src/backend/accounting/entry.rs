@@ -1,8 +1,8 @@
 use std::{path::Display, str::FromStr};
 
 use crate::backend::{
-    accounting::{Account, CostCenter},
-    database::{DefaultGenerator, Insertable, PrimaryKey},
+    accounting::{Account, CostCenter, Currency},
+    database::{Database, DefaultGenerator, Error, Insertable, PrimaryKey, Record, Selectable},
     document::Document,
     user::User,
     util::Date,
@@ -12,16 +12,63 @@ crate::backend::database::make_struct!(
     #[derive(serde::Serialize, serde::Deserialize)]
     #[table("entries")]
     #[dependencies((Document, Account, CostCenter))]
-    #[impl_select(true, testing: true)]
+    #[impl_select(true, testing: true, timestamps: true)]
     Entry {
         evidence: PrimaryKey<Document>,
         account: PrimaryKey<Account>,
         cost_center: PrimaryKey<CostCenter>,
         amount: Amount,
-        description: String
+        description: String,
+        reference_number: Option<String>,
+        #[serde(default)]
+        currency: Currency
     }
 );
 
+impl Entry {
+    /// Find all entries with the given reference number, i.e. for reconciling them with a bank statement.
+    pub fn find_by_reference(
+        database: &Database,
+        reference_number: impl AsRef<str>,
+    ) -> Result<Vec<Record<Self>>, Error> {
+        const SELECT_BY_REFERENCE_QUERY: &str = const_format::concatcp!(
+            <Entry as Selectable>::STATEMENT_SELECT_ALL,
+            " WHERE reference_number = ?"
+        );
+
+        let mut stmt = database.connection.prepare(SELECT_BY_REFERENCE_QUERY)?;
+        let iterator = stmt.query_map((reference_number.as_ref(),), |row| {
+            <Self as Selectable>::SelectValue::try_from(row).map(Self::deserialize_sql)
+        })?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Sum up the entries of a cost center for each month of a year, using the date the evidence was processed.
+    /// Entries in different currencies are summed up separately. Months without any entry are omitted.
+    pub fn monthly_totals(
+        database: &Database,
+        cost_center: PrimaryKey<CostCenter>,
+        year: i32,
+    ) -> Result<Vec<(u32, Currency, Amount)>, Error> {
+        const MONTHLY_TOTALS_QUERY: &str = const_format::concatcp!(
+            "SELECT CAST(strftime('%m', documents.processed) AS INTEGER) AS month, entries.currency, SUM(entries.amount) FROM (",
+            super::entry_allocation::ALLOCATED_ENTRIES,
+            r#") AS entries
+            INNER JOIN documents ON documents.id = entries.evidence
+            WHERE entries.cost_center = ? AND strftime('%Y', documents.processed) = ?
+            GROUP BY month, entries.currency ORDER BY month, entries.currency"#
+        );
+
+        let mut stmt = database.connection.prepare(MONTHLY_TOTALS_QUERY)?;
+        let iterator = stmt.query_map((cost_center, format!("{:04}", year)), |row| {
+            <(u32, Currency, Amount)>::try_from(row)
+        })?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+}
+
 impl DefaultGenerator for Entry {
     fn create_default(database: &crate::backend::database::Database) -> Self {
         let evidence = Document::create_default(&database)
@@ -40,20 +87,46 @@ impl DefaultGenerator for Entry {
             cost_center,
             amount: 32i64.into(),
             description: String::new(),
+            reference_number: None,
+            currency: Currency::default(),
         }
     }
 }
 
 /// A amount of money with two digits after the comma. This type will never have floating point issues.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Amount(i64);
+pub struct Amount(pub(super) i64);
 
 impl Amount {
+    /// The amount of nothing, i.e. the start of a sum.
+    pub const ZERO: Amount = Amount(0);
+
+    /// Create an amount from its integer part and its cents, i.e. `Amount::new(1, 5)` is 1.05.
+    /// The cents share the sign of the integer part, i.e. `Amount::new(-5, 30)` is -5.30.
     pub fn new(integer_part: i64, fractional_part: i64) -> Result<Self, AmountError> {
         if !(0..100).contains(&fractional_part) {
             return Err(AmountError::FractionTooLarge);
         }
-        Ok(Amount(integer_part * 100 + fractional_part))
+        let cents = integer_part.checked_mul(100);
+        match integer_part < 0 {
+            true => cents.and_then(|cents| cents.checked_sub(fractional_part)),
+            false => cents.and_then(|cents| cents.checked_add(fractional_part)),
+        }
+        .map(Amount)
+        .ok_or(AmountError::Overflow)
+    }
+
+    /// Add two amounts, returning `None` instead of overflowing.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Amount)
+    }
+
+    /// Sum up the amounts, with the sum of nothing being zero. Unlike adding two amounts, an overflow is reported instead of being silently wrapped.
+    pub fn checked_sum(amounts: impl IntoIterator<Item = Self>) -> Result<Self, AmountError> {
+        amounts
+            .into_iter()
+            .try_fold(Amount::ZERO, |total, amount| total.checked_add(amount))
+            .ok_or(AmountError::Overflow)
     }
 }
 
@@ -73,6 +146,14 @@ impl std::ops::Sub for Amount {
     }
 }
 
+impl std::ops::Neg for Amount {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Amount(-self.0)
+    }
+}
+
 impl From<i64> for Amount {
     fn from(value: i64) -> Self {
         Amount(value * 100)
@@ -111,7 +192,9 @@ impl serde::Serialize for Amount {
 
 impl std::fmt::Display for Amount {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}.{:0>2}", self.0 / 100, self.0 % 100)
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{}{}.{:0>2}", sign, cents / 100, cents % 100)
     }
 }
 
@@ -119,6 +202,11 @@ impl std::str::FromStr for Amount {
     type Err = AmountError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The sign is handled separately, as it would be lost for amounts like "-0.50" otherwise.
+        let (is_negative, s) = match s.strip_prefix('-') {
+            Some(value) => (true, value),
+            None => (false, s),
+        };
         let components: Vec<_> = s.split(|c| c == ',' || c == '.').collect();
         if components.len() > 2 {
             return Err(AmountError::DecimalSeparatorIncluded);
@@ -128,14 +216,27 @@ impl std::str::FromStr for Amount {
             .first()
             .ok_or_else(|| panic!("At least one value should be there"))
             .and_then(|value| i64::from_str(*value))?;
+        if is_negative && components[0].starts_with(['+', '-']) {
+            return Err(AmountError::InvalidNumber);
+        }
 
-        Ok(match components.get(1) {
+        let amount = match components.get(1) {
             Some(value) => {
-                let fractional_part = i64::from_str(*value)?;
+                if !value.bytes().all(|digit| digit.is_ascii_digit()) {
+                    return Err(AmountError::InvalidNumber);
+                }
+
+                // The fraction is left-aligned, i.e. "1.5" means 1.50 and not 1.05.
+                let fractional_part = match value.len() {
+                    1 => i64::from_str(value)? * 10,
+                    2 => i64::from_str(value)?,
+                    _ => return Err(AmountError::FractionTooLarge),
+                };
                 Amount::new(integer_part, fractional_part)?
             }
-            None => integer_part.into(),
-        })
+            None => Amount::new(integer_part, 0)?,
+        };
+        Ok(if is_negative { -amount } else { amount })
     }
 }
 
@@ -151,6 +252,8 @@ pub enum AmountError {
     FractionTooLarge,
     DecimalSeparatorIncluded,
     InvalidNumber,
+    /// The amount exceeds the range which could be stored.
+    Overflow,
 }
 
 impl std::fmt::Display for AmountError {
@@ -161,6 +264,7 @@ impl std::fmt::Display for AmountError {
                 "There are more than two seperated blocks in the value"
             }
             AmountError::InvalidNumber => "The provided numbers are invalid",
+            AmountError::Overflow => "The amount is too large",
         })
     }
 }
@@ -241,6 +345,12 @@ mod tests {
         assert_eq!(Amount::new(123, 50), Ok(Amount(12350)));
     }
 
+    #[test]
+    fn test_amount_new_negative() {
+        assert_eq!(Amount::new(-5, 30), Ok(Amount(-530)));
+        assert_eq!(Amount::new(-5, 0), Ok(Amount(-500)));
+    }
+
     #[test]
     fn test_amount_new_empty_fraction() {
         assert_eq!(Amount::new(123, 0), Ok(Amount(12300)));
@@ -251,10 +361,29 @@ mod tests {
         assert_eq!(Amount::new(123, 100), Err(AmountError::FractionTooLarge));
     }
 
+    #[test]
+    fn test_amount_new_overflow() {
+        assert_eq!(Amount::new(i64::MAX / 100, 7), Ok(Amount(i64::MAX)));
+        assert_eq!(Amount::new(i64::MAX / 100, 8), Err(AmountError::Overflow));
+        assert_eq!(Amount::new(i64::MAX, 0), Err(AmountError::Overflow));
+        assert_eq!(Amount::new(i64::MIN / 100, 8), Ok(Amount(i64::MIN)));
+        assert_eq!(Amount::new(i64::MIN / 100, 9), Err(AmountError::Overflow));
+        assert_eq!(
+            "92233720368547758.08".parse::<Amount>(),
+            Err(AmountError::Overflow)
+        );
+        assert_eq!(
+            "1000000000000000000".parse::<Amount>(),
+            Err(AmountError::Overflow)
+        );
+    }
+
     #[test]
     fn test_amount_display() {
         assert_eq!(Amount::new(123, 45).unwrap().to_string(), "123.45");
         assert_eq!(Amount::from(123).to_string(), "123.00");
+        assert_eq!(Amount(-530).to_string(), "-5.30");
+        assert_eq!(Amount(-5).to_string(), "-0.05");
     }
 
     #[test]
@@ -264,6 +393,30 @@ mod tests {
         assert_eq!("123".parse::<Amount>(), Ok(Amount(12300)));
     }
 
+    #[test]
+    fn test_amount_from_str_negative() {
+        assert_eq!("-5.30".parse::<Amount>(), Ok(Amount(-530)));
+        assert_eq!("-0.50".parse::<Amount>(), Ok(Amount(-50)));
+        assert_eq!("-0.05".parse::<Amount>(), Ok(Amount(-5)));
+        assert_eq!("-0,5".parse::<Amount>(), Ok(Amount(-50)));
+        assert_eq!("-12".parse::<Amount>(), Ok(Amount(-1200)));
+        assert_eq!("--5.30".parse::<Amount>(), Err(AmountError::InvalidNumber));
+        assert_eq!("-+5.30".parse::<Amount>(), Err(AmountError::InvalidNumber));
+        assert_eq!(
+            "-0.50".parse::<Amount>().map(|amount| amount.to_string()),
+            Ok(String::from("-0.50"))
+        );
+    }
+
+    #[test]
+    fn test_amount_from_str_fraction_alignment() {
+        assert_eq!("1.5".parse::<Amount>(), Ok(Amount(150)));
+        assert_eq!("1,5".parse::<Amount>(), Ok(Amount(150)));
+        assert_eq!("1.05".parse::<Amount>(), Ok(Amount(105)));
+        assert_eq!("1.50".parse::<Amount>(), Ok(Amount(150)));
+        assert_eq!("1.+5".parse::<Amount>(), Err(AmountError::InvalidNumber));
+    }
+
     #[test]
     fn test_amount_from_str_invalid() {
         assert_eq!(
@@ -293,11 +446,32 @@ mod tests {
         assert_eq!(Amount(100) + Amount(200), Amount(300));
     }
 
+    #[test]
+    fn test_amount_sum() {
+        let amounts = vec![Amount(100), Amount(-250), Amount(1205)];
+        assert_eq!(Amount::checked_sum(amounts), Ok(Amount(1055)));
+        assert_eq!(Amount::checked_sum(Vec::new()), Ok(Amount::ZERO));
+    }
+
+    #[test]
+    fn test_amount_sum_overflow() {
+        assert_eq!(
+            Amount::checked_sum([Amount(i64::MAX), Amount(1)]),
+            Err(AmountError::Overflow)
+        );
+        assert_eq!(Amount(i64::MIN).checked_add(Amount(-1)), None);
+    }
+
     #[test]
     fn test_amount_sub() {
         assert_eq!(Amount(300) - Amount(100), Amount(200));
     }
 
+    #[test]
+    fn test_amount_neg() {
+        assert_eq!(-Amount(300), Amount(-300));
+    }
+
     #[test]
     fn test_amount_serialize() {
         assert_eq!(
@@ -344,4 +518,64 @@ mod tests {
     fn test_amount_deserialize_invalid() {
         assert!(serde_json::from_str::<Amount>(r#""123.456""#).is_err());
     }
+
+    #[test]
+    fn test_find_by_reference() {
+        let database = Database::in_memory().expect("valid database");
+
+        let mut entry = Entry::create_default(&database);
+        entry.reference_number = Some(String::from("RF18 5390 0754 7034"));
+        let entry_id = entry.insert(&database).expect("valid insert");
+        Entry::create_default(&database)
+            .insert(&database)
+            .expect("valid insert");
+
+        let found =
+            Entry::find_by_reference(&database, "RF18 5390 0754 7034").expect("valid query");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].identifier, entry_id);
+        assert!(Entry::find_by_reference(&database, "unknown")
+            .expect("valid query")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_monthly_totals() {
+        let database = Database::in_memory().expect("valid database");
+        let cost_center = CostCenter::default()
+            .insert(&database)
+            .expect("valid cost center");
+
+        for (date, amount, currency) in [
+            ("2024-01-15", Amount(1050), Currency::Eur),
+            ("2024-01-20", Amount(250), Currency::Eur),
+            ("2024-01-21", Amount(700), Currency::Usd),
+            ("2024-02-03", Amount(-100), Currency::Eur),
+            ("2023-02-03", Amount(9999), Currency::Eur),
+        ] {
+            let mut evidence = Document::create_default(&database);
+            evidence.processed = Date::try_from(date).expect("valid date");
+
+            let mut entry = Entry::create_default(&database);
+            entry.evidence = evidence.insert(&database).expect("valid evidence");
+            entry.cost_center = cost_center;
+            entry.amount = amount;
+            entry.currency = currency;
+            entry.insert(&database).expect("valid entry");
+        }
+
+        // Entries of other cost centers are ignored.
+        Entry::create_default(&database)
+            .insert(&database)
+            .expect("valid entry");
+
+        assert_eq!(
+            Entry::monthly_totals(&database, cost_center, 2024).expect("valid query"),
+            vec![
+                (1, Currency::Eur, Amount(1300)),
+                (1, Currency::Usd, Amount(700)),
+                (2, Currency::Eur, Amount(-100))
+            ]
+        );
+    }
 }
@@ -0,0 +1,181 @@
+use rusqlite::OptionalExtension;
+
+use crate::backend::{
+    accounting::{AccountCostCenter, Amount, CostCenter, Entry},
+    database::{Database, DatabaseEntry, Error, PrimaryKey},
+};
+
+/// The entries with their amounts attributed to cost centers. Entries without allocations are booked completely on their own cost center.
+pub(super) const ALLOCATED_ENTRIES: &str = r#"
+    SELECT entries.id, entries.evidence, entries.account,
+        COALESCE(entry_allocations.cost_center, entries.cost_center) AS cost_center,
+        COALESCE(entry_allocations.amount, entries.amount) AS amount, entries.currency
+    FROM entries LEFT JOIN entry_allocations ON entry_allocations.entry = entries.id"#;
+
+/// The part of an entry which is attributed to a cost center.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EntryAllocation {
+    pub cost_center: PrimaryKey<CostCenter>,
+    pub amount: Amount,
+}
+
+/// The result of allocating an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationOutcome {
+    /// The allocations were stored.
+    Allocated,
+    /// The allocations do not sum up to the amount of the entry, which is given.
+    SumMismatch(Amount),
+    /// The account of the entry is not allowed for the given cost center.
+    NotAllowed(PrimaryKey<CostCenter>),
+}
+
+impl DatabaseEntry for EntryAllocation {
+    type DependsOn = (Entry, CostCenter);
+
+    const TABLE_NAME: &'static str = "entry_allocations";
+    const STATEMENT_CREATE_TABLE: &'static str = std::concat!(
+        "CREATE TABLE IF NOT EXISTS entry_allocations (
+            entry INTEGER NOT NULL, cost_center INTEGER NOT NULL, amount INTEGER NOT NULL,
+            PRIMARY KEY (entry, cost_center),
+            FOREIGN KEY (entry) REFERENCES entries(id),
+            FOREIGN KEY (cost_center) REFERENCES cost_centers(id)
+        )"
+    );
+}
+
+impl EntryAllocation {
+    /// Load the allocations of an entry, which is empty if the entry is not split.
+    pub fn load(database: &Database, entry: PrimaryKey<Entry>) -> Result<Vec<Self>, Error> {
+        let mut stmt = database.connection.prepare(
+            "SELECT cost_center, amount FROM entry_allocations WHERE entry = ? ORDER BY cost_center",
+        )?;
+        let iterator = stmt.query_map((entry,), |row| {
+            Ok(EntryAllocation {
+                cost_center: row.get(0)?,
+                amount: row.get(1)?,
+            })
+        })?;
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Replace the allocations of an entry. The allocations must sum up to the amount of the entry, while an empty list removes the split.
+    /// Returns `None` if the entry does not exist.
+    pub fn replace(
+        database: &Database,
+        entry: PrimaryKey<Entry>,
+        allocations: &[Self],
+    ) -> Result<Option<AllocationOutcome>, Error> {
+        const SELECT_ENTRY_QUERY: &str = "SELECT account, amount FROM entries WHERE id = ?";
+
+        database.transaction(false, |database| {
+            let (account, amount): (_, Amount) = match database
+                .connection
+                .query_row(SELECT_ENTRY_QUERY, (entry,), |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })
+                .optional()?
+            {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+
+            if !allocations.is_empty() {
+                // A sum too large to be stored could never match the amount either.
+                let sum =
+                    Amount::checked_sum(allocations.iter().map(|allocation| allocation.amount));
+                if sum != Ok(amount) {
+                    return Ok(Some(AllocationOutcome::SumMismatch(amount)));
+                }
+                for allocation in allocations {
+                    if !AccountCostCenter::is_allowed(database, account, allocation.cost_center)? {
+                        return Ok(Some(AllocationOutcome::NotAllowed(allocation.cost_center)));
+                    }
+                }
+            }
+
+            database
+                .connection
+                .execute("DELETE FROM entry_allocations WHERE entry = ?", (entry,))?;
+            for allocation in allocations {
+                database.connection.execute(
+                    "INSERT INTO entry_allocations (entry, cost_center, amount) VALUES (?, ?, ?)",
+                    (entry, allocation.cost_center, allocation.amount),
+                )?;
+            }
+            Ok(Some(AllocationOutcome::Allocated))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AllocationOutcome, EntryAllocation};
+    use crate::backend::{
+        accounting::{AccountSummary, Amount, CostCenter, Entry},
+        database::{Database, DefaultGenerator, Insertable, PrimaryKey},
+    };
+
+    #[test]
+    fn test_replace() {
+        let database = Database::in_memory().expect("valid database");
+        let mut entry = Entry::create_default(&database);
+        entry.amount = Amount(10000);
+        let entry_id = entry.clone().insert(&database).expect("valid entry");
+        let other_cost_center = CostCenter {
+            description: String::from("Other"),
+            parent: None,
+        }
+        .insert(&database)
+        .expect("valid cost center");
+
+        let allocations = vec![
+            EntryAllocation {
+                cost_center: entry.cost_center,
+                amount: Amount(6000),
+            },
+            EntryAllocation {
+                cost_center: other_cost_center,
+                amount: Amount(4000),
+            },
+        ];
+        assert_eq!(
+            EntryAllocation::replace(&database, entry_id, &allocations),
+            Ok(Some(AllocationOutcome::Allocated))
+        );
+        assert_eq!(EntryAllocation::load(&database, entry_id), Ok(allocations));
+
+        // The summary attributes each allocation to its cost center.
+        let amounts: Vec<_> = AccountSummary::load_all(&database)
+            .expect("valid summary")
+            .into_iter()
+            .map(|summary| (summary.cost_center, summary.amount))
+            .collect();
+        assert_eq!(amounts.len(), 2);
+        assert!(amounts.contains(&(String::from("Other"), Amount(4000))));
+
+        // Wrong sums are rejected without touching the stored allocations.
+        let wrong_allocations = vec![EntryAllocation {
+            cost_center: other_cost_center,
+            amount: Amount(9999),
+        }];
+        assert_eq!(
+            EntryAllocation::replace(&database, entry_id, &wrong_allocations),
+            Ok(Some(AllocationOutcome::SumMismatch(Amount(10000))))
+        );
+        assert_eq!(
+            EntryAllocation::load(&database, entry_id).map(|value| value.len()),
+            Ok(2)
+        );
+
+        assert_eq!(
+            EntryAllocation::replace(&database, entry_id, &[]),
+            Ok(Some(AllocationOutcome::Allocated))
+        );
+        assert_eq!(EntryAllocation::load(&database, entry_id), Ok(Vec::new()));
+        assert_eq!(
+            EntryAllocation::replace(&database, PrimaryKey::from(42), &[]),
+            Ok(None)
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::backend::database::{Database, Error, PrimaryKey, Record, Selectable};
+
+/// An entry which may be nested below another entry of the same kind, i.e. a cost center of a department.
+/// New entries could not create a cycle, as nothing is nested below them yet. Therefore, only moving and updating entries is checked.
+pub trait Hierarchical: Selectable<Output = Record<Self>> {
+    /// Select the entries directly nested below the parent.
+    fn children(database: &Database, parent: PrimaryKey<Self>) -> Result<Vec<Record<Self>>, Error> {
+        let statement = format!("{} WHERE parent = ?", Self::STATEMENT_SELECT_ALL);
+        let mut stmt = database.connection.prepare(&statement)?;
+        let iterator = stmt.query_map((parent,), |row| {
+            Self::SelectValue::try_from(row).map(Self::deserialize_sql)
+        })?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Select the path from the top level down to the entry itself. The path is empty if the entry does not exist.
+    fn path(database: &Database, entry: PrimaryKey<Self>) -> Result<Vec<Record<Self>>, Error> {
+        let statement = format!(
+            "WITH RECURSIVE ancestors(ancestor, depth) AS (
+                SELECT ?, 0 UNION ALL
+                SELECT parent, depth + 1 FROM {table} INNER JOIN ancestors ON {table}.id = ancestor WHERE parent IS NOT NULL
+            ) {select} INNER JOIN ancestors ON ancestor = id ORDER BY depth DESC",
+            table = Self::TABLE_NAME,
+            select = Self::STATEMENT_SELECT_ALL
+        );
+        let mut stmt = database.connection.prepare(&statement)?;
+        let iterator = stmt.query_map((entry,), |row| {
+            Self::SelectValue::try_from(row).map(Self::deserialize_sql)
+        })?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Check whether nesting the entry below the parent would nest it below itself.
+    fn creates_cycle(
+        database: &Database,
+        entry: PrimaryKey<Self>,
+        parent: Option<PrimaryKey<Self>>,
+    ) -> Result<bool, Error> {
+        Ok(match parent {
+            Some(parent) => Self::path(database, parent)?
+                .iter()
+                .any(|ancestor| ancestor.identifier.raw_index() == entry.raw_index()),
+            None => false,
+        })
+    }
+
+    /// Move the entry below a new parent or to the top level.
+    /// Returns `false` without changing anything if the entry does not exist or the move would create a cycle.
+    fn set_parent(
+        database: &Database,
+        entry: PrimaryKey<Self>,
+        parent: Option<PrimaryKey<Self>>,
+    ) -> Result<bool, Error> {
+        if Self::creates_cycle(database, entry, parent)? {
+            return Ok(false);
+        }
+
+        let statement = format!("UPDATE {} SET parent = ? WHERE id = ?", Self::TABLE_NAME);
+        Ok(database.connection.execute(&statement, (parent, entry))? == 1)
+    }
+}
+
+impl Hierarchical for super::Category {}
+
+impl Hierarchical for super::CostCenter {}
+
+#[cfg(test)]
+mod tests {
+    use super::Hierarchical;
+    use crate::backend::{
+        accounting::CostCenter,
+        database::{Database, Insertable},
+    };
+
+    #[test]
+    fn test_hierarchy() {
+        let database = Database::in_memory().expect("valid database");
+        let department = CostCenter {
+            description: String::from("Department"),
+            parent: None,
+        }
+        .insert(&database)
+        .expect("valid cost center");
+        let team = CostCenter {
+            description: String::from("Team"),
+            parent: Some(department),
+        }
+        .insert(&database)
+        .expect("valid cost center");
+        let project = CostCenter {
+            description: String::from("Project"),
+            parent: Some(team),
+        }
+        .insert(&database)
+        .expect("valid cost center");
+
+        let children = CostCenter::children(&database, department).expect("valid children");
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].identifier, team);
+
+        let path: Vec<_> = CostCenter::path(&database, project)
+            .expect("valid path")
+            .into_iter()
+            .map(|record| record.value.description)
+            .collect();
+        assert_eq!(path, ["Department", "Team", "Project"]);
+    }
+
+    #[test]
+    fn test_hierarchy_cycle() {
+        let database = Database::in_memory().expect("valid database");
+        let department = CostCenter::default()
+            .insert(&database)
+            .expect("valid cost center");
+        let team = CostCenter {
+            description: String::from("Team"),
+            parent: Some(department),
+        }
+        .insert(&database)
+        .expect("valid cost center");
+
+        assert_eq!(
+            CostCenter::set_parent(&database, department, Some(team)),
+            Ok(false)
+        );
+        assert_eq!(
+            CostCenter::set_parent(&database, department, Some(department)),
+            Ok(false)
+        );
+        assert_eq!(
+            CostCenter::path(&database, department).map(|path| path.len()),
+            Ok(1)
+        );
+
+        // Moving an entry to the top level is always possible.
+        assert_eq!(CostCenter::set_parent(&database, team, None), Ok(true));
+        assert_eq!(
+            CostCenter::set_parent(&database, department, Some(team)),
+            Ok(true)
+        );
+        assert_eq!(
+            CostCenter::children(&database, team).map(|children| children.len()),
+            Ok(1)
+        );
+    }
+}
@@ -1,13 +1,23 @@
+mod account_cost_center;
 mod account_summary;
 mod accounts;
+mod budget;
 mod category;
 mod cost_center;
+mod currency;
 mod entry;
+mod entry_allocation;
+mod hierarchy;
 
 pub use self::{
+    account_cost_center::AccountCostCenter,
     account_summary::AccountSummary,
-    accounts::Account,
+    accounts::{Account, AccountKind},
+    budget::{Budget, BudgetComparison, Percentage},
     category::Category,
     cost_center::CostCenter,
-    entry::{Amount, Entry},
+    currency::Currency,
+    entry::{Amount, AmountError, Entry},
+    entry_allocation::{AllocationOutcome, EntryAllocation},
+    hierarchy::Hierarchical,
 };
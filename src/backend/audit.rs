@@ -0,0 +1,223 @@
+use super::{
+    database::{Database, DefaultGenerator, Error, Insertable, PrimaryKey, Record, Selectable},
+    user::User,
+    Column, Date, FutureDate, Limit, WhereClause,
+};
+
+crate::backend::database::make_struct!(
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[table("audit_log")]
+    #[dependencies(User)]
+    #[impl_select(true, testing: true)]
+    AuditEntry {
+        user: PrimaryKey<User>,
+        table_name: String,
+        row_id: i64,
+        action: String,
+        timestamp: Date,
+        details: Option<String>
+    } ("FOREIGN KEY(user) REFERENCES users(id)")
+);
+
+impl AuditEntry {
+    pub const INSERT: &'static str = "insert";
+    pub const UPDATE: &'static str = "update";
+    pub const DELETE: &'static str = "delete";
+    pub const ARCHIVE: &'static str = "archive";
+    pub const UNARCHIVE: &'static str = "unarchive";
+
+    /// Record a change a user made to a row of a table.
+    pub fn record(
+        database: &Database,
+        user: PrimaryKey<User>,
+        table_name: &str,
+        row_id: i64,
+        action: &str,
+    ) -> Result<(), Error> {
+        Self::insert_entry(database, user, table_name, row_id, action, None)
+    }
+
+    /// Record a change which the action alone does not describe, i.e. the other half of a link between two rows.
+    pub fn record_details(
+        database: &Database,
+        user: PrimaryKey<User>,
+        table_name: &str,
+        row_id: i64,
+        action: &str,
+        details: impl Into<String>,
+    ) -> Result<(), Error> {
+        Self::insert_entry(
+            database,
+            user,
+            table_name,
+            row_id,
+            action,
+            Some(details.into()),
+        )
+    }
+
+    fn insert_entry(
+        database: &Database,
+        user: PrimaryKey<User>,
+        table_name: &str,
+        row_id: i64,
+        action: &str,
+        details: Option<String>,
+    ) -> Result<(), Error> {
+        AuditEntry {
+            user,
+            table_name: String::from(table_name),
+            row_id,
+            action: String::from(action),
+            timestamp: Date::today(),
+            details,
+        }
+        .insert(database)
+        .map(|_| ())
+    }
+
+    /// Restrict the log to the changes of a user, to a table, or to a period. Each of them is optional.
+    pub fn filter(
+        user: Option<PrimaryKey<User>>,
+        table_name: Option<&str>,
+        from: Option<FutureDate>,
+        to: Option<FutureDate>,
+    ) -> WhereClause<Self> {
+        // The columns are fixed and sortable, so they are always known.
+        let column = |name: &str| Column::try_from(name).expect("sortable column");
+        let mut filter = WhereClause::new().within(column("timestamp"), from, to);
+        if let Some(user) = user {
+            filter = filter.equals(column("user"), user);
+        }
+        if let Some(table_name) = table_name {
+            filter = filter.equals(column("table_name"), String::from(table_name));
+        }
+        filter
+    }
+
+    /// Select a page of the matching changes, starting with the most recent one.
+    pub fn select_latest(
+        database: &Database,
+        filter: &WhereClause<Self>,
+        limit: Limit,
+        offset: usize,
+    ) -> Result<Vec<Record<Self>>, Error> {
+        // Limit and offset are numbers and therefore inserted directly, keeping the parameters of the filter unchanged.
+        let statement = format!(
+            "{} {} ORDER BY id DESC LIMIT {} OFFSET {}",
+            <AuditEntry as Selectable>::STATEMENT_SELECT_ALL,
+            filter.display_sql(),
+            usize::from(limit),
+            offset
+        );
+        let mut stmt = database.connection.prepare(&statement)?;
+        let iterator = stmt.query_map(filter.params(), |row| {
+            <Self as Selectable>::SelectValue::try_from(row).map(Self::deserialize_sql)
+        })?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+}
+
+impl DefaultGenerator for AuditEntry {
+    fn create_default(database: &Database) -> Self {
+        let user = User::create_default(database)
+            .insert(database)
+            .expect("valid user");
+
+        AuditEntry {
+            user,
+            table_name: String::from("persons"),
+            row_id: 1,
+            action: String::from(AuditEntry::INSERT),
+            timestamp: Date::today(),
+            details: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{
+        database::{Database, DefaultGenerator, Insertable},
+        user::User,
+        FutureDate, Limit, WhereClause,
+    };
+
+    use super::AuditEntry;
+
+    #[test]
+    fn test_select_latest() {
+        let database = Database::in_memory().expect("valid database");
+        let user = User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        for row_id in 1..=3 {
+            AuditEntry::record(&database, user, "persons", row_id, AuditEntry::INSERT)
+                .expect("valid record");
+        }
+
+        let row_ids = |limit: usize, offset: usize| {
+            AuditEntry::select_latest(&database, &WhereClause::new(), Limit::from(limit), offset)
+                .expect("valid selection")
+                .into_iter()
+                .map(|record| record.value.row_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(row_ids(10, 0), vec![3, 2, 1]);
+        assert_eq!(row_ids(1, 1), vec![2]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let database = Database::in_memory().expect("valid database");
+        let users = [1, 2].map(|_| {
+            User::create_default(&database)
+                .insert(&database)
+                .expect("valid user")
+        });
+        AuditEntry::record(&database, users[0], "persons", 1, AuditEntry::INSERT)
+            .expect("valid record");
+        AuditEntry::record(&database, users[1], "persons", 2, AuditEntry::INSERT)
+            .expect("valid record");
+        AuditEntry::record(&database, users[1], "groups", 3, AuditEntry::INSERT)
+            .expect("valid record");
+
+        let row_ids = |filter: WhereClause<AuditEntry>| {
+            AuditEntry::select_latest(&database, &filter, Limit::from(10), 0)
+                .expect("valid selection")
+                .into_iter()
+                .map(|record| record.value.row_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            row_ids(AuditEntry::filter(Some(users[0]), None, None, None)),
+            vec![1]
+        );
+        assert_eq!(
+            row_ids(AuditEntry::filter(
+                Some(users[1]),
+                Some("persons"),
+                None,
+                None
+            )),
+            vec![2]
+        );
+        assert_eq!(
+            row_ids(AuditEntry::filter(
+                None,
+                None,
+                Some(FutureDate::today()),
+                None
+            )),
+            vec![3, 2, 1]
+        );
+        assert!(row_ids(AuditEntry::filter(
+            None,
+            None,
+            None,
+            FutureDate::try_from("2000-01-01").ok()
+        ))
+        .is_empty());
+    }
+}
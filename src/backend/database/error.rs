@@ -46,6 +46,8 @@ mod tests {
             related_to: Some(PrimaryKey::from(42)),
             active: true,
             creation_date: crate::backend::Date::today(),
+            must_change_password: false,
+            is_admin: false,
         };
 
         assert!(document
@@ -4,17 +4,35 @@ macro_rules! question_mark {
     };
 }
 
+macro_rules! assignment {
+    ($name: ident) => {
+        std::concat!(stringify!($name), " = ?")
+    };
+}
+
 macro_rules! impl_select {
-    (false, $name: ident, $table_name: expr, $($element: ident: $ty: ty),*) => {};
-    (true, $name: ident, $table_name: expr, $($element: ident: $ty: ty),*) => {
+    (false, $name: ident, $table_name: expr, [$($archivable: expr)?], [$($timestamps: expr)?], $($element: ident: $ty: ty),*) => {};
+    (true, $name: ident, $table_name: expr, [$($archivable: expr)?], [$($timestamps: expr)?], $($element: ident: $ty: ty),*) => {
         impl crate::backend::database::Selectable for $name {
             type Output = crate::backend::database::Record<Self>;
 
+            const ARCHIVABLE: bool = false $(|| $archivable)?;
+            const TIMESTAMPED: bool = false $(|| $timestamps)?;
+
             const STATEMENT_SELECT_ALL: &'static str = std::concat!("SELECT id, ", concat_with::concat!(with ", ", $(stringify!($element)),*) ," FROM ", $table_name);
 
+            const OUTPUT_FIELDS: &'static [&'static str] = &["identifier", $(stringify!($element)),*];
+
             // By now, we fill all those not sortable values with id. That will be safe.
             const SORTABLE_COLUMNS: &'static [&'static str] = &[
                 "id", $(if <$ty as crate::backend::database::DatabaseType>::IS_SORTABLE { stringify!($element) } else { "id" }),*
+                $(, if $timestamps { "created_at" } else { "id" }, if $timestamps { "updated_at" } else { "id" })?
+            ];
+
+            const SORTABLE_COLUMN_TYPES: &'static [&'static str] = &[
+                "INTEGER", $(if <$ty as crate::backend::database::DatabaseType>::IS_SORTABLE { <$ty as crate::backend::database::DatabaseType>::RAW_COLUMN_VALUE } else { "INTEGER" }),*
+                $(, if $timestamps { <crate::backend::Timestamp as crate::backend::database::DatabaseType>::RAW_COLUMN_VALUE } else { "INTEGER" },
+                if $timestamps { <crate::backend::Timestamp as crate::backend::database::DatabaseType>::RAW_COLUMN_VALUE } else { "INTEGER" })?
             ];
 
             type SelectValue<'a> = (i64, $( $ty ),*);
@@ -26,6 +44,10 @@ macro_rules! impl_select {
                     value: $name { $( $element ),* }
                 }
             }
+
+            fn primary_key(output: &Self::Output) -> crate::backend::database::PrimaryKey<Self> {
+                output.identifier
+            }
         }
 
         impl crate::backend::database::SelectableByPrimaryKey for $name {
@@ -76,7 +98,7 @@ macro_rules! impl_referenceable {
             let database = crate::backend::database::Database::plain().expect("valid database");
             $name::create_table(&database).expect("valid table");
 
-            let descriptions = $name::generate_descriptions(&database).expect("valid descriptions");
+            let descriptions = $name::generate_descriptors(&database).expect("valid descriptions");
             assert_eq!(descriptions.len(), 0);
         }
 
@@ -88,9 +110,9 @@ macro_rules! impl_referenceable {
             let example = $name::create_default(&database);
             let record = example.insert_record(&database).expect("insert sucessfull");
 
-            let descriptions = $name::generate_descriptions(&database).expect("valid descriptions");
+            let descriptions = $name::generate_descriptors(&database).expect("valid descriptions");
             assert_eq!(descriptions.len(), 1);
-            assert_eq!(descriptions[0].0, record.identifier);
+            assert_eq!(descriptions[0].identifier, record.identifier);
         }
     };
 }
@@ -169,7 +191,7 @@ macro_rules! make_struct {(
     $(#[derive($( $derived: ty ),+)] )?
     #[table($table_name: expr)]
     #[dependencies( $dependencies: ty )]
-    #[impl_select($should_impl: expr, testing: $should_impl_text: expr $(, description: $foreign_key_descriptor: expr)?)]
+    #[impl_select($should_impl: expr, testing: $should_impl_text: expr $(, description: $foreign_key_descriptor: expr)? $(, archivable: $archivable: expr)? $(, timestamps: $timestamps: expr)?)]
     $name: ident { $( $(#[$os_attr: meta])? $element: ident: $ty: ty),* } $( ($additional_conditions: expr) )?
 ) => {
     paste::paste! {
@@ -200,9 +222,25 @@ macro_rules! make_struct {(
             }
         }
 
+        impl crate::backend::database::Updatable for $name {
+            const STATEMENT_UPDATE: &'static str = std::concat!(
+                "UPDATE ", $table_name, " SET ", concat_with::concat!(with ", ", $(crate::backend::database::assignment!($element)),*), " WHERE id = ?"
+            );
+
+            type UpdateValue<'a> = ($( &'a $ty ),*, i64);
+
+            fn serialize_update_sql<'a>(&'a self, index: crate::backend::database::PrimaryKey<Self>) -> Self::UpdateValue<'a> {
+                ($( &self.$element ),* , index.0)
+            }
+        }
+
+        impl crate::backend::database::Deletable for $name {
+            const STATEMENT_DELETE: &'static str = std::concat!("DELETE FROM ", $table_name, " WHERE id = ?");
+        }
+
         impl crate::backend::database::Indexable for $name { }
 
-        crate::backend::database::impl_select!($should_impl, $name, $table_name, $($element: $ty),*);
+        crate::backend::database::impl_select!($should_impl, $name, $table_name, [$($archivable)?], [$($timestamps)?], $($element: $ty),*);
 
         crate::backend::database::impl_referenceable!($(
             $name => $foreign_key_descriptor
@@ -229,6 +267,7 @@ macro_rules! make_struct {(
     }
 }}
 
+pub(crate) use assignment;
 pub(crate) use impl_referenceable;
 pub(crate) use impl_select;
 #[cfg(test)]
@@ -240,10 +279,10 @@ pub(crate) use question_mark;
 mod test {
     use crate::backend::{
         database::{
-            Database, DatabaseEntry, Insertable, PrimaryKey, Record, Selectable,
-            SelectableByPrimaryKey,
+            Database, DatabaseEntry, Deletable, Insertable, PrimaryKey, Record, Selectable,
+            SelectableByPrimaryKey, Updatable,
         },
-        Limit, Order, Pagination,
+        Column, Limit, Order, Pagination, WhereClause,
     };
 
     crate::backend::database::make_struct!(
@@ -285,7 +324,7 @@ mod test {
         }
 
         let created_values =
-            Test::select_all_sorted(&database, pagination).expect("valid database query");
+            Test::select_all_sorted(&database, pagination, None).expect("valid database query");
 
         created_values
             .iter()
@@ -314,6 +353,51 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_update_statement() {
+        assert_eq!(
+            Test::STATEMENT_UPDATE,
+            "UPDATE tests SET bool_value = ?, string_value = ?, integer_value = ? WHERE id = ?"
+        );
+        assert_eq!(
+            TestSingleElement::STATEMENT_UPDATE,
+            "UPDATE tests_single SET string_value = ? WHERE id = ?"
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let database = Database::plain().expect("valid database");
+        Test::create_table(&database).expect("valid table");
+
+        let mut record = Test::default()
+            .insert_record(&database)
+            .expect("insert sucessfull");
+        record.value.string_value = String::from("ABC");
+        record.value.integer_value = 42;
+        assert_eq!(record.update(&database, record.identifier), Ok(true));
+        assert_eq!(Test::select(&database, record.identifier), Ok(record));
+
+        assert_eq!(
+            Test::default().update(&database, PrimaryKey::from(42)),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_delete() {
+        let database = Database::plain().expect("valid database");
+        Test::create_table(&database).expect("valid table");
+
+        let index = Test::default()
+            .insert(&database)
+            .expect("insert sucessfull");
+        assert_eq!(Test::STATEMENT_DELETE, "DELETE FROM tests WHERE id = ?");
+        assert_eq!(Test::delete(&database, index), Ok(true));
+        assert_eq!(Test::try_select(&database, index.raw_index()), Ok(None));
+        assert_eq!(Test::delete(&database, index), Ok(false));
+    }
+
     #[test]
     fn test_select_statement() {
         assert_eq!(
@@ -389,9 +473,58 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_output_fields() {
+        assert_eq!(
+            Test::OUTPUT_FIELDS,
+            ["identifier", "bool_value", "string_value", "integer_value"]
+        );
+    }
+
     #[test]
     fn test_sortable_columns() {
-        assert_eq!(Test::SORTABLE_COLUMNS, ["id", "id", "id", "integer_value"]);
+        assert_eq!(
+            Test::SORTABLE_COLUMNS,
+            ["id", "id", "string_value", "integer_value"]
+        );
+        assert_eq!(
+            Test::SORTABLE_COLUMN_TYPES,
+            ["INTEGER", "INTEGER", "TEXT", "INTEGER"]
+        );
+    }
+
+    #[test]
+    fn test_for_each() {
+        let database = Database::plain().expect("valid database");
+        Test::create_table(&database).expect("valid table");
+        for integer_value in [42u32, 43, 44] {
+            Test {
+                bool_value: false,
+                string_value: String::from("ABC"),
+                integer_value,
+            }
+            .insert(&database)
+            .expect("insert sucessfull");
+        }
+
+        let mut visited = Vec::new();
+        Test::for_each(&database, |output| {
+            visited.push(output.value.integer_value);
+            Ok::<_, crate::backend::database::Error>(())
+        })
+        .expect("valid database query");
+        assert_eq!(visited, vec![42, 43, 44]);
+
+        // Failures of the visitor end the iteration.
+        let mut visited = 0;
+        let result = Test::for_each(&database, |_| {
+            visited += 1;
+            Err(crate::backend::database::Error::from(
+                rusqlite::Error::InvalidQuery,
+            ))
+        });
+        assert!(result.is_err());
+        assert_eq!(visited, 1);
     }
 
     #[test]
@@ -425,4 +558,102 @@ mod test {
 
         assert_eq!(generate_pagination_data(pagination), vec![44, 43]);
     }
+
+    #[test]
+    fn test_sorted_select_filtered() {
+        let database = Database::plain().expect("valid database");
+        Test::create_table(&database).expect("valid table");
+        for (string_value, integer_value) in [("ABC", 42u32), ("DEF", 43), ("ABC", 44), ("ABC", 45)]
+        {
+            Test {
+                bool_value: false,
+                string_value: String::from(string_value),
+                integer_value,
+            }
+            .insert(&database)
+            .expect("insert sucessfull");
+        }
+
+        let select = |filter: WhereClause<Test>, limit: usize| {
+            let pagination =
+                Pagination::new("integer_value", 0, Limit::from(limit), Order::Descending)
+                    .expect("valid pagination");
+            Test::select_all_sorted(&database, pagination, Some(&filter))
+                .expect("valid database query")
+                .iter()
+                .map(|value| value.integer_value)
+                .collect::<Vec<_>>()
+        };
+        let string_value = Column::try_from("string_value").expect("valid column");
+        let integer_value = Column::try_from("integer_value").expect("valid column");
+
+        assert_eq!(
+            select(WhereClause::new().equals(string_value.clone(), "ABC"), 2),
+            vec![45, 44]
+        );
+        assert_eq!(
+            select(
+                WhereClause::new()
+                    .equals(string_value, "ABC")
+                    .between(integer_value, 43, 44),
+                5
+            ),
+            vec![44]
+        );
+    }
+
+    #[test]
+    fn test_count_filtered() {
+        let database = Database::plain().expect("valid database");
+        Test::create_table(&database).expect("valid table");
+        for integer_value in [42u32, 43, 44] {
+            Test {
+                bool_value: integer_value % 2 == 0,
+                string_value: String::from("ABC"),
+                integer_value,
+            }
+            .insert(&database)
+            .expect("insert sucessfull");
+        }
+
+        let integer_value = || Column::try_from("integer_value").expect("valid column");
+        assert_eq!(Test::count(&database), Ok(3));
+        assert_eq!(
+            Test::count_filtered(
+                &database,
+                &WhereClause::new().between(integer_value(), 43, 44)
+            ),
+            Ok(2)
+        );
+        assert_eq!(
+            Test::count_filtered(&database, &WhereClause::new().equals(integer_value(), 44)),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_exists_where() {
+        let database = Database::plain().expect("valid database");
+        Test::create_table(&database).expect("valid table");
+        let matching = |value: &str| {
+            let column = Column::try_from("string_value").expect("valid column");
+            WhereClause::new().equals(column, String::from(value))
+        };
+        assert_eq!(Test::exists_where(&database, &matching("ABC")), Ok(false));
+
+        Test {
+            bool_value: false,
+            string_value: String::from("ABC"),
+            integer_value: 42,
+        }
+        .insert(&database)
+        .expect("insert sucessfull");
+
+        assert_eq!(Test::exists_where(&database, &matching("ABC")), Ok(true));
+        assert_eq!(Test::exists_where(&database, &matching("DEF")), Ok(false));
+        assert_eq!(
+            Test::exists_where(&database, &WhereClause::new().only_archived()),
+            Ok(false)
+        );
+    }
 }
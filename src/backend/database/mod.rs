@@ -1,18 +1,24 @@
 mod error;
 mod macros;
+mod pool;
 mod primary_key;
 mod record;
+mod search;
 mod sqlite;
 mod traits;
 
 pub use self::error::Error;
 #[cfg(test)]
 pub(crate) use self::macros::impl_select_test;
-pub(crate) use self::macros::{impl_referenceable, impl_select, make_struct, question_mark};
+pub(crate) use self::macros::{
+    assignment, impl_referenceable, impl_select, make_struct, question_mark,
+};
+pub use self::pool::{ConnectionManager, Pool, PooledDatabase};
 pub use self::primary_key::{ParseError as PrimaryKeyParseError, PrimaryKey};
-pub use self::record::Record;
+pub use self::record::{Record, Timestamped};
+pub use self::search::{like_pattern, LIKE_ESCAPE};
 pub use self::sqlite::Database;
 pub use self::traits::{
-    DatabaseEntry, DatabaseType, DefaultGenerator, Dependency, Indexable, Insertable,
-    Referenceable, Selectable, SelectableByPrimaryKey,
+    DatabaseEntry, DatabaseType, DefaultGenerator, Deletable, Dependency, Descriptor, Indexable,
+    Insertable, Referenceable, Selectable, SelectableByPrimaryKey, Updatable,
 };
@@ -0,0 +1,160 @@
+use std::{path::PathBuf, sync::Mutex};
+
+use rusqlite::{Connection, OpenFlags};
+
+use super::{Database, Error};
+
+/// Connections to the same database shared among concurrent requests.
+pub type Pool = r2d2::Pool<ConnectionManager>;
+
+/// A connection taken from a [`Pool`], which is returned to the pool once dropped.
+pub type PooledDatabase = r2d2::PooledConnection<ConnectionManager>;
+
+/// Open the connections of a [`Pool`].
+pub struct ConnectionManager(Source);
+
+enum Source {
+    /// A file which is opened again for every connection.
+    File { path: PathBuf, flags: OpenFlags },
+    /// An in-memory database which is lost once its connection is closed. Therefore, it is handed out just once.
+    Memory(Mutex<Option<Database>>),
+}
+
+impl std::fmt::Debug for ConnectionManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectionManager").finish_non_exhaustive()
+    }
+}
+
+impl r2d2::ManageConnection for ConnectionManager {
+    type Connection = Database;
+    type Error = Error;
+
+    fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        match &self.0 {
+            Source::File { path, flags } => Ok(Database {
+                connection: Connection::open_with_flags(path, *flags)?,
+            }),
+            Source::Memory(database) => {
+                database
+                    .lock()
+                    .expect("database mutex")
+                    .take()
+                    .ok_or_else(|| {
+                        Error::from(rusqlite::Error::SqliteFailure(
+                            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+                            Some(String::from("in-memory databases could not be reopened")),
+                        ))
+                    })
+            }
+        }
+    }
+
+    fn is_valid(&self, database: &mut Self::Connection) -> Result<(), Self::Error> {
+        Ok(database.connection.execute_batch("")?)
+    }
+
+    fn has_broken(&self, _: &mut Self::Connection) -> bool {
+        false
+    }
+}
+
+/// Prepare each new connection like the one opened at startup, apart from the migrations.
+#[derive(Debug)]
+struct Customizer {
+    tracer: Option<fn(&str)>,
+}
+
+impl r2d2::CustomizeConnection<Database, Error> for Customizer {
+    fn on_acquire(&self, database: &mut Database) -> Result<(), Error> {
+        database.configure_connection()?;
+        database.set_tracer(self.tracer);
+        Ok(())
+    }
+}
+
+impl Database {
+    /// Share the database among a pool of connections, which trace their statements if requested.
+    /// The migrations already ran on this connection and are not repeated for each connection.
+    /// In-memory databases could not be shared and therefore become a pool of this single connection.
+    pub fn into_pool(self, tracer: Option<fn(&str)>) -> Result<Pool, r2d2::Error> {
+        let builder = Pool::builder().connection_customizer(Box::new(Customizer { tracer }));
+        match self.path() {
+            Some(path) => builder.build(ConnectionManager(Source::File {
+                path,
+                flags: OpenFlags::default(),
+            })),
+            None => builder
+                .max_size(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .build(ConnectionManager(Source::Memory(Mutex::new(Some(self))))),
+        }
+    }
+
+    /// Open a pool of connections to the same file which are unable to modify the database.
+    /// In-memory databases could not be shared and therefore have no such pool.
+    pub fn read_only_pool(&self, tracer: Option<fn(&str)>) -> Result<Option<Pool>, r2d2::Error> {
+        match self.path() {
+            Some(path) => Pool::builder()
+                .connection_customizer(Box::new(Customizer { tracer }))
+                .build(ConnectionManager(Source::File {
+                    path,
+                    flags: OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                }))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::{
+        database::{Database, Insertable, Selectable},
+        person::Person,
+    };
+
+    #[test]
+    fn test_into_pool() {
+        let path = std::env::temp_dir().join(format!("shelby-pool-test-{}.db", std::process::id()));
+        {
+            let pool = Database::open(&path)
+                .expect("valid database")
+                .into_pool(None)
+                .expect("valid pool");
+            let first = pool.get().expect("valid connection");
+            let second = pool.get().expect("valid connection");
+
+            Person::default().insert(&first).expect("valid person");
+            assert_eq!(Person::count(&second), Ok(1));
+            assert_eq!(second.foreign_keys_enabled(), Ok(true));
+
+            let read_only = Database::open(&path)
+                .expect("valid database")
+                .read_only_pool(None)
+                .expect("valid pool")
+                .expect("file-based database");
+            let read_only = read_only.get().expect("valid connection");
+            assert_eq!(Person::count(&read_only), Ok(1));
+            assert!(Person::default().insert(&read_only).is_err());
+        }
+        for suffix in ["", "-shm", "-wal"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_into_pool_in_memory() {
+        let database = Database::in_memory().expect("valid database");
+        Person::default().insert(&database).expect("valid person");
+        assert!(database.read_only_pool(None).expect("valid pool").is_none());
+
+        let pool = database.into_pool(None).expect("valid pool");
+        assert_eq!(pool.max_size(), 1);
+        for _ in 0..2 {
+            let database = pool.get().expect("valid connection");
+            assert_eq!(Person::count(&database), Ok(1));
+        }
+    }
+}
@@ -30,3 +30,14 @@ where
         &self.value
     }
 }
+
+/// An output together with the time its row was created and last changed, which are omitted if not tracked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Timestamped<T> {
+    #[serde(flatten)]
+    pub value: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<crate::backend::Timestamp>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<crate::backend::Timestamp>,
+}
@@ -0,0 +1,52 @@
+/// The clause which must follow a `LIKE` whose pattern was created by [`like_pattern`].
+pub const LIKE_ESCAPE: &str = "ESCAPE '\\'";
+
+/// Create a pattern for `LIKE` matching the value anywhere. The metacharacters `%` and `_` within the value match literally.
+pub fn like_pattern(value: impl AsRef<str>) -> String {
+    let mut pattern = String::from("%");
+    for character in value.as_ref().chars() {
+        if matches!(character, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(character);
+    }
+    pattern.push('%');
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{like_pattern, LIKE_ESCAPE};
+    use crate::backend::{
+        database::{Database, Insertable},
+        person::Person,
+    };
+
+    #[test]
+    fn test_like_pattern() {
+        assert_eq!(like_pattern("50%"), "%50\\%%");
+        assert_eq!(like_pattern("a_b\\c"), "%a\\_b\\\\c%");
+    }
+
+    #[test]
+    fn test_literal_percent() {
+        let database = Database::in_memory().expect("valid database");
+        for name in ["50% Club", "500 Club", "Fifty"] {
+            Person {
+                name: String::from(name),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid insert");
+        }
+
+        let query = format!("SELECT name FROM persons WHERE name LIKE ? {}", LIKE_ESCAPE);
+        let mut stmt = database.connection.prepare(&query).expect("valid query");
+        let names: Vec<String> = stmt
+            .query_map((like_pattern("50%"),), |row| row.get(0))
+            .expect("valid query")
+            .filter_map(|value| value.ok())
+            .collect();
+        assert_eq!(names, vec![String::from("50% Club")]);
+    }
+}
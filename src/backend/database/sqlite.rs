@@ -1,8 +1,91 @@
-use rusqlite::Connection;
+use rusqlite::{Connection, OptionalExtension};
 use rusqlite_migration::{Migrations, M};
 
 use super::{DatabaseEntry, Error};
 
+/// Record the time each row of the tables is changed last. The creation counts as a change as well.
+macro_rules! track_updates {
+    ($($table: literal),*) => {
+        const_format::concatcp!(
+            $(
+                "ALTER TABLE ", $table, " ADD COLUMN updated_at TEXT; ",
+                "UPDATE ", $table, " SET updated_at = created_at; ",
+                track_updates!(@trigger $table, "UPDATE"),
+            )*
+        )
+    };
+    (@trigger $table: literal, $event: expr) => {
+        const_format::concatcp!(
+            "CREATE TRIGGER IF NOT EXISTS ", $table, "_touched AFTER ", $event, " ON ", $table,
+            " BEGIN UPDATE ", $table, " SET updated_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id; END; "
+        )
+    };
+    (drop $($table: literal),*) => {
+        concat!(
+            $(
+                "DROP TRIGGER ", $table, "_touched; ALTER TABLE ", $table, " DROP COLUMN updated_at; ",
+            )*
+        )
+    };
+}
+
+/// The migration adding the contact details of persons. Persons stored before must remain readable afterwards.
+const PERSON_CONTACTS_MIGRATION: M<'static> = M::up("ALTER TABLE persons ADD COLUMN telephone TEXT; ALTER TABLE persons ADD COLUMN mobile TEXT; ALTER TABLE persons ADD COLUMN company TEXT; ALTER TABLE persons ADD COLUMN role TEXT; ALTER TABLE persons ADD COLUMN website TEXT;")
+    .down("ALTER TABLE persons DROP COLUMN telephone; ALTER TABLE persons DROP COLUMN mobile; ALTER TABLE persons DROP COLUMN company; ALTER TABLE persons DROP COLUMN role; ALTER TABLE persons DROP COLUMN website;");
+
+/// The columns holding the data of users. Logins only change the time of the last login, which is neither a change of the user nor of the table.
+/// The creation time is set after inserting a user, which counts as a change like for the other tables. Columns added to the users later must be added here as well.
+const USER_DATA_UPDATE: &str = "UPDATE OF username, password_hash, active, creation_date, related_to, must_change_password, is_admin, created_at";
+
+/// Record the time each row of the tables is created, i.e. for showing what is new since the last login.
+macro_rules! track_creation {
+    ($($table: literal),*) => {
+        concat!(
+            $(
+                "ALTER TABLE ", $table, " ADD COLUMN created_at TEXT; ",
+                "CREATE TRIGGER IF NOT EXISTS ", $table, "_created AFTER INSERT ON ", $table,
+                " BEGIN UPDATE ", $table, " SET created_at = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = NEW.id; END; ",
+            )*
+        )
+    };
+    (drop $($table: literal),*) => {
+        concat!(
+            $(
+                "DROP TRIGGER ", $table, "_created; ALTER TABLE ", $table, " DROP COLUMN created_at; ",
+            )*
+        )
+    };
+}
+
+/// Record the time of the last change of the tables in milliseconds, i.e. for answering conditional requests.
+macro_rules! track_changes {
+    ($($table: literal),*) => {
+        const_format::concatcp!(
+            "CREATE TABLE IF NOT EXISTS table_changes (table_name TEXT PRIMARY KEY, updated_at INTEGER NOT NULL); ",
+            $(
+                track_changes!(@trigger $table, "inserted", "INSERT"),
+                track_changes!(@trigger $table, "updated", "UPDATE"),
+                track_changes!(@trigger $table, "deleted", "DELETE"),
+            )*
+        )
+    };
+    (@trigger $table: literal, $suffix: literal, $event: expr) => {
+        const_format::concatcp!(
+            "CREATE TRIGGER IF NOT EXISTS ", $table, "_", $suffix, " AFTER ", $event, " ON ", $table,
+            " BEGIN INSERT OR REPLACE INTO table_changes (table_name, updated_at) VALUES ('", $table,
+            "', CAST(unixepoch('now', 'subsec') * 1000 AS INTEGER)); END; "
+        )
+    };
+    (drop $($table: literal),*) => {
+        concat!(
+            $(
+                "DROP TRIGGER ", $table, "_inserted; DROP TRIGGER ", $table, "_updated; DROP TRIGGER ", $table, "_deleted; ",
+            )*
+            "DROP TABLE table_changes;"
+        )
+    };
+}
+
 pub struct Database {
     pub(crate) connection: Connection,
 }
@@ -32,6 +115,14 @@ impl Database {
         })?)
     }
 
+    /// Get the file of the database, which in-memory databases do not have.
+    pub(super) fn path(&self) -> Option<std::path::PathBuf> {
+        self.connection
+            .path()
+            .filter(|path| !path.is_empty())
+            .map(std::path::PathBuf::from)
+    }
+
     /// Get a raw SQLite database. This should only be relevant for unit testing purposes.
     #[cfg(test)]
     pub fn plain() -> Result<Self, Error> {
@@ -45,21 +136,135 @@ impl Database {
                 rusqlite_migration::Error::RusqliteError { query: _, err } => err,
                 _ => panic!("Unexpected error in running the migration"),
             })?;
+        self.configure_connection()
+    }
+
+    /// Apply the settings SQLite keeps per connection. Read-only connections keep the journal mode of the file.
+    pub(super) fn configure_connection(&self) -> Result<(), rusqlite::Error> {
+        if !self.connection.is_readonly(rusqlite::DatabaseName::Main)? {
+            self.connection.pragma_update(None, "journal_mode", "WAL")?;
+        }
+        self.connection.pragma_update(None, "foreign_keys", "ON")?;
 
-        self.connection.pragma_update(None, "journal_mode", "WAL")?;
-        self.connection.pragma_update(None, "foreign_keys", "ON")
+        // SQLite builds without foreign key support silently ignore the pragma. Therefore, it is read back.
+        match self.foreign_keys_enabled()? {
+            true => Ok(()),
+            false => Err(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_MISUSE),
+                Some(String::from(
+                    "foreign keys could not be enabled in this SQLite build",
+                )),
+            )),
+        }
+    }
+
+    /// Run an operation atomically, rolling all of its changes back if it fails.
+    /// In a dry run, the changes are rolled back even on success, so the result only describes what would have changed.
+    /// Transactions could be nested.
+    pub fn transaction<T>(
+        &self,
+        dry_run: bool,
+        operation: impl FnOnce(&Database) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.connection.execute_batch("SAVEPOINT operation")?;
+        let result = operation(self);
+        self.connection.execute_batch(match (&result, dry_run) {
+            (Ok(_), false) => "RELEASE operation",
+            _ => "ROLLBACK TO operation; RELEASE operation",
+        })?;
+        result
+    }
+
+    /// Get the time of the last change to any of the tables. Tables which were never changed are ignored.
+    pub fn last_modified(
+        &self,
+        tables: &[&str],
+    ) -> Result<Option<chrono::DateTime<chrono::Utc>>, Error> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT updated_at FROM table_changes WHERE table_name = ?")?;
+        let mut last_modified = None;
+        for table in tables {
+            let updated_at: Option<i64> = stmt.query_row((table,), |row| row.get(0)).optional()?;
+            last_modified = std::cmp::max(last_modified, updated_at);
+        }
+        Ok(last_modified.and_then(chrono::DateTime::from_timestamp_millis))
+    }
+
+    /// Register a callback receiving every executed statement with its parameters expanded, or remove it.
+    pub fn set_tracer(&mut self, tracer: Option<fn(&str)>) {
+        self.connection.trace(tracer);
+    }
+
+    /// Print an executed statement for debugging, redacting blobs such as the password hash.
+    pub fn log_statement(statement: &str) {
+        eprintln!("SQL: {}", Self::redact_blobs(statement));
+    }
+
+    /// Replace the content of all blob literals, i.e. `X'0A1B'`, in an expanded statement.
+    fn redact_blobs(statement: &str) -> String {
+        let mut redacted = String::with_capacity(statement.len());
+        let mut rest = statement;
+        while let Some(start) = rest.find('\'') {
+            let (before, literal) = rest.split_at(start);
+            // Escaped quotes within strings are handled as two adjacent literals.
+            let end = literal[1..].find('\'').map_or(literal.len(), |end| end + 2);
+            let is_blob = before
+                .strip_suffix(['X', 'x'])
+                .is_some_and(|before| !before.ends_with(|c: char| c.is_alphanumeric() || c == '_'));
+            redacted.push_str(before);
+            redacted.push_str(match is_blob {
+                true => "'<redacted>'",
+                false => &literal[..end],
+            });
+            rest = &literal[end..];
+        }
+        redacted.push_str(rest);
+        redacted
+    }
+
+    /// Check the database for corruption, i.e. after a crash. A healthy database reports no problems.
+    pub fn integrity_check(&self) -> Result<Vec<String>, Error> {
+        let mut stmt = self.connection.prepare("PRAGMA integrity_check")?;
+        let problems = stmt
+            .query_map((), |row| row.get::<usize, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(match problems.as_slice() {
+            [result] if result == "ok" => Vec::new(),
+            _ => problems,
+        })
+    }
+
+    /// Copy a consistent snapshot of the database into a new file, even while it is in use.
+    pub fn backup_to(&self, path: &std::path::Path) -> Result<(), Error> {
+        Ok(self
+            .connection
+            .backup(rusqlite::DatabaseName::Main, path, None)?)
+    }
+
+    /// Check whether foreign key constraints are enforced on the connection.
+    pub(super) fn foreign_keys_enabled(&self) -> Result<bool, rusqlite::Error> {
+        self.connection
+            .pragma_query_value(None, "foreign_keys", |row| row.get(0))
     }
 
     fn get_migrations() -> Migrations<'static> {
-        Migrations::new(vec![
+        Migrations::new(Self::migration_list())
+    }
+
+    /// The migrations in the order they are applied. The version of a database is the number of migrations applied to it.
+    fn migration_list() -> Vec<M<'static>> {
+        vec![
             M::up(const_format::concatcp!(
-                crate::backend::person::Person::STATEMENT_CREATE_TABLE,
+                // The persons table changed afterwards. Therefore, this migration keeps its original definition.
+                "CREATE TABLE IF NOT EXISTS persons (id INTEGER PRIMARY KEY, name TEXT NOT NULL, address TEXT NOT NULL, email TEXT, birthday DATETIME, comment TEXT )",
                 "; ",
                 crate::backend::person::Group::STATEMENT_CREATE_TABLE,
                 "; ",
                 crate::backend::person::Membership::STATEMENT_CREATE_TABLE,
                 "; ",
-                crate::backend::user::User::STATEMENT_CREATE_TABLE,
+                // The users table changed afterwards. Therefore, this migration keeps its original definition.
+                "CREATE TABLE IF NOT EXISTS users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, password_hash BLOB NOT NULL, active BOOL NOT NULL, creation_date DATETIME NOT NULL, related_to INTEGER, FOREIGN KEY(related_to) REFERENCES persons(id)  )",
                 "; ",
                 crate::backend::document::Document::STATEMENT_CREATE_TABLE,
                 "; ",
@@ -76,13 +281,16 @@ impl Database {
                 "; ",
             )),
             M::up(const_format::concatcp!(
-                crate::backend::accounting::Account::STATEMENT_CREATE_TABLE,
+                // The accounts table changed afterwards. Therefore, this migration keeps its original definition.
+                "CREATE TABLE IF NOT EXISTS accounts (id INTEGER PRIMARY KEY, code INTEGER NOT NULL, category INTEGER NOT NULL, description TEXT NOT NULL )",
                 "; ",
-                crate::backend::accounting::Category::STATEMENT_CREATE_TABLE,
+                // The categories and cost centers tables changed afterwards, too.
+                "CREATE TABLE IF NOT EXISTS categories (id INTEGER PRIMARY KEY, description TEXT NOT NULL )",
                 "; ",
-                crate::backend::accounting::CostCenter::STATEMENT_CREATE_TABLE,
+                "CREATE TABLE IF NOT EXISTS cost_centers (id INTEGER PRIMARY KEY, description TEXT NOT NULL )",
                 "; ",
-                crate::backend::accounting::Entry::STATEMENT_CREATE_TABLE,
+                // The entries table changed afterwards, too.
+                "CREATE TABLE IF NOT EXISTS entries (id INTEGER PRIMARY KEY, evidence INTEGER NOT NULL, account INTEGER NOT NULL, cost_center INTEGER NOT NULL, amount INTEGER NOT NULL, description TEXT NOT NULL )",
                 "; ",
             ))
             .down(const_format::concatcp!(
@@ -96,7 +304,94 @@ impl Database {
                 crate::backend::accounting::Entry::TABLE_NAME,
                 ";"
             )),
-        ])
+            M::up("ALTER TABLE accounts ADD COLUMN kind TEXT NOT NULL DEFAULT 'asset';")
+                .down("ALTER TABLE accounts DROP COLUMN kind;"),
+            M::up("ALTER TABLE entries ADD COLUMN reference_number TEXT; CREATE INDEX IF NOT EXISTS entries_reference_number ON entries (reference_number);")
+                .down("DROP INDEX entries_reference_number; ALTER TABLE entries DROP COLUMN reference_number;"),
+            M::up("ALTER TABLE users ADD COLUMN must_change_password BOOL NOT NULL DEFAULT FALSE;")
+                .down("ALTER TABLE users DROP COLUMN must_change_password;"),
+            // The budgets table changed afterwards. Therefore, this migration keeps its original definition.
+            M::up("CREATE TABLE IF NOT EXISTS budgets (id INTEGER PRIMARY KEY, cost_center INTEGER NOT NULL, year INTEGER NOT NULL, target INTEGER NOT NULL, FOREIGN KEY(cost_center) REFERENCES cost_centers(id), UNIQUE(cost_center, year)  );")
+            .down(const_format::concatcp!(
+                "DROP TABLE ",
+                crate::backend::accounting::Budget::TABLE_NAME,
+                ";"
+            )),
+            M::up("CREATE TABLE IF NOT EXISTS document_uploads (id INTEGER PRIMARY KEY, started DATETIME NOT NULL); CREATE TABLE IF NOT EXISTS document_upload_chunks (upload INTEGER NOT NULL, offset INTEGER NOT NULL, data BLOB NOT NULL, PRIMARY KEY (upload, offset), FOREIGN KEY (upload) REFERENCES document_uploads(id));")
+                .down("DROP TABLE document_upload_chunks; DROP TABLE document_uploads;"),
+            M::up(crate::backend::accounting::AccountCostCenter::STATEMENT_CREATE_TABLE).down(
+                const_format::concatcp!(
+                    "DROP TABLE ",
+                    crate::backend::accounting::AccountCostCenter::TABLE_NAME,
+                    ";"
+                ),
+            ),
+            M::up(track_changes!(
+                "persons", "groups", "memberships", "users", "documents", "categories", "cost_centers",
+                "accounts", "entries", "budgets", "account_cost_centers"
+            ))
+            .down(track_changes!(drop
+                "persons", "groups", "memberships", "users", "documents", "categories", "cost_centers",
+                "accounts", "entries", "budgets", "account_cost_centers"
+            )),
+            M::up(const_format::concatcp!(
+                crate::backend::accounting::EntryAllocation::STATEMENT_CREATE_TABLE,
+                "; ",
+                track_changes!(@trigger "entry_allocations", "inserted", "INSERT"),
+                track_changes!(@trigger "entry_allocations", "updated", "UPDATE"),
+                track_changes!(@trigger "entry_allocations", "deleted", "DELETE"),
+            ))
+            .down("DROP TRIGGER entry_allocations_inserted; DROP TRIGGER entry_allocations_updated; DROP TRIGGER entry_allocations_deleted; DROP TABLE entry_allocations;"),
+            M::up("ALTER TABLE categories ADD COLUMN parent INTEGER REFERENCES categories(id); ALTER TABLE cost_centers ADD COLUMN parent INTEGER REFERENCES cost_centers(id);")
+                .down("ALTER TABLE categories DROP COLUMN parent; ALTER TABLE cost_centers DROP COLUMN parent;"),
+            M::up(const_format::concatcp!(
+                track_creation!(
+                    "persons", "groups", "users", "documents", "categories", "cost_centers",
+                    "accounts", "entries", "budgets"
+                ),
+                "ALTER TABLE users ADD COLUMN last_login TEXT; ALTER TABLE users ADD COLUMN previous_login TEXT;"
+            ))
+            .down(const_format::concatcp!(
+                "ALTER TABLE users DROP COLUMN previous_login; ALTER TABLE users DROP COLUMN last_login; ",
+                track_creation!(drop
+                    "persons", "groups", "users", "documents", "categories", "cost_centers",
+                    "accounts", "entries", "budgets"
+                )
+            )),
+            M::up("ALTER TABLE entries ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR';")
+                .down("ALTER TABLE entries DROP COLUMN currency;"),
+            // The oldest user becomes an administrator, such that existing databases could still be managed.
+            M::up("ALTER TABLE users ADD COLUMN is_admin BOOL NOT NULL DEFAULT FALSE; UPDATE users SET is_admin = TRUE WHERE id = (SELECT MIN(id) FROM users);")
+                .down("ALTER TABLE users DROP COLUMN is_admin;"),
+            M::up("ALTER TABLE persons ADD COLUMN archived BOOL NOT NULL DEFAULT FALSE; ALTER TABLE documents ADD COLUMN archived BOOL NOT NULL DEFAULT FALSE; ALTER TABLE accounts ADD COLUMN archived BOOL NOT NULL DEFAULT FALSE;")
+                .down("ALTER TABLE persons DROP COLUMN archived; ALTER TABLE documents DROP COLUMN archived; ALTER TABLE accounts DROP COLUMN archived;"),
+            M::up(crate::backend::audit::AuditEntry::STATEMENT_CREATE_TABLE)
+                .down("DROP TABLE audit_log;"),
+            M::up(track_updates!(
+                "persons", "groups", "users", "documents", "categories", "cost_centers",
+                "accounts", "entries", "budgets"
+            ))
+            .down(track_updates!(drop
+                "persons", "groups", "users", "documents", "categories", "cost_centers",
+                "accounts", "entries", "budgets"
+            )),
+            PERSON_CONTACTS_MIGRATION,
+            // Uploads started before are not owned by anyone and therefore expire unfinished.
+            M::up("ALTER TABLE document_uploads ADD COLUMN owner INTEGER REFERENCES users(id);")
+                .down("ALTER TABLE document_uploads DROP COLUMN owner;"),
+            M::up("ALTER TABLE budgets ADD COLUMN currency TEXT NOT NULL DEFAULT 'EUR';")
+                .down("ALTER TABLE budgets DROP COLUMN currency;"),
+            M::up(const_format::concatcp!(
+                "DROP TRIGGER users_touched; DROP TRIGGER users_updated; ",
+                track_updates!(@trigger "users", USER_DATA_UPDATE),
+                track_changes!(@trigger "users", "updated", USER_DATA_UPDATE),
+            ))
+            .down(const_format::concatcp!(
+                "DROP TRIGGER users_touched; DROP TRIGGER users_updated; ",
+                track_updates!(@trigger "users", "UPDATE"),
+                track_changes!(@trigger "users", "updated", "UPDATE"),
+            )),
+        ]
     }
 }
 
@@ -108,4 +403,206 @@ mod tests {
     fn test_migrations() {
         assert!(Database::get_migrations().validate().is_ok());
     }
+
+    #[test]
+    fn test_person_contact_migration() {
+        use crate::backend::{database::SelectableByPrimaryKey, person::Person};
+
+        let mut database = Database::in_memory().expect("valid database");
+        // Later migrations are appended, so the version before the contact details is found by their position.
+        let before_contacts = Database::migration_list()
+            .iter()
+            .position(|migration| *migration == super::PERSON_CONTACTS_MIGRATION)
+            .expect("known migration");
+
+        // Persons stored before the contact details existed remain readable ...
+        Database::get_migrations()
+            .to_version(&mut database.connection, before_contacts)
+            .expect("valid downgrade");
+        assert!(database
+            .connection
+            .prepare("SELECT telephone FROM persons")
+            .is_err());
+        database
+            .connection
+            .execute(
+                "INSERT INTO persons (name, address) VALUES ('Max', 'Street 1')",
+                (),
+            )
+            .expect("valid insert");
+        database.prepare_connection().expect("valid upgrade");
+
+        let person = Person::try_select(&database, 1)
+            .expect("valid query")
+            .expect("existing person");
+        assert_eq!(person.name, "Max");
+        assert_eq!(person.telephone, None);
+        assert_eq!(person.website, None);
+    }
+
+    #[test]
+    fn test_transaction() {
+        use crate::backend::{
+            database::{Error, Insertable, Selectable},
+            person::Person,
+        };
+
+        let database = Database::in_memory().expect("valid database");
+        let count = |database: &Database| Person::select_all(database).expect("valid query").len();
+
+        assert_eq!(
+            database.transaction(true, |database| {
+                Person::default().insert(database)?;
+                Ok(count(database))
+            }),
+            Ok(1)
+        );
+        assert_eq!(count(&database), 0);
+
+        assert!(database
+            .transaction(false, |database| {
+                Person::default().insert(database)?;
+                Err::<(), _>(Error::from(rusqlite::Error::InvalidQuery))
+            })
+            .is_err());
+        assert_eq!(count(&database), 0);
+
+        assert!(database
+            .transaction(false, |database| database
+                .transaction(false, |database| Person::default().insert(database)))
+            .is_ok());
+        assert_eq!(count(&database), 1);
+    }
+
+    #[test]
+    fn test_tracing() {
+        use crate::backend::{database::Selectable, person::Person};
+
+        thread_local! {
+            static STATEMENTS: std::cell::RefCell<Vec<String>> = Default::default();
+        }
+
+        let mut database = Database::in_memory().expect("valid database");
+        database.set_tracer(Some(|statement| {
+            STATEMENTS.with(|statements| statements.borrow_mut().push(String::from(statement)))
+        }));
+        Person::select_all(&database).expect("valid query");
+
+        let statements = STATEMENTS.with(|statements| statements.take());
+        assert!(statements
+            .iter()
+            .any(|statement| statement.starts_with("SELECT") && statement.contains("persons")));
+    }
+
+    #[test]
+    fn test_redact_blobs() {
+        assert_eq!(
+            Database::redact_blobs(
+                "UPDATE users SET password_hash = X'0A1B', username = 'Max''s X' WHERE id = 1"
+            ),
+            "UPDATE users SET password_hash = X'<redacted>', username = 'Max''s X' WHERE id = 1"
+        );
+        assert_eq!(
+            Database::redact_blobs("SELECT 'tax' FROM persons"),
+            "SELECT 'tax' FROM persons"
+        );
+    }
+
+    #[test]
+    fn test_backup_to() {
+        use crate::backend::{
+            database::{Insertable, Selectable},
+            person::Person,
+        };
+
+        let database = Database::in_memory().expect("valid database");
+        for _ in 0..3 {
+            Person::default().insert(&database).expect("valid person");
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("shelby-backup-test-{}.db", std::process::id()));
+        database.backup_to(&path).expect("valid backup");
+        let copy = Database::open(&path).expect("valid copy");
+        assert_eq!(
+            Person::count(&copy).expect("valid count"),
+            Person::count(&database).expect("valid count")
+        );
+        drop(copy);
+        for suffix in ["", "-shm", "-wal"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_integrity_check() {
+        let database = Database::in_memory().expect("valid database");
+        assert_eq!(database.integrity_check(), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_foreign_keys_enabled() {
+        let database = Database::in_memory().expect("valid database");
+        assert_eq!(database.foreign_keys_enabled(), Ok(true));
+    }
+
+    #[test]
+    fn test_last_modified() {
+        use crate::backend::{database::Insertable, person::Person};
+
+        let database = Database::in_memory().expect("valid database");
+        assert_eq!(database.last_modified(&["persons"]), Ok(None));
+
+        // Changes within the same second are distinguishable.
+        Person::default().insert(&database).expect("valid person");
+        let first = database.last_modified(&["persons"]).expect("valid query");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        Person::default().insert(&database).expect("valid person");
+        let second = database
+            .last_modified(&["persons", "groups"])
+            .expect("valid query");
+        assert!(first.is_some() && first < second);
+        assert!(chrono::Utc::now() - second.expect("valid time") < chrono::Duration::minutes(1));
+    }
+
+    #[test]
+    fn test_login_untracked() {
+        use crate::backend::{
+            database::{DefaultGenerator, Insertable},
+            user::User,
+        };
+
+        let database = Database::in_memory().expect("valid database");
+        let user = User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        let changes = || {
+            let updated_at: String = database
+                .connection
+                .query_row(
+                    "SELECT updated_at FROM users WHERE id = ?",
+                    (user.0,),
+                    |row| row.get(0),
+                )
+                .expect("valid query");
+            (
+                updated_at,
+                database.last_modified(&["users"]).expect("valid query"),
+            )
+        };
+        let before = changes();
+
+        // Logging in changes no data of the user ...
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(User::record_login(&database, user), Ok(true));
+        assert_eq!(changes(), before);
+
+        // ... unlike changing the user.
+        database
+            .connection
+            .execute("UPDATE users SET active = FALSE WHERE id = ?", (user.0,))
+            .expect("valid update");
+        let after = changes();
+        assert!(after.0 > before.0 && after.1 > before.1);
+    }
 }
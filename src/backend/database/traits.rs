@@ -1,7 +1,7 @@
 use rusqlite::OptionalExtension;
 
-use super::{Database, Error, PrimaryKey, Record};
-use crate::backend::util::Pagination;
+use super::{Database, Error, PrimaryKey, Record, Timestamped};
+use crate::backend::util::{Column, FutureDate, Pagination, WhereClause};
 
 pub trait Dependency {
     fn create_dependencies(database: &Database) -> Result<(), Error>;
@@ -96,6 +96,42 @@ pub trait Insertable: DatabaseEntry + Indexable + DefaultGenerator {
     }
 }
 
+/// A value which could replace an existing entry in the database.
+pub trait Updatable: Insertable {
+    /// The statement for updating all values of the entry, given the UpdateValue.
+    const STATEMENT_UPDATE: &'static str;
+
+    /// A (mostly tuple-based) values with the primary key as its last element.
+    type UpdateValue<'a>: rusqlite::Params
+    where
+        Self: 'a;
+
+    /// Convert the value as the update values of the entry with the given primary key.
+    fn serialize_update_sql<'a>(&'a self, index: PrimaryKey<Self>) -> Self::UpdateValue<'a>;
+
+    /// Replace the values of the entry with the given primary key. Returns whether the entry exists.
+    fn update(&self, database: &Database, index: PrimaryKey<Self>) -> Result<bool, Error> {
+        Ok(database
+            .connection
+            .execute(Self::STATEMENT_UPDATE, self.serialize_update_sql(index))?
+            == 1)
+    }
+}
+
+/// A value which could be removed from the database.
+pub trait Deletable: DatabaseEntry + Indexable {
+    /// The statement for deleting the entry with the given primary key.
+    const STATEMENT_DELETE: &'static str;
+
+    /// Remove the entry with the given primary key. Returns whether it existed.
+    fn delete(database: &Database, index: PrimaryKey<Self>) -> Result<bool, Error> {
+        Ok(database
+            .connection
+            .execute(Self::STATEMENT_DELETE, (index.0,))?
+            == 1)
+    }
+}
+
 pub trait Selectable: DatabaseEntry + Indexable {
     /// The public output. Other than the value itself, this value should be renderable in JSON without leaking sensible information.
     type Output: From<Record<Self>>;
@@ -106,12 +142,33 @@ pub trait Selectable: DatabaseEntry + Indexable {
     /// The statement for selecting all entries.
     const STATEMENT_SELECT_ALL: &'static str;
 
+    /// The fields of the serialized output, which clients could select.
+    const OUTPUT_FIELDS: &'static [&'static str];
+
     /// The sortable values within a table.
     const SORTABLE_COLUMNS: &'static [&'static str];
 
+    /// The subset of the sortable values clients may request. Internal values like ids could be hidden here.
+    const PUBLIC_SORTABLE_COLUMNS: &'static [&'static str] = Self::SORTABLE_COLUMNS;
+
+    /// The database types of the sortable values, in the same order.
+    const SORTABLE_COLUMN_TYPES: &'static [&'static str];
+
+    /// Whether elements are archived instead of removed, i.e. as they must be preserved. Archived elements are hidden unless requested explicitly.
+    const ARCHIVABLE: bool = false;
+
+    /// Whether the table tracks when its rows were created and last changed, i.e. for sorting by recency.
+    const TIMESTAMPED: bool = false;
+
+    /// The sortable date column clients may select a range of, if any.
+    const RANGE_COLUMN: Option<&'static str> = None;
+
     /// Deserialize the database value into a Record.
     fn deserialize_sql<'a>(value: Self::SelectValue<'a>) -> Self::Output;
 
+    /// Get the primary key of an output, i.e. for continuing after it.
+    fn primary_key(output: &Self::Output) -> PrimaryKey<Self>;
+
     /// Select all the elements from the database.
     fn select_all(database: &Database) -> Result<Vec<Self::Output>, Error> {
         let mut stmt = database.connection.prepare(Self::STATEMENT_SELECT_ALL)?;
@@ -122,18 +179,138 @@ pub trait Selectable: DatabaseEntry + Indexable {
         Ok(iterator.filter_map(|value| value.ok()).collect())
     }
 
+    /// Visit all the elements one after another without collecting them, i.e. for exporting large tables.
+    fn for_each<E: From<Error>>(
+        database: &Database,
+        mut visit: impl FnMut(Self::Output) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut stmt = database
+            .connection
+            .prepare(Self::STATEMENT_SELECT_ALL)
+            .map_err(Error::from)?;
+        let iterator = stmt
+            .query_map((), |row| {
+                Self::SelectValue::try_from(row).map(Self::deserialize_sql)
+            })
+            .map_err(Error::from)?;
+
+        for value in iterator.filter_map(|value| value.ok()) {
+            visit(value)?;
+        }
+        Ok(())
+    }
+
+    /// Select a page of the elements, optionally matching a filter.
     fn select_all_sorted(
         database: &Database,
         selection: Pagination<Self>,
+        filter: Option<&WhereClause<Self>>,
     ) -> Result<Vec<Self::Output>, Error> {
-        let statement = format!("{} {}", Self::STATEMENT_SELECT_ALL, selection.display_sql());
+        let empty_filter = WhereClause::new();
+        let filter = filter.unwrap_or(&empty_filter);
+        let statement = format!(
+            "{} {} {}",
+            Self::STATEMENT_SELECT_ALL,
+            filter.display_sql_paginated(&selection),
+            selection.display_sql()
+        );
         let mut stmt = database.connection.prepare(&statement)?;
-        let iterator = stmt.query_map((), |row| {
+        let iterator = stmt.query_map(filter.params(), |row| {
             Self::SelectValue::try_from(row).map(Self::deserialize_sql)
         })?;
 
         Ok(iterator.filter_map(|value| value.ok()).collect())
     }
+
+    /// Select a page of the elements like `select_all_sorted`, together with their timestamps if tracked.
+    fn select_all_timestamped(
+        database: &Database,
+        selection: Pagination<Self>,
+        filter: Option<&WhereClause<Self>>,
+    ) -> Result<Vec<Timestamped<Self::Output>>, Error> {
+        let empty_filter = WhereClause::new();
+        let filter = filter.unwrap_or(&empty_filter);
+        let statement = format!(
+            "{} {} {}",
+            with_timestamps::<Self>(Self::STATEMENT_SELECT_ALL),
+            filter.display_sql_paginated(&selection),
+            selection.display_sql()
+        );
+        let mut stmt = database.connection.prepare(&statement)?;
+        let iterator = stmt.query_map(filter.params(), parse_timestamped::<Self>)?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Select a page of the elements whose column equals the value.
+    fn select_all_filtered(
+        database: &Database,
+        selection: Pagination<Self>,
+        column: Column<Self>,
+        value: impl rusqlite::ToSql + 'static,
+    ) -> Result<Vec<Self::Output>, Error> {
+        let filter = WhereClause::new().equals(column, value);
+        Self::select_all_sorted(database, selection, Some(&filter))
+    }
+
+    /// Count all the elements within the table.
+    fn count(database: &Database) -> Result<usize, Error> {
+        Self::count_filtered(database, &WhereClause::new())
+    }
+
+    /// Count the elements matching a filter, i.e. for showing the total number of pages of a selection.
+    fn count_filtered(database: &Database, filter: &WhereClause<Self>) -> Result<usize, Error> {
+        let statement = format!(
+            "SELECT COUNT(*) FROM {} {}",
+            Self::TABLE_NAME,
+            filter.display_sql()
+        );
+        Ok(database
+            .connection
+            .query_row(&statement, filter.params(), |row| row.get(0))?)
+    }
+
+    /// Archive an element or restore it. Returns whether it exists, which is never the case if the elements could not be archived.
+    fn set_archived(
+        database: &Database,
+        index: PrimaryKey<Self>,
+        archived: bool,
+    ) -> Result<bool, Error> {
+        if !Self::ARCHIVABLE {
+            return Ok(false);
+        }
+        let statement = format!("UPDATE {} SET archived = ? WHERE id = ?", Self::TABLE_NAME);
+        Ok(database
+            .connection
+            .execute(&statement, (archived, index.0))?
+            == 1)
+    }
+
+    /// Check whether an element is archived. Elements which could not be archived never are.
+    fn is_archived(database: &Database, index: i64) -> Result<bool, Error> {
+        match Self::ARCHIVABLE {
+            // The first column is always the identifier.
+            true => Self::exists_where(
+                database,
+                &WhereClause::new()
+                    .equals(Column::default(), index)
+                    .only_archived(),
+            ),
+            false => Ok(false),
+        }
+    }
+
+    /// Check whether any element matches a filter without loading it, i.e. before inserting a unique value.
+    fn exists_where(database: &Database, filter: &WhereClause<Self>) -> Result<bool, Error> {
+        let statement = format!(
+            "SELECT EXISTS(SELECT 1 FROM {} {})",
+            Self::TABLE_NAME,
+            filter.display_sql()
+        );
+        Ok(database
+            .connection
+            .query_row(&statement, filter.params(), |row| row.get(0))?)
+    }
 }
 
 pub trait SelectableByPrimaryKey: Selectable + Indexable {
@@ -158,22 +335,84 @@ pub trait SelectableByPrimaryKey: Selectable + Indexable {
             })
             .optional()?)
     }
+
+    /// Try to select an element like `try_select`, together with its timestamps if tracked.
+    fn try_select_timestamped(
+        database: &Database,
+        index: i64,
+    ) -> Result<Option<Timestamped<Self::Output>>, Error> {
+        Ok(database
+            .connection
+            .query_row(
+                &with_timestamps::<Self>(Self::STATEMENT_SELECT),
+                (index,),
+                parse_timestamped::<Self>,
+            )
+            .optional()?)
+    }
+}
+
+/// Append the timestamps to the selected columns, or placeholders if they are not tracked.
+/// As the values are parsed from the start of the row, the timestamps are found at its end.
+fn with_timestamps<T: Selectable>(statement: &str) -> String {
+    let timestamps = match T::TIMESTAMPED {
+        true => "created_at, updated_at",
+        false => "NULL, NULL",
+    };
+    match statement.split_once(" FROM ") {
+        Some((columns, remainder)) => format!("{}, {} FROM {}", columns, timestamps, remainder),
+        None => String::from(statement),
+    }
+}
+
+/// Parse a row selected with the statement of `with_timestamps`.
+fn parse_timestamped<T: Selectable>(
+    row: &rusqlite::Row<'_>,
+) -> Result<Timestamped<T::Output>, rusqlite::Error> {
+    let count = row.as_ref().column_count();
+    Ok(Timestamped {
+        value: T::SelectValue::try_from(row).map(T::deserialize_sql)?,
+        created_at: row.get(count - 2)?,
+        updated_at: row.get(count - 1)?,
+    })
 }
 
 /// A element which is referencable as a foreign key. Beside the primary key, it contains a desccription.
 pub trait Referenceable: SelectableByPrimaryKey {
+    /// Select the primary key and the label of all elements. The label may be any SQL expression, i.e. combine multiple columns.
     const STATEMENT_SELECT_NAME: &'static str;
 
-    /// Generate all descriptions.
-    fn generate_descriptions(
-        database: &Database,
-    ) -> Result<Vec<(PrimaryKey<Self>, String)>, Error> {
+    /// Generate the descriptors of all elements.
+    fn generate_descriptors(database: &Database) -> Result<Vec<Descriptor<Self>>, Error> {
         let mut stmt = database.connection.prepare(Self::STATEMENT_SELECT_NAME)?;
-        let iterator = stmt.query_map((), |row| <(PrimaryKey<Self>, String)>::try_from(row))?;
+        let iterator = stmt.query_map((), |row| {
+            <(PrimaryKey<Self>, String)>::try_from(row).map(|(identifier, label)| Descriptor {
+                identifier,
+                label,
+                secondary: None,
+            })
+        })?;
         Ok(iterator.filter_map(|value| value.ok()).collect())
     }
 }
 
+/// The human-readable description of a referenceable element. The parts are kept separately such that templates could format them.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Descriptor<T: Indexable> {
+    pub identifier: PrimaryKey<T>,
+    pub label: String,
+    pub secondary: Option<String>,
+}
+
+impl<T: Indexable> std::fmt::Display for Descriptor<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.secondary {
+            Some(secondary) => write!(f, "{} ({})", self.label, secondary),
+            None => f.write_str(&self.label),
+        }
+    }
+}
+
 /// An trait for creating a default for objects with complex constraints like foreign keys requiering database access.
 pub trait DefaultGenerator {
     /// Create the default element.
@@ -210,8 +449,11 @@ macro_rules! create_database_type {
 
 create_database_type!(bool => "BOOL"; sortable: false);
 create_database_type!(u32 => "INTEGER"; sortable: true);
-create_database_type!(String => "TEXT"; sortable: false);
+create_database_type!(i64 => "INTEGER"; sortable: true);
+create_database_type!(String => "TEXT"; sortable: true);
 create_database_type!(crate::backend::Date => "DATETIME"; sortable: true);
+create_database_type!(crate::backend::Email => "TEXT"; sortable: true);
+create_database_type!(crate::backend::FutureDate => "DATETIME"; sortable: true);
 create_database_type!(Vec<u8> => "BLOB"; sortable: false);
 
 impl<T: crate::backend::database::Indexable> DatabaseType
@@ -3,9 +3,10 @@ use std::io::Read;
 use serde::{Deserialize, Serialize};
 
 use crate::backend::database::{
-    Database, DatabaseEntry, DefaultGenerator, Insertable, PrimaryKey, Record,
+    Database, DatabaseEntry, DatabaseType, DefaultGenerator, Descriptor, Insertable, PrimaryKey,
+    Record, Selectable,
 };
-use crate::backend::{person::Person, user::User, Date};
+use crate::backend::{person::Person, user::User, Date, Timestamp};
 
 crate::backend::database::make_struct!(
     #[derive(serde::Serialize, serde::Deserialize)]
@@ -17,7 +18,9 @@ crate::backend::database::make_struct!(
         processed_by: PrimaryKey<User>,
         from_person: PrimaryKey<Person>,
         to_person: PrimaryKey<Person>,
+        #[doc = "The document was already recieved, so this date is past-only."]
         recieved: Date,
+        #[doc = "The document was already processed, so this date is past-only."]
         processed: Date,
         description: String
     } ("FOREIGN KEY(processed_by) REFERENCES users(id), FOREIGN KEY(from_person) REFERENCES persons(id), FOREIGN KEY(to_person) REFERENCES persons(id)")
@@ -45,7 +48,156 @@ impl Document {
             .expect("reading blobs into allocated vector should not fail");
         Ok(container)
     }
+
+    /// Select the metadata of all documents which are not used as evidence by any entry.
+    /// Archived documents are kept deliberately and therefore not reported.
+    pub fn find_orphans(
+        database: &Database,
+    ) -> Result<Vec<Metadata>, crate::backend::database::Error> {
+        const SELECT_ORPHANS_QUERY: &str = const_format::concatcp!(
+            <Document as Selectable>::STATEMENT_SELECT_ALL,
+            " WHERE archived = FALSE AND NOT EXISTS (SELECT 1 FROM ",
+            crate::backend::accounting::Entry::TABLE_NAME,
+            " WHERE evidence = ",
+            Document::TABLE_NAME,
+            ".id) ORDER BY id"
+        );
+
+        let mut stmt = database.connection.prepare(SELECT_ORPHANS_QUERY)?;
+        let iterator = stmt.query_map((), |row| {
+            <Document as Selectable>::SelectValue::try_from(row)
+                .map(<Document as Selectable>::deserialize_sql)
+        })?;
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Replace the stored file while keeping all the metadata. Returns false if the document does not exist.
+    pub fn replace_file(
+        database: &Database,
+        identifier: PrimaryKey<Self>,
+        content: &[u8],
+    ) -> Result<bool, crate::backend::database::Error> {
+        const REPLACE_FILE_QUERY: &str = const_format::formatcp!(
+            "UPDATE {} SET document = ? WHERE id = ?",
+            Document::TABLE_NAME
+        );
+
+        Ok(database
+            .connection
+            .execute(REPLACE_FILE_QUERY, (content, identifier.raw_index()))?
+            == 1)
+    }
+}
+
+/// The kinds of files which could be archived as a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocumentType {
+    Pdf,
+    Png,
+    Jpeg,
+    /// Office Open XML documents like Word files.
+    Docx,
+    /// Office Open XML spreadsheets like Excel files.
+    Xlsx,
+    /// OpenDocument texts.
+    Odt,
 }
+
+impl DocumentType {
+    /// All the supported types.
+    pub const ALL: &'static [DocumentType] = &[
+        DocumentType::Pdf,
+        DocumentType::Png,
+        DocumentType::Jpeg,
+        DocumentType::Docx,
+        DocumentType::Xlsx,
+        DocumentType::Odt,
+    ];
+
+    /// Parse a type from its name like "pdf".
+    pub fn parse(value: &str) -> Option<Self> {
+        match value
+            .trim()
+            .trim_start_matches('.')
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "pdf" => Some(DocumentType::Pdf),
+            "png" => Some(DocumentType::Png),
+            "jpg" | "jpeg" => Some(DocumentType::Jpeg),
+            "docx" => Some(DocumentType::Docx),
+            "xlsx" => Some(DocumentType::Xlsx),
+            "odt" => Some(DocumentType::Odt),
+            _ => None,
+        }
+    }
+
+    /// The extension of the files including the leading dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DocumentType::Pdf => ".pdf",
+            DocumentType::Png => ".png",
+            DocumentType::Jpeg => ".jpg",
+            DocumentType::Docx => ".docx",
+            DocumentType::Xlsx => ".xlsx",
+            DocumentType::Odt => ".odt",
+        }
+    }
+
+    /// The MIME type of the files.
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            DocumentType::Pdf => "application/pdf",
+            DocumentType::Png => "image/png",
+            DocumentType::Jpeg => "image/jpeg",
+            DocumentType::Docx => {
+                "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+            }
+            DocumentType::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+            DocumentType::Odt => "application/vnd.oasis.opendocument.text",
+        }
+    }
+
+    /// Check the magic number of the content. Office files share the one of ZIP archives and are told apart by their entries.
+    pub fn matches(&self, content: &[u8]) -> bool {
+        match self {
+            DocumentType::Pdf => content.starts_with(b"%PDF-"),
+            DocumentType::Png => content.starts_with(b"\x89PNG\r\n\x1a\n"),
+            DocumentType::Jpeg => content.starts_with(b"\xff\xd8\xff"),
+            DocumentType::Docx => Self::inspect_archive(content, |archive| {
+                archive.by_name("word/document.xml").is_ok()
+            }),
+            DocumentType::Xlsx => Self::inspect_archive(content, |archive| {
+                archive.by_name("xl/workbook.xml").is_ok()
+            }),
+            // OpenDocument files name their type in an uncompressed entry.
+            DocumentType::Odt => Self::inspect_archive(content, |archive| {
+                let mut media_type = String::new();
+                archive.by_name("mimetype").is_ok_and(|mut entry| {
+                    std::io::Read::read_to_string(&mut entry, &mut media_type).is_ok()
+                }) && media_type.trim() == self.media_type()
+            }),
+        }
+    }
+
+    /// Check the entries of a ZIP archive. Any other content is rejected.
+    fn inspect_archive(
+        content: &[u8],
+        check: impl FnOnce(&mut zip::ZipArchive<std::io::Cursor<&[u8]>>) -> bool,
+    ) -> bool {
+        content.starts_with(b"PK\x03\x04")
+            && zip::ZipArchive::new(std::io::Cursor::new(content))
+                .is_ok_and(|mut archive| check(&mut archive))
+    }
+
+    /// Find the first of the allowed types matching the content.
+    pub fn detect(allowed: &[DocumentType], content: &[u8]) -> Option<Self> {
+        allowed.iter().copied().find(|value| value.matches(content))
+    }
+}
+
 impl crate::backend::database::Selectable for Document {
     /// The public output. Other than the value itself, this value should be renderable in JSON without leaking sensible information.
     type Output = Metadata;
@@ -59,12 +211,36 @@ impl crate::backend::database::Selectable for Document {
         Date,
         Date,
         String,
+        u64,
     );
 
-    const SORTABLE_COLUMNS: &'static [&'static str] = &["id", "recieved", "processed"];
+    const OUTPUT_FIELDS: &'static [&'static str] = &[
+        "identifier",
+        "processed_by",
+        "from_person",
+        "to_person",
+        "recieved",
+        "processed",
+        "description",
+        "size_bytes",
+    ];
+    const SORTABLE_COLUMNS: &'static [&'static str] =
+        &["id", "recieved", "processed", "created_at", "updated_at"];
+    const SORTABLE_COLUMN_TYPES: &'static [&'static str] = &[
+        <PrimaryKey<Document> as DatabaseType>::RAW_COLUMN_VALUE,
+        <Date as DatabaseType>::RAW_COLUMN_VALUE,
+        <Date as DatabaseType>::RAW_COLUMN_VALUE,
+        <Timestamp as DatabaseType>::RAW_COLUMN_VALUE,
+        <Timestamp as DatabaseType>::RAW_COLUMN_VALUE,
+    ];
+
+    /// The statement for selecting all entries. The size is queried without loading the document.
+    const STATEMENT_SELECT_ALL: &'static str = "SELECT id, processed_by, from_person, to_person, recieved, processed, description, length(document) FROM documents";
 
-    /// The statement for selecting all entries.
-    const STATEMENT_SELECT_ALL: &'static str = "SELECT id, processed_by, from_person, to_person, recieved, processed, description FROM documents";
+    /// Documents must be preserved, so they are archived instead of removed.
+    const ARCHIVABLE: bool = true;
+    const TIMESTAMPED: bool = true;
+    const RANGE_COLUMN: Option<&'static str> = Some("recieved");
 
     /// Deserialize the database value into a Record.
     fn deserialize_sql<'a>(value: Self::SelectValue<'a>) -> Self::Output {
@@ -76,8 +252,13 @@ impl crate::backend::database::Selectable for Document {
             recieved: value.4,
             processed: value.5,
             description: value.6,
+            size_bytes: value.7,
         }
     }
+
+    fn primary_key(output: &Self::Output) -> PrimaryKey<Self> {
+        output.identifier
+    }
 }
 
 impl crate::backend::database::SelectableByPrimaryKey for Document {
@@ -90,18 +271,17 @@ impl crate::backend::database::SelectableByPrimaryKey for Document {
 impl crate::backend::database::Referenceable for Document {
     const STATEMENT_SELECT_NAME: &'static str = "";
 
-    fn generate_descriptions(
+    fn generate_descriptors(
         database: &Database,
-    ) -> Result<Vec<(PrimaryKey<Self>, String)>, super::database::Error> {
+    ) -> Result<Vec<Descriptor<Self>>, super::database::Error> {
         const QUERY: &'static str = "SELECT id, processed, description FROM documents";
         let mut stmt = database.connection.prepare(QUERY)?;
         let iterator = stmt.query_map((), |row| {
             <(PrimaryKey<Self>, Date, Option<String>)>::try_from(row).map(
-                |(primary_key, date, description)| {
-                    (
-                        primary_key,
-                        format!("{} {}", date, description.unwrap_or_default()),
-                    )
+                |(identifier, date, description)| Descriptor {
+                    identifier,
+                    label: description.unwrap_or_default(),
+                    secondary: Some(date.to_string()),
                 },
             )
         })?;
@@ -117,7 +297,7 @@ impl DefaultGenerator for Document {
             .expect("valid user");
 
         Document {
-            document: Vec::default(),
+            document: b"%PDF-1.4\n%%EOF".to_vec(),
             processed_by: user,
             from_person: person,
             to_person: person,
@@ -138,6 +318,7 @@ pub struct Metadata {
     pub recieved: Date,
     pub processed: Date,
     pub description: String,
+    pub size_bytes: u64,
 }
 
 impl From<Record<Document>> for Metadata {
@@ -152,14 +333,18 @@ impl From<Record<Document>> for Metadata {
             recieved: value.recieved,
             processed: value.processed,
             description: value.description,
+            size_bytes: value.document.len() as u64,
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Document;
-    use crate::backend::database::{DefaultGenerator, Insertable};
+    use super::{Document, DocumentType};
+    use crate::backend::database::{
+        DefaultGenerator, Insertable, Referenceable, Selectable, SelectableByPrimaryKey,
+    };
+    use crate::backend::Date;
 
     #[test]
     fn test_availability_in_default_migrations() {
@@ -168,4 +353,160 @@ mod tests {
             .insert(&database)
             .expect("insert sucessfull");
     }
+
+    #[test]
+    fn test_descriptors_contain_date() {
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        let mut document = Document::create_default(&database);
+        document.processed = Date::try_from("2024-03-01").expect("valid date");
+        document.description = String::from("Invoice");
+        let identifier = document.insert(&database).expect("insert sucessfull");
+
+        let descriptors = Document::generate_descriptors(&database).expect("valid descriptors");
+        assert_eq!(descriptors.len(), 1);
+        assert_eq!(descriptors[0].identifier, identifier);
+        assert_eq!(descriptors[0].label, "Invoice");
+        assert_eq!(descriptors[0].secondary.as_deref(), Some("2024-03-01"));
+        assert_eq!(descriptors[0].to_string(), "Invoice (2024-03-01)");
+    }
+
+    #[test]
+    fn test_size_bytes() {
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        let mut document = Document::create_default(&database);
+        document.document = b"%PDF-1.4 with some content".to_vec();
+        let length = document.document.len() as u64;
+        let identifier = document.insert(&database).expect("insert sucessfull");
+
+        let metadata = Document::select(&database, identifier).expect("valid metadata");
+        assert_eq!(metadata.size_bytes, length);
+    }
+
+    #[test]
+    fn test_document_types() {
+        let png = b"\x89PNG\r\n\x1a\n....";
+        assert_eq!(
+            DocumentType::detect(&[DocumentType::Pdf, DocumentType::Png], png),
+            Some(DocumentType::Png)
+        );
+        assert_eq!(DocumentType::detect(&[DocumentType::Pdf], png), None);
+        assert_eq!(DocumentType::detect(DocumentType::ALL, b"GIF89a"), None);
+        assert_eq!(DocumentType::parse(".JPEG"), Some(DocumentType::Jpeg));
+        assert_eq!(DocumentType::parse("gif"), None);
+    }
+
+    #[test]
+    fn test_office_document_types() {
+        use std::io::Write;
+
+        let archive = |entries: &[(&str, &str)]| {
+            let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            for (name, content) in entries {
+                archive
+                    .start_file(*name, zip::write::FileOptions::default())
+                    .expect("valid entry");
+                archive.write_all(content.as_bytes()).expect("valid entry");
+            }
+            archive.finish().expect("valid archive").into_inner()
+        };
+        let docx = archive(&[("[Content_Types].xml", ""), ("word/document.xml", "")]);
+        let xlsx = archive(&[("[Content_Types].xml", ""), ("xl/workbook.xml", "")]);
+        let odt = archive(&[("mimetype", "application/vnd.oasis.opendocument.text")]);
+        let other = archive(&[("readme.txt", "")]);
+
+        assert_eq!(
+            DocumentType::detect(DocumentType::ALL, &docx),
+            Some(DocumentType::Docx)
+        );
+        assert_eq!(
+            DocumentType::detect(DocumentType::ALL, &xlsx),
+            Some(DocumentType::Xlsx)
+        );
+        assert_eq!(
+            DocumentType::detect(DocumentType::ALL, &odt),
+            Some(DocumentType::Odt)
+        );
+        assert_eq!(DocumentType::detect(&[DocumentType::Docx], &xlsx), None);
+        assert_eq!(DocumentType::detect(DocumentType::ALL, &other), None);
+        assert_eq!(DocumentType::detect(DocumentType::ALL, b"PK\x03\x04"), None);
+    }
+
+    #[test]
+    fn test_find_orphans() {
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        let orphan = Document::create_default(&database)
+            .insert(&database)
+            .expect("insert sucessfull");
+        let entry = crate::backend::accounting::Entry::create_default(&database);
+        let evidence = entry.evidence;
+        entry.insert(&database).expect("valid entry");
+
+        let orphans = Document::find_orphans(&database).expect("valid query");
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].identifier, orphan);
+        assert_ne!(orphans[0].identifier, evidence);
+
+        // Archived documents are no longer reported.
+        assert_eq!(Document::set_archived(&database, orphan, true), Ok(true));
+        assert_eq!(Document::find_orphans(&database), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_replace_file_missing() {
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        assert_eq!(
+            Document::replace_file(
+                &database,
+                crate::backend::database::PrimaryKey::from(42),
+                b"%PDF-1.7"
+            ),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn test_select_within() {
+        use crate::backend::{
+            database::Selectable, Column, FutureDate, Order, Pagination, WhereClause,
+        };
+
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        let identifiers = ["2024-01-10", "2024-02-10", "2024-03-10"].map(|recieved| {
+            let mut document = Document::create_default(&database);
+            document.recieved = Date::try_from(recieved).expect("valid date");
+            document.insert(&database).expect("valid document")
+        });
+        let select = |from: Option<&str>, to: Option<&str>| {
+            let date = |value: &str| FutureDate::try_from(value).expect("valid date");
+            let filter = WhereClause::new().within(
+                Column::try_from("recieved").expect("valid column"),
+                from.map(date),
+                to.map(date),
+            );
+            Document::select_all_sorted(
+                &database,
+                Pagination::new("recieved", 0, Default::default(), Order::Ascending)
+                    .expect("valid pagination"),
+                Some(&filter),
+            )
+            .expect("valid query")
+            .into_iter()
+            .map(|document| document.identifier)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            select(Some("2024-02-01"), Some("2024-02-28")),
+            vec![identifiers[1]]
+        );
+        assert_eq!(
+            select(Some("2024-02-10"), Some("2024-03-10")),
+            vec![identifiers[1], identifiers[2]]
+        );
+        assert_eq!(
+            select(None, Some("2024-02-10")),
+            vec![identifiers[0], identifiers[1]]
+        );
+        assert_eq!(select(None, None), identifiers.to_vec());
+    }
 }
@@ -1,10 +1,15 @@
 pub mod database;
 pub mod document;
 pub mod person;
+pub mod upload;
 pub mod user;
 
 pub mod accounting;
+pub mod audit;
 
 mod util;
 
-pub use self::util::{Column, Date, DateError, Limit, Order, Pagination, PaginationError};
+pub use self::util::{
+    Column, ColumnDescription, Date, DateError, Email, EmailError, FutureDate, Limit, Order,
+    Pagination, PaginationError, Timestamp, WhereClause,
+};
@@ -1,19 +1,29 @@
+use rusqlite::OptionalExtension;
+
 use crate::backend::{
-    database::{Database, DatabaseEntry, Error, PrimaryKey},
-    Date,
+    database::{Database, DatabaseEntry, Error, PrimaryKey, Record, Selectable},
+    Date, Email, Limit, Pagination, WhereClause,
 };
 
 crate::backend::database::make_struct!(
     #[derive(Default, serde::Serialize, serde::Deserialize)]
     #[table("persons")]
     #[dependencies(())]
-    #[impl_select(true, testing: true, description: "name")]
+    // Namesakes are told apart by their email, if known.
+    #[impl_select(true, testing: true, description: "name || COALESCE(' <' || email || '>', '')", archivable: true, timestamps: true)]
     Person {
         name: String,
         address: String,
-        email: Option<String>,
+        email: Option<Email>,
+        #[doc = "A birthday is past-only, so future dates are rejected."]
         birthday: Option<Date>,
-        comment: Option<String>
+        comment: Option<String>,
+        telephone: Option<String>,
+        mobile: Option<String>,
+        company: Option<String>,
+        #[doc = "The role of the person within the company."]
+        role: Option<String>,
+        website: Option<String>
     }
 );
 
@@ -21,16 +31,223 @@ crate::backend::database::make_struct!(
     #[derive(Default, serde::Serialize, serde::Deserialize)]
     #[table("groups")]
     #[dependencies(())]
-    #[impl_select(true, testing: true, description: "description")]
+    #[impl_select(true, testing: true, description: "description", timestamps: true)]
     Group {
         description: String
     }
 );
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl Person {
+    /// Find the persons matching the filter whose name, address, email, or comment contains the query literally.
+    pub fn search(
+        database: &Database,
+        query: &str,
+        filter: WhereClause<Self>,
+        pagination: Pagination<Self>,
+    ) -> Result<Vec<<Self as Selectable>::Output>, Error> {
+        let filter = filter.contains(&["name", "address", "email", "comment"], query);
+        Self::select_all_sorted(database, pagination, Some(&filter))
+    }
+
+    /// Merge a duplicated person into the one to keep, moving its documents, memberships, and users before removing it.
+    /// Memberships in groups both persons belong to are kept for the remaining person only.
+    /// Returns `None` without any change if one of the persons does not exist or both are the same.
+    pub fn merge(
+        database: &Database,
+        keep: PrimaryKey<Person>,
+        remove: PrimaryKey<Person>,
+    ) -> Result<Option<MergeSummary>, Error> {
+        if keep == remove {
+            return Ok(None);
+        }
+
+        database.transaction(false, |database| {
+            let connection = &database.connection;
+            let existing: u32 = connection.query_row(
+                "SELECT COUNT(*) FROM persons WHERE id IN (?, ?)",
+                (keep.0, remove.0),
+                |row| row.get(0),
+            )?;
+            if existing != 2 {
+                return Ok(None);
+            }
+
+            let documents = connection.query_row(
+                "SELECT COUNT(*) FROM documents WHERE from_person = ?1 OR to_person = ?1",
+                (remove.0,),
+                |row| row.get(0),
+            )?;
+            connection.execute(
+                "UPDATE documents SET from_person = ?1 WHERE from_person = ?2",
+                (keep.0, remove.0),
+            )?;
+            connection.execute(
+                "UPDATE documents SET to_person = ?1 WHERE to_person = ?2",
+                (keep.0, remove.0),
+            )?;
+            let memberships = connection.execute(
+                "UPDATE OR IGNORE memberships SET person_id = ?1 WHERE person_id = ?2",
+                (keep.0, remove.0),
+            )?;
+            let duplicated_memberships =
+                connection.execute("DELETE FROM memberships WHERE person_id = ?", (remove.0,))?;
+            let users = connection.execute(
+                "UPDATE users SET related_to = ?1 WHERE related_to = ?2",
+                (keep.0, remove.0),
+            )?;
+            connection.execute("DELETE FROM persons WHERE id = ?", (remove.0,))?;
+
+            Ok(Some(MergeSummary {
+                removed: remove,
+                documents,
+                memberships,
+                duplicated_memberships,
+                users,
+            }))
+        })
+    }
+
+    /// Create a vCard 3.0 of the person, i.e. for importing it into an address book. Unknown values are omitted.
+    pub fn to_vcard(&self, identifier: PrimaryKey<Person>) -> String {
+        let name = escape_vcard(&self.name);
+        let mut lines = vec![
+            String::from("BEGIN:VCARD"),
+            String::from("VERSION:3.0"),
+            format!("UID:shelby-person-{}", identifier.0),
+            format!("FN:{}", name),
+            format!("N:;{};;;", name),
+        ];
+        if !self.address.is_empty() {
+            lines.push(format!("ADR:;;{};;;;", escape_vcard(&self.address)));
+        }
+        if let Some(email) = self.email.as_ref().map(Email::as_str) {
+            lines.push(format!("EMAIL;TYPE=INTERNET:{}", escape_vcard(email)));
+        }
+        for (property, value) in [
+            ("TEL;TYPE=VOICE", &self.telephone),
+            ("TEL;TYPE=CELL", &self.mobile),
+            ("ORG", &self.company),
+            ("TITLE", &self.role),
+            ("URL", &self.website),
+        ] {
+            if let Some(value) = value.as_deref().filter(|value| !value.is_empty()) {
+                lines.push(format!("{}:{}", property, escape_vcard(value)));
+            }
+        }
+        if let Some(birthday) = &self.birthday {
+            lines.push(format!("BDAY:{}", birthday));
+        }
+        if let Some(comment) = self.comment.as_deref().filter(|value| !value.is_empty()) {
+            lines.push(format!("NOTE:{}", escape_vcard(comment)));
+        }
+        lines.push(String::from("END:VCARD"));
+
+        lines.iter().map(|line| fold_vcard_line(line)).collect()
+    }
+}
+
+/// Escape the characters with a special meaning within a vCard value.
+fn escape_vcard(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '\\' | ',' | ';' => {
+                escaped.push('\\');
+                escaped.push(character);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Terminate a vCard line, folding it into lines of at most 75 bytes as required by RFC 2425.
+fn fold_vcard_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut length = 0;
+    for character in line.chars() {
+        if length + character.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(character);
+        length += character.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+impl Group {
+    /// Select all groups the person is not a member of yet, ordered by their creation.
+    pub fn available_for_person(
+        database: &Database,
+        person: PrimaryKey<Person>,
+    ) -> Result<Vec<Record<Self>>, Error> {
+        const SELECT_AVAILABLE_QUERY: &str = "SELECT groups.id, groups.description FROM groups LEFT JOIN memberships ON memberships.group_id = groups.id AND memberships.person_id = ? WHERE memberships.group_id IS NULL ORDER BY groups.id";
+
+        let mut stmt = database.connection.prepare(SELECT_AVAILABLE_QUERY)?;
+        let iterator = stmt.query_map((person.0,), |row| {
+            <Self as Selectable>::SelectValue::try_from(row).map(Self::deserialize_sql)
+        })?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Remove a group. A group which still has members is only removed if forced, dropping its memberships first.
+    /// Returns `None` if the group does not exist.
+    pub fn remove(
+        database: &Database,
+        group: PrimaryKey<Group>,
+        force: bool,
+    ) -> Result<Option<GroupRemoval>, Error> {
+        database.transaction(false, |database| {
+            let connection = &database.connection;
+            let members: usize = connection.query_row(
+                "SELECT COUNT(*) FROM memberships WHERE group_id = ?",
+                (group.0,),
+                |row| row.get(0),
+            )?;
+            if members > 0 && !force {
+                return Ok(Some(GroupRemoval::HasMembers(members)));
+            }
+
+            let memberships =
+                connection.execute("DELETE FROM memberships WHERE group_id = ?", (group.0,))?;
+            match connection.execute("DELETE FROM groups WHERE id = ?", (group.0,))? {
+                0 => Ok(None),
+                _ => Ok(Some(GroupRemoval::Removed(memberships))),
+            }
+        })
+    }
+}
+
+/// The result of removing a group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupRemoval {
+    /// The group was removed alongside the given number of memberships.
+    Removed(usize),
+    /// The group was kept as it still has the given number of members.
+    HasMembers(usize),
+}
+
+/// The references moved while merging two persons.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct MergeSummary {
+    pub removed: PrimaryKey<Person>,
+    pub documents: usize,
+    pub memberships: usize,
+    /// Memberships dropped as the remaining person was already part of the group.
+    pub duplicated_memberships: usize,
+    pub users: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Membership {
     pub person: PrimaryKey<Person>,
     pub group: PrimaryKey<Group>,
+    /// The last change of the membership, which is past-only.
     pub updated: Option<Date>,
     pub comment: Option<String>,
 }
@@ -92,12 +309,61 @@ impl Membership {
         Ok(iterator.filter_map(|value| value.ok()).collect())
     }
 
-    /// Insert a membership into the database.
-    pub fn insert(&self, database: &Database) -> Result<usize, Error> {
-        Ok(database.connection.execute(
+    /// Find the most recently updated members of a group. Memberships never updated come last.
+    pub fn recent_members(
+        database: &Database,
+        group: PrimaryKey<Group>,
+        limit: Limit,
+    ) -> Result<Vec<Membership>, Error> {
+        let mut stmt = database.connection.prepare(
+            "SELECT person_id, updated, comment FROM memberships WHERE group_id = ? ORDER BY updated DESC NULLS LAST, rowid ASC LIMIT ?",
+        )?;
+
+        let iterator = stmt.query_map((group.0, usize::from(limit)), |row| {
+            Ok(Membership {
+                person: PrimaryKey::from(row.get::<usize, i64>(0)?),
+                group,
+                updated: row.get(1)?,
+                comment: row.get(2)?,
+            })
+        })?;
+
+        Ok(iterator.filter_map(|value| value.ok()).collect())
+    }
+
+    /// Find the membership of a person in a group, if existing.
+    pub fn find(
+        database: &Database,
+        person: PrimaryKey<Person>,
+        group: PrimaryKey<Group>,
+    ) -> Result<Option<Membership>, Error> {
+        Ok(database
+            .connection
+            .query_row(
+                "SELECT updated, comment FROM memberships WHERE person_id = ? AND group_id = ?",
+                (person.0, group.0),
+                |row| {
+                    Ok(Membership {
+                        person,
+                        group,
+                        updated: row.get(0)?,
+                        comment: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    /// Insert a membership into the database, returning the composite key of the new pairing.
+    pub fn insert(
+        &self,
+        database: &Database,
+    ) -> Result<(PrimaryKey<Person>, PrimaryKey<Group>), Error> {
+        database.connection.execute(
             "INSERT INTO memberships (person_id, group_id, updated, comment) VALUES (?, ?, ?, ?)",
             (self.person.0, self.group.0, &self.updated, &self.comment),
-        )?)
+        )?;
+        Ok((self.person, self.group))
     }
 
     /// Remove a membership from the database.
@@ -116,6 +382,7 @@ impl Membership {
 #[cfg(test)]
 mod membership_tests {
     use crate::backend::database::{Database, DatabaseEntry, Insertable, PrimaryKey};
+    use crate::backend::{Date, Limit};
 
     use super::{Group, Membership, Person};
 
@@ -174,7 +441,7 @@ mod membership_tests {
             comment: Some(String::from("Example")),
         };
 
-        membership.insert(&database).expect("Valid insert");
+        assert_eq!(membership.insert(&database), Ok((p1, g1)));
 
         let memberships_of_group = Membership::find_all_members(&database, g1.clone()).unwrap();
         assert_eq!(memberships_of_group.len(), 1);
@@ -183,6 +450,8 @@ mod membership_tests {
         let memberships_of_person = Membership::find_all_memberships(&database, p1).unwrap();
         assert_eq!(memberships_of_person.len(), 1);
         assert_eq!(memberships_of_person[0], membership);
+
+        assert_eq!(Membership::find(&database, p1, g1), Ok(Some(membership)));
     }
 
     #[test]
@@ -207,6 +476,37 @@ mod membership_tests {
         assert_eq!(Membership::find_all_memberships(&database, p1), Ok(vec![]));
     }
 
+    #[test]
+    fn test_recent_members() {
+        let (database, (p1, p2, p3), g1) = setup_database();
+        for (person, updated) in [
+            (p1, None),
+            (p2, Some("2023-05-01")),
+            (p3, Some("2024-02-01")),
+        ] {
+            Membership {
+                person,
+                group: g1,
+                updated: updated.map(|value| Date::try_from(value).expect("valid date")),
+                comment: None,
+            }
+            .insert(&database)
+            .expect("Valid insert");
+        }
+
+        let members: Vec<_> = Membership::recent_members(&database, g1, Limit::DEFAULT)
+            .expect("valid query")
+            .into_iter()
+            .map(|membership| membership.person)
+            .collect();
+        assert_eq!(members, vec![p3, p2, p1]);
+
+        let members =
+            Membership::recent_members(&database, g1, Limit::from(1)).expect("valid query");
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].person, p3);
+    }
+
     #[test]
     fn test_find_all_members_with_no_members() {
         let (database, _, group) = setup_database();
@@ -219,3 +519,296 @@ mod membership_tests {
         assert_eq!(Membership::find_all_memberships(&database, p1), Ok(vec![]));
     }
 }
+
+#[cfg(test)]
+mod group_tests {
+    use crate::backend::database::{
+        Database, Insertable, PrimaryKey, Referenceable, SelectableByPrimaryKey,
+    };
+
+    use super::{Group, GroupRemoval, Membership, Person};
+
+    #[test]
+    fn test_remove() {
+        let database = Database::in_memory().expect("valid database");
+        let person = Person::default().insert(&database).expect("valid person");
+        let empty = Group::default().insert(&database).expect("valid group");
+        let group = Group::default().insert(&database).expect("valid group");
+        Membership {
+            person,
+            group,
+            updated: None,
+            comment: None,
+        }
+        .insert(&database)
+        .expect("valid membership");
+
+        assert_eq!(
+            Group::remove(&database, empty, false),
+            Ok(Some(GroupRemoval::Removed(0)))
+        );
+        assert_eq!(Group::try_select(&database, empty.raw_index()), Ok(None));
+
+        assert_eq!(
+            Group::remove(&database, group, false),
+            Ok(Some(GroupRemoval::HasMembers(1)))
+        );
+        assert_eq!(
+            Membership::find_all_members(&database, group).map(|members| members.len()),
+            Ok(1)
+        );
+
+        assert_eq!(
+            Group::remove(&database, group, true),
+            Ok(Some(GroupRemoval::Removed(1)))
+        );
+        assert_eq!(Group::try_select(&database, group.raw_index()), Ok(None));
+        assert_eq!(
+            Membership::find_all_memberships(&database, person),
+            Ok(vec![])
+        );
+
+        assert_eq!(
+            Group::remove(&database, PrimaryKey::from(42), true),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_available_for_person() {
+        let database = Database::in_memory().expect("valid database");
+        let person = Person::default().insert(&database).expect("valid person");
+        let other = Person::default().insert(&database).expect("valid person");
+        let joined = Group::default().insert(&database).expect("valid group");
+        let available = Group::default().insert(&database).expect("valid group");
+        for (person, group) in [(person, joined), (other, available)] {
+            Membership {
+                person,
+                group,
+                updated: None,
+                comment: None,
+            }
+            .insert(&database)
+            .expect("valid membership");
+        }
+
+        let groups: Vec<_> = Group::available_for_person(&database, person)
+            .expect("valid query")
+            .into_iter()
+            .map(|group| group.identifier)
+            .collect();
+        assert_eq!(groups, vec![available]);
+    }
+
+    #[test]
+    fn test_group_descriptors() {
+        let database = Database::in_memory().expect("valid database");
+        for description in ["Board", "Members"] {
+            Group {
+                description: String::from(description),
+            }
+            .insert(&database)
+            .expect("valid group");
+        }
+
+        let labels: Vec<String> = Group::generate_descriptors(&database)
+            .expect("valid descriptors")
+            .into_iter()
+            .map(|descriptor| descriptor.label)
+            .collect();
+        assert_eq!(labels, vec![String::from("Board"), String::from("Members")]);
+    }
+}
+
+#[cfg(test)]
+mod person_tests {
+    use crate::backend::{
+        database::{
+            Database, DefaultGenerator, Insertable, PrimaryKey, Selectable, SelectableByPrimaryKey,
+        },
+        document::Document,
+        user::User,
+        Date, Email, Limit, Order, Pagination, WhereClause,
+    };
+
+    use super::{Group, Membership, MergeSummary, Person};
+
+    #[test]
+    fn test_merge() {
+        let database = Database::in_memory().expect("valid database");
+        let keep = Person::default().insert(&database).expect("valid person");
+        let remove = Person::default().insert(&database).expect("valid person");
+
+        let mut document = Document::create_default(&database);
+        document.from_person = remove;
+        document.to_person = remove;
+        let document = document.insert(&database).expect("valid document");
+
+        let mut user = User::create_default(&database);
+        user.related_to = Some(remove);
+        let user = user.insert(&database).expect("valid user");
+
+        let shared = Group::default().insert(&database).expect("valid group");
+        let other = Group::default().insert(&database).expect("valid group");
+        for (person, group) in [(keep, shared), (remove, shared), (remove, other)] {
+            Membership {
+                person,
+                group,
+                updated: None,
+                comment: None,
+            }
+            .insert(&database)
+            .expect("valid membership");
+        }
+
+        assert_eq!(
+            Person::merge(&database, keep, remove),
+            Ok(Some(MergeSummary {
+                removed: remove,
+                documents: 1,
+                memberships: 1,
+                duplicated_memberships: 1,
+                users: 1
+            }))
+        );
+
+        let document = Document::try_select(&database, document.raw_index())
+            .expect("valid query")
+            .expect("document exists");
+        assert_eq!((document.from_person, document.to_person), (keep, keep));
+        let user = User::try_select(&database, user.raw_index())
+            .expect("valid query")
+            .expect("user exists");
+        assert_eq!(user.related_to, Some(keep));
+
+        let mut groups: Vec<PrimaryKey<Group>> = Membership::find_all_memberships(&database, keep)
+            .expect("valid query")
+            .into_iter()
+            .map(|membership| membership.group)
+            .collect();
+        groups.sort_by_key(|group| group.raw_index());
+        assert_eq!(groups, vec![shared, other]);
+        assert_eq!(Person::try_select(&database, remove.raw_index()), Ok(None));
+    }
+
+    #[test]
+    fn test_descriptors_with_email() {
+        use crate::backend::database::Referenceable;
+
+        let database = Database::in_memory().expect("valid database");
+        for email in [Some("max@example.com"), None] {
+            Person {
+                name: String::from("Max Mustermann"),
+                email: email.map(|email| Email::try_from(email).expect("valid address")),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person");
+        }
+
+        let labels: Vec<String> = Person::generate_descriptors(&database)
+            .expect("valid descriptors")
+            .into_iter()
+            .map(|descriptor| descriptor.label)
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                String::from("Max Mustermann <max@example.com>"),
+                String::from("Max Mustermann")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_invalid() {
+        let database = Database::in_memory().expect("valid database");
+        let keep = Person::default().insert(&database).expect("valid person");
+
+        assert_eq!(Person::merge(&database, keep, keep), Ok(None));
+        assert_eq!(
+            Person::merge(&database, keep, PrimaryKey::from(42)),
+            Ok(None)
+        );
+        assert!(Person::try_select(&database, keep.raw_index())
+            .expect("valid query")
+            .is_some());
+    }
+
+    #[test]
+    fn test_search() {
+        let database = Database::in_memory().expect("valid database");
+        for (name, address, email) in [
+            ("Jane Doe", "Main Street 1", None),
+            ("John Doe", "Side Street 2", Some("john@example.com")),
+            ("50% Club", "Elsewhere", None),
+        ] {
+            Person {
+                name: String::from(name),
+                address: String::from(address),
+                email: email.map(|email| Email::try_from(email).expect("valid address")),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person");
+        }
+
+        let search = |query: &str, offset: usize, limit: usize| {
+            let pagination = Pagination::new("id", offset, Limit::from(limit), Order::Ascending)
+                .expect("valid pagination");
+            Person::search(&database, query, WhereClause::new(), pagination)
+                .expect("valid search")
+                .into_iter()
+                .map(|record| record.value.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search("doe", 0, 10), vec!["Jane Doe", "John Doe"]);
+        assert_eq!(search("side", 0, 10), vec!["John Doe"]);
+        assert_eq!(search("example.com", 0, 10), vec!["John Doe"]);
+        assert_eq!(search("0%", 0, 10), vec!["50% Club"]);
+        assert_eq!(search("_", 0, 10), Vec::<String>::new());
+        assert_eq!(search("Doe", 1, 1), vec!["John Doe"]);
+
+        // The filter applies on top of the query.
+        Person::set_archived(&database, PrimaryKey::from(2), true).expect("valid archive");
+        let pagination =
+            Pagination::new("id", 0, Limit::from(10), Order::Ascending).expect("valid pagination");
+        assert_eq!(
+            Person::search(
+                &database,
+                "doe",
+                WhereClause::new().without_archived(),
+                pagination
+            )
+            .expect("valid search")
+            .into_iter()
+            .map(|record| record.value.name)
+            .collect::<Vec<_>>(),
+            vec!["Jane Doe"]
+        );
+    }
+
+    #[test]
+    fn test_to_vcard() {
+        let person = Person {
+            name: String::from("Doe, Jane"),
+            address: String::from("Main Street 1; Springfield"),
+            email: None,
+            birthday: Some(Date::try_from("1990-01-02").expect("valid date")),
+            comment: Some(format!("Line\n{}", "x".repeat(80))),
+            company: Some(String::from("ACME")),
+            website: Some(String::from("https://example.com")),
+            ..Default::default()
+        };
+        let vcard = person.to_vcard(PrimaryKey::from(42));
+        assert_eq!(
+            vcard,
+            format!(
+                "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:shelby-person-42\r\nFN:Doe\\, Jane\r\nN:;Doe\\, Jane;;;\r\nADR:;;Main Street 1\\; Springfield;;;;\r\nORG:ACME\r\nURL:https://example.com\r\nBDAY:1990-01-02\r\nNOTE:Line\\n{}\r\n {}\r\nEND:VCARD\r\n",
+                "x".repeat(64),
+                "x".repeat(16)
+            )
+        );
+        assert!(vcard.split("\r\n").all(|line| line.len() <= 75));
+    }
+}
@@ -0,0 +1,274 @@
+use rusqlite::OptionalExtension;
+
+use crate::backend::{
+    database::{Database, Error, PrimaryKey},
+    document::Document,
+    person::Person,
+    user::User,
+    Date,
+};
+
+/// A document uploaded in multiple chunks, which is staged until it is complete.
+/// Each chunk is stored in its own row, such that a broken connection only requires resending the last chunk.
+/// Uploads belong to the user who started them and are invisible to everyone else.
+pub struct Upload;
+
+/// The result of appending a chunk to an upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendOutcome {
+    /// The chunk was appended, resulting in the given length.
+    Appended(u64),
+    /// The chunk was not appended as its offset does not match the current length.
+    OffsetMismatch(u64),
+    /// The chunk was not appended as the upload would exceed the maximum length, given the current length.
+    TooLarge(u64),
+}
+
+impl Upload {
+    /// The table of the uploads, i.e. for recording changes in the audit log.
+    pub const TABLE_NAME: &'static str = "document_uploads";
+
+    /// The number of days an upload may take before it is considered abandoned.
+    pub const MAX_AGE_DAYS: u64 = 1;
+
+    /// Start a new upload of the user, returning its identifier. Abandoned uploads are removed beforehand.
+    pub fn start(database: &Database, owner: PrimaryKey<User>) -> Result<i64, Error> {
+        let today = chrono::Utc::now().date_naive();
+        if let Some(cutoff) = today
+            .checked_sub_days(chrono::Days::new(Self::MAX_AGE_DAYS))
+            .and_then(|cutoff| Date::try_from(cutoff).ok())
+        {
+            Upload::expire(database, cutoff)?;
+        }
+
+        database.connection.execute(
+            "INSERT INTO document_uploads (started, owner) VALUES (?, ?)",
+            (Date::today(), owner),
+        )?;
+        Ok(database.connection.last_insert_rowid())
+    }
+
+    /// Remove all uploads started before the given date, returning their number.
+    pub fn expire(database: &Database, started_before: Date) -> Result<usize, Error> {
+        database.transaction(false, |database| {
+            database.connection.execute(
+                "DELETE FROM document_upload_chunks WHERE upload IN (SELECT id FROM document_uploads WHERE started < ?)",
+                (started_before,),
+            )?;
+            Ok(database.connection.execute(
+                "DELETE FROM document_uploads WHERE started < ?",
+                (started_before,),
+            )?)
+        })
+    }
+
+    /// Get the number of bytes uploaded so far, i.e. the offset of the next chunk.
+    pub fn length(
+        database: &Database,
+        upload: i64,
+        owner: PrimaryKey<User>,
+    ) -> Result<Option<u64>, Error> {
+        const LENGTH_QUERY: &str = "SELECT (SELECT COALESCE(SUM(length(data)), 0) FROM document_upload_chunks WHERE upload = document_uploads.id) FROM document_uploads WHERE id = ? AND owner = ?";
+        Ok(database
+            .connection
+            .query_row(LENGTH_QUERY, (upload, owner), |row| row.get(0))
+            .optional()?)
+    }
+
+    /// Append a chunk at the given offset, which must be the current length of the upload.
+    /// The complete upload must not exceed the maximum length, i.e. the limit of a regular file upload.
+    pub fn append(
+        database: &Database,
+        upload: i64,
+        owner: PrimaryKey<User>,
+        offset: u64,
+        chunk: &[u8],
+        max_length: u64,
+    ) -> Result<Option<AppendOutcome>, Error> {
+        database.transaction(false, |database| {
+            let length = match Upload::length(database, upload, owner)? {
+                Some(length) => length,
+                None => return Ok(None),
+            };
+            if length != offset {
+                return Ok(Some(AppendOutcome::OffsetMismatch(length)));
+            }
+            if length + chunk.len() as u64 > max_length {
+                return Ok(Some(AppendOutcome::TooLarge(length)));
+            }
+
+            database.connection.execute(
+                "INSERT INTO document_upload_chunks (upload, offset, data) VALUES (?, ?, ?)",
+                (upload, offset, chunk),
+            )?;
+            Ok(Some(AppendOutcome::Appended(length + chunk.len() as u64)))
+        })
+    }
+
+    /// Load all chunks of the upload in order.
+    pub fn load(
+        database: &Database,
+        upload: i64,
+        owner: PrimaryKey<User>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        if Upload::length(database, upload, owner)?.is_none() {
+            return Ok(None);
+        }
+
+        let mut stmt = database
+            .connection
+            .prepare("SELECT data FROM document_upload_chunks WHERE upload = ? ORDER BY offset")?;
+        let mut rows = stmt.query((upload,))?;
+        let mut data = Vec::new();
+        while let Some(row) = rows.next()? {
+            data.extend(row.get::<usize, Vec<u8>>(0)?);
+        }
+        Ok(Some(data))
+    }
+
+    /// Remove the upload and all of its chunks.
+    pub fn remove(database: &Database, upload: i64) -> Result<bool, Error> {
+        database.transaction(false, |database| {
+            database.connection.execute(
+                "DELETE FROM document_upload_chunks WHERE upload = ?",
+                (upload,),
+            )?;
+            Ok(database
+                .connection
+                .execute("DELETE FROM document_uploads WHERE id = ?", (upload,))?
+                == 1)
+        })
+    }
+}
+
+/// The metadata of a document whose file was uploaded in chunks.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct UploadedDocument {
+    pub processed_by: PrimaryKey<User>,
+    pub from_person: PrimaryKey<Person>,
+    pub to_person: PrimaryKey<Person>,
+    pub recieved: Date,
+    pub processed: Date,
+    pub description: String,
+}
+
+impl UploadedDocument {
+    /// Combine the metadata with the uploaded file.
+    pub fn into_document(self, document: Vec<u8>) -> Document {
+        Document {
+            document,
+            processed_by: self.processed_by,
+            from_person: self.from_person,
+            to_person: self.to_person,
+            recieved: self.recieved,
+            processed: self.processed,
+            description: self.description,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppendOutcome, Upload};
+    use crate::backend::{
+        database::{Database, DefaultGenerator, Insertable},
+        user::User,
+        Date,
+    };
+
+    #[test]
+    fn test_append() {
+        let database = Database::in_memory().expect("valid database");
+        let owner = User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        let upload = Upload::start(&database, owner).expect("valid upload");
+        assert_eq!(Upload::length(&database, upload, owner), Ok(Some(0)));
+
+        assert_eq!(
+            Upload::append(&database, upload, owner, 0, b"Hello, ", 16),
+            Ok(Some(AppendOutcome::Appended(7)))
+        );
+        // Resending the first chunk must not duplicate it.
+        assert_eq!(
+            Upload::append(&database, upload, owner, 0, b"Hello, ", 16),
+            Ok(Some(AppendOutcome::OffsetMismatch(7)))
+        );
+        assert_eq!(
+            Upload::append(&database, upload, owner, 7, b"world\0", 16),
+            Ok(Some(AppendOutcome::Appended(13)))
+        );
+        // The chunks together must not exceed the maximum length.
+        assert_eq!(
+            Upload::append(&database, upload, owner, 13, b"!!!!", 16),
+            Ok(Some(AppendOutcome::TooLarge(13)))
+        );
+        assert_eq!(
+            Upload::load(&database, upload, owner),
+            Ok(Some(b"Hello, world\0".to_vec()))
+        );
+
+        assert_eq!(Upload::remove(&database, upload), Ok(true));
+        assert_eq!(Upload::load(&database, upload, owner), Ok(None));
+        assert_eq!(
+            Upload::append(&database, upload, owner, 0, b"Hello", 16),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_owner() {
+        let database = Database::in_memory().expect("valid database");
+        let owner = User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        let other = User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        let upload = Upload::start(&database, owner).expect("valid upload");
+
+        assert_eq!(Upload::length(&database, upload, other), Ok(None));
+        assert_eq!(
+            Upload::append(&database, upload, other, 0, b"Hello", 16),
+            Ok(None)
+        );
+        assert_eq!(Upload::load(&database, upload, other), Ok(None));
+        assert_eq!(Upload::length(&database, upload, owner), Ok(Some(0)));
+    }
+
+    #[test]
+    fn test_expire() {
+        let database = Database::in_memory().expect("valid database");
+        let owner = User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        let abandoned = Upload::start(&database, owner).expect("valid upload");
+        Upload::append(&database, abandoned, owner, 0, b"Hello", 16).expect("valid chunk");
+        database
+            .connection
+            .execute(
+                "UPDATE document_uploads SET started = ? WHERE id = ?",
+                (Date::try_from("2000-01-01").expect("valid date"), abandoned),
+            )
+            .expect("valid update");
+
+        // Starting another upload removes the abandoned one with its chunks.
+        let upload = Upload::start(&database, owner).expect("valid upload");
+        let count = |table: &str| {
+            database
+                .connection
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), (), |row| {
+                    row.get::<_, usize>(0)
+                })
+                .expect("valid count")
+        };
+        assert_eq!(count("document_uploads"), 1);
+        assert_eq!(count("document_upload_chunks"), 0);
+        assert_eq!(Upload::length(&database, upload, owner), Ok(Some(0)));
+        assert_eq!(
+            Upload::expire(&database, Date::today()),
+            Ok(0),
+            "uploads of today are kept"
+        );
+    }
+}
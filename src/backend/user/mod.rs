@@ -2,9 +2,9 @@ use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 
 use crate::backend::{
-    database::{Database, DatabaseEntry, DefaultGenerator, PrimaryKey, Record},
+    database::{Database, DatabaseEntry, DatabaseType, DefaultGenerator, PrimaryKey, Record},
     person::Person,
-    Date,
+    Date, Timestamp,
 };
 
 mod password_hash;
@@ -19,44 +19,188 @@ crate::backend::database::make_struct!(
         username: String,
         password_hash: PasswordHash,
         active: bool,
+        #[doc = "Users are never created in advance, so this date is past-only."]
         creation_date: Date,
-        related_to: Option<PrimaryKey<Person>>
+        related_to: Option<PrimaryKey<Person>>,
+        #[doc = "The user has to change the password before accessing anything else."]
+        must_change_password: bool,
+        #[doc = "Only administrators may manage users and remove entries."]
+        is_admin: bool
     } ("FOREIGN KEY(related_to) REFERENCES persons(id)")
 );
 
 impl User {
+    /// The statement selecting the full record including the password hash, whose columns are expected by `parse_row`.
+    const STATEMENT_SELECT_RECORD: &'static str = const_format::formatcp!(
+        "SELECT id, username, password_hash, active, creation_date, related_to, must_change_password, is_admin FROM {}",
+        User::TABLE_NAME
+    );
+
     /// Select a user by its name.
     pub fn select_by_name(
         database: &Database,
         name: impl AsRef<str>,
     ) -> Result<Option<Record<Self>>, crate::backend::database::Error> {
         const SELECT_BY_NAME_QUERY: &'static str =
-            const_format::formatcp!("SELECT * FROM {} WHERE username = ?", User::TABLE_NAME);
+            const_format::concatcp!(User::STATEMENT_SELECT_RECORD, " WHERE username = ?");
+
+        Ok(database
+            .connection
+            .query_row(SELECT_BY_NAME_QUERY, (name.as_ref(),), User::parse_row)
+            .optional()?)
+    }
+
+    /// Select a user including its password hash.
+    pub fn select_record(
+        database: &Database,
+        identifier: PrimaryKey<User>,
+    ) -> Result<Option<Record<Self>>, crate::backend::database::Error> {
+        const SELECT_RECORD_QUERY: &str =
+            const_format::concatcp!(User::STATEMENT_SELECT_RECORD, " WHERE id = ?");
 
         Ok(database
             .connection
-            .query_row(SELECT_BY_NAME_QUERY, (name.as_ref(),), |row| {
-                <(
-                    PrimaryKey<User>,
-                    String,
-                    PasswordHash,
-                    bool,
-                    Date,
-                    Option<PrimaryKey<Person>>,
-                )>::try_from(row)
-                .map(|value| Record {
-                    identifier: value.0,
-                    value: User {
-                        username: value.1,
-                        password_hash: value.2,
-                        active: value.3,
-                        creation_date: value.4,
-                        related_to: value.5,
-                    },
-                })
-            })
+            .query_row(SELECT_RECORD_QUERY, (identifier.0,), User::parse_row)
             .optional()?)
     }
+
+    /// Check whether the user has to change the password before accessing anything else.
+    pub fn must_change_password(
+        database: &Database,
+        identifier: PrimaryKey<User>,
+    ) -> Result<bool, crate::backend::database::Error> {
+        const MUST_CHANGE_QUERY: &str = const_format::formatcp!(
+            "SELECT must_change_password FROM {} WHERE id = ?",
+            User::TABLE_NAME
+        );
+
+        Ok(database
+            .connection
+            .query_row(MUST_CHANGE_QUERY, (identifier.0,), |row| row.get(0))
+            .optional()?
+            .unwrap_or(false))
+    }
+
+    /// Require the user to change the password on the next request.
+    pub fn require_password_change(
+        database: &Database,
+        identifier: PrimaryKey<User>,
+        required: bool,
+    ) -> Result<bool, crate::backend::database::Error> {
+        const REQUIRE_CHANGE_QUERY: &str = const_format::formatcp!(
+            "UPDATE {} SET must_change_password = ? WHERE id = ?",
+            User::TABLE_NAME
+        );
+
+        Ok(database
+            .connection
+            .execute(REQUIRE_CHANGE_QUERY, (required, identifier.0))?
+            == 1)
+    }
+
+    /// Replace the password of the user, which fulfills any pending request to change it.
+    pub fn change_password(
+        database: &Database,
+        identifier: PrimaryKey<User>,
+        password_hash: &PasswordHash,
+    ) -> Result<bool, crate::backend::database::Error> {
+        const CHANGE_PASSWORD_QUERY: &str = const_format::formatcp!(
+            "UPDATE {} SET password_hash = ?, must_change_password = FALSE WHERE id = ?",
+            User::TABLE_NAME
+        );
+
+        Ok(database
+            .connection
+            .execute(CHANGE_PASSWORD_QUERY, (password_hash, identifier.0))?
+            == 1)
+    }
+
+    /// Count the users which are both active and administrators, except for the given one.
+    pub fn count_other_active_admins(
+        database: &Database,
+        identifier: PrimaryKey<User>,
+    ) -> Result<usize, crate::backend::database::Error> {
+        const COUNT_ADMINS_QUERY: &str = const_format::formatcp!(
+            "SELECT COUNT(*) FROM {} WHERE active AND is_admin AND id != ?",
+            User::TABLE_NAME
+        );
+
+        Ok(database
+            .connection
+            .query_row(COUNT_ADMINS_QUERY, (identifier.0,), |row| row.get(0))?)
+    }
+
+    /// Remember the time of the login, keeping the time of the login before.
+    pub fn record_login(
+        database: &Database,
+        identifier: PrimaryKey<User>,
+    ) -> Result<bool, crate::backend::database::Error> {
+        const RECORD_LOGIN_QUERY: &str = const_format::formatcp!(
+            "UPDATE {} SET previous_login = last_login, last_login = strftime('%Y-%m-%d %H:%M:%f', 'now') WHERE id = ?",
+            User::TABLE_NAME
+        );
+
+        Ok(database
+            .connection
+            .execute(RECORD_LOGIN_QUERY, (identifier.0,))?
+            == 1)
+    }
+
+    /// Count the rows of each table created after the login before the current one. Without such a login, every row is new.
+    /// The table names are inserted verbatim and must therefore be constants.
+    pub fn whats_new(
+        database: &Database,
+        identifier: PrimaryKey<User>,
+        tables: &[&'static str],
+    ) -> Result<WhatsNew, crate::backend::database::Error> {
+        const PREVIOUS_LOGIN_QUERY: &str = const_format::formatcp!(
+            "SELECT previous_login FROM {} WHERE id = ?",
+            User::TABLE_NAME
+        );
+
+        let since: Option<chrono::NaiveDateTime> = database
+            .connection
+            .query_row(PREVIOUS_LOGIN_QUERY, (identifier.0,), |row| row.get(0))
+            .optional()?
+            .flatten();
+        let mut created = std::collections::BTreeMap::new();
+        for table in tables {
+            let statement = format!(
+                "SELECT COUNT(*) FROM {} WHERE created_at > COALESCE((SELECT previous_login FROM users WHERE id = ?), '')",
+                table
+            );
+            let count = database
+                .connection
+                .query_row(&statement, (identifier.0,), |row| row.get(0))?;
+            created.insert(*table, count);
+        }
+        Ok(WhatsNew { since, created })
+    }
+
+    fn parse_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Record<Self>> {
+        <(
+            PrimaryKey<User>,
+            String,
+            PasswordHash,
+            bool,
+            Date,
+            Option<PrimaryKey<Person>>,
+            bool,
+            bool,
+        )>::try_from(row)
+        .map(|value| Record {
+            identifier: value.0,
+            value: User {
+                username: value.1,
+                password_hash: value.2,
+                active: value.3,
+                creation_date: value.4,
+                related_to: value.5,
+                must_change_password: value.6,
+                is_admin: value.7,
+            },
+        })
+    }
 }
 
 impl DefaultGenerator for User {
@@ -67,6 +211,8 @@ impl DefaultGenerator for User {
             active: true,
             creation_date: Date::today(),
             related_to: None,
+            must_change_password: false,
+            is_admin: false,
         }
     }
 }
@@ -82,15 +228,37 @@ impl crate::backend::database::Selectable for User {
         bool,
         Date,
         Option<PrimaryKey<Person>>,
+        bool,
+        bool,
     );
 
     /// The statement for selecting all entries.
     const STATEMENT_SELECT_ALL: &'static str = const_format::formatcp!(
-        "SELECT id, username, active, creation_date, related_to FROM {}",
+        "SELECT id, username, active, creation_date, related_to, must_change_password, is_admin FROM {}",
         User::TABLE_NAME
     );
 
-    const SORTABLE_COLUMNS: &'static [&'static str] = &["id", "creation_date"];
+    const OUTPUT_FIELDS: &'static [&'static str] = &[
+        "identifier",
+        "username",
+        "active",
+        "creation_date",
+        "related_to",
+        "must_change_password",
+        "is_admin",
+    ];
+    const SORTABLE_COLUMNS: &'static [&'static str] =
+        &["id", "creation_date", "created_at", "updated_at"];
+    const PUBLIC_SORTABLE_COLUMNS: &'static [&'static str] =
+        &["creation_date", "created_at", "updated_at"];
+    const SORTABLE_COLUMN_TYPES: &'static [&'static str] = &[
+        <PrimaryKey<User> as DatabaseType>::RAW_COLUMN_VALUE,
+        <Date as DatabaseType>::RAW_COLUMN_VALUE,
+        <Timestamp as DatabaseType>::RAW_COLUMN_VALUE,
+        <Timestamp as DatabaseType>::RAW_COLUMN_VALUE,
+    ];
+    const TIMESTAMPED: bool = true;
+    const RANGE_COLUMN: Option<&'static str> = Some("creation_date");
 
     /// Deserialize the database value into a Record.
     fn deserialize_sql<'a>(value: Self::SelectValue<'a>) -> Self::Output {
@@ -100,8 +268,14 @@ impl crate::backend::database::Selectable for User {
             active: value.2,
             creation_date: value.3,
             related_to: value.4,
+            must_change_password: value.5,
+            is_admin: value.6,
         }
     }
+
+    fn primary_key(output: &Self::Output) -> PrimaryKey<Self> {
+        output.identifier
+    }
 }
 
 impl crate::backend::database::SelectableByPrimaryKey for User {
@@ -125,6 +299,10 @@ impl<'de> Deserialize<'de> for User {
             active: bool,
             creation_date: Date,
             related_to: Option<PrimaryKey<Person>>,
+            #[serde(default)]
+            must_change_password: bool,
+            #[serde(default)]
+            is_admin: bool,
         }
 
         let helper = UserHelper::deserialize(deserializer)?;
@@ -146,6 +324,8 @@ impl<'de> Deserialize<'de> for User {
             active: helper.active,
             creation_date: helper.creation_date,
             related_to: helper.related_to,
+            must_change_password: helper.must_change_password,
+            is_admin: helper.is_admin,
         })
     }
 }
@@ -157,6 +337,8 @@ pub struct Metadata {
     pub active: bool,
     pub creation_date: Date,
     pub related_to: Option<PrimaryKey<Person>>,
+    pub must_change_password: bool,
+    pub is_admin: bool,
 }
 
 impl From<Record<User>> for Metadata {
@@ -169,10 +351,20 @@ impl From<Record<User>> for Metadata {
             active: value.active,
             creation_date: value.creation_date,
             related_to: value.related_to,
+            must_change_password: value.must_change_password,
+            is_admin: value.is_admin,
         }
     }
 }
 
+/// The number of rows created since the previous login, grouped by their table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WhatsNew {
+    /// The time of the previous login, if any.
+    pub since: Option<chrono::NaiveDateTime>,
+    pub created: std::collections::BTreeMap<&'static str, usize>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::{PasswordHash, User};
@@ -190,6 +382,8 @@ mod tests {
             active: true,
             creation_date: Date::today(),
             related_to: None,
+            must_change_password: false,
+            is_admin: false,
         };
 
         assert_eq!(user.password_hash.matches(username, "test123"), false);
@@ -209,6 +403,8 @@ mod tests {
             active: true,
             creation_date: Date::today(),
             related_to: None,
+            must_change_password: false,
+            is_admin: false,
         }
         .insert(&database)
         .expect("Insert sucessful");
@@ -263,6 +459,8 @@ mod tests {
             active: true,
             creation_date: Date::today(),
             related_to: None,
+            must_change_password: false,
+            is_admin: false,
         };
 
         let serialized = serde_json::to_string(&user).expect("serialization successful");
@@ -51,6 +51,14 @@ impl PasswordHash {
         .is_ok()
     }
 
+    /// Spend as much time as verifying the password of an existing user, without ever succeeding.
+    /// This keeps the login of unknown users from being distinguishable by its timing.
+    pub fn mismatch(username: &str, password: &str) -> bool {
+        const DUMMY: PasswordHash = PasswordHash([0u8; PasswordHash::CREDENTIAL_LEN], true);
+        let _ = DUMMY.matches(username, password);
+        false
+    }
+
     pub fn is_valid(&self) -> bool {
         self.1
     }
@@ -1,7 +1,10 @@
 use chrono::{DateTime, NaiveDate, Utc};
 
 /// A date which is today or in the past.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// All stored dates are past-only: the reception and processing of documents, the creation of users,
+/// the birthdays of persons, and the entries of the audit log. Dates which may lie ahead use [`FutureDate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Date(NaiveDate);
 
 impl Date {
@@ -9,6 +12,21 @@ impl Date {
     pub fn today() -> Date {
         Date(Utc::now().date_naive())
     }
+
+    /// Format the date using a strftime-like format string.
+    pub fn format<'a>(&self, format: &'a str) -> impl std::fmt::Display + 'a {
+        self.0.format(format)
+    }
+
+    /// Check whether the date is strictly before the other one.
+    pub fn is_before(&self, other: &Date) -> bool {
+        self < other
+    }
+
+    /// Check whether the date is strictly after the other one.
+    pub fn is_after(&self, other: &Date) -> bool {
+        self > other
+    }
 }
 
 impl std::fmt::Display for Date {
@@ -57,7 +75,7 @@ impl<'de> serde::Deserialize<'de> for Date {
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_str(DateVisitor)
+        deserializer.deserialize_any(DateVisitor)
     }
 }
 
@@ -83,16 +101,103 @@ impl rusqlite::types::FromSql for Date {
     }
 }
 
+/// A date which may also lie in the future, i.e. for deadlines or planned events.
+/// Currently, it only bounds queries like the documents recieved within a period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FutureDate(NaiveDate);
+
+impl FutureDate {
+    /// Get the current date.
+    pub fn today() -> FutureDate {
+        FutureDate(Utc::now().date_naive())
+    }
+}
+
+impl std::fmt::Display for FutureDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format("%Y-%m-%d"))
+    }
+}
+
+impl From<Date> for FutureDate {
+    fn from(value: Date) -> Self {
+        FutureDate(value.0)
+    }
+}
+
+impl From<NaiveDate> for FutureDate {
+    fn from(value: NaiveDate) -> Self {
+        FutureDate(value)
+    }
+}
+
+impl<'a> TryFrom<&'a str> for FutureDate {
+    type Error = chrono::ParseError;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").map(FutureDate)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for FutureDate {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        FutureDate::try_from(value.as_str()).map_err(|_| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&value),
+                &"a string in the form '2000-01-31'",
+            )
+        })
+    }
+}
+
+impl serde::Serialize for FutureDate {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl rusqlite::ToSql for FutureDate {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        self.0.to_sql()
+    }
+}
+
+impl rusqlite::types::FromSql for FutureDate {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        NaiveDate::column_result(value).map(FutureDate)
+    }
+}
+
 #[derive(Default)]
 struct DateVisitor;
 
+impl DateVisitor {
+    /// Unix timestamps beyond this magnitude are taken as milliseconds, as seconds would exceed the year 5000.
+    const MILLISECONDS_THRESHOLD: i64 = 100_000_000_000;
+
+    /// Parse a date or a RFC 3339 timestamp, whose time is truncated.
+    fn parse(value: &str) -> Result<Date, Error> {
+        match chrono::DateTime::parse_from_rfc3339(value) {
+            Ok(datetime) => Date::try_from(datetime.date_naive()),
+            Err(_) => Date::try_from(value),
+        }
+    }
+}
+
 impl<'de> serde::de::Visitor<'de> for DateVisitor {
     type Value = Date;
 
     fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             formatter,
-            "a unix timestamp or a string in the form '2000-01-31'"
+            "a unix timestamp or a string in the form '2000-01-31' or '2000-01-31T12:00:00Z'"
         )
     }
 
@@ -100,18 +205,30 @@ impl<'de> serde::de::Visitor<'de> for DateVisitor {
     where
         E: serde::de::Error,
     {
-        DateTime::<Utc>::from_timestamp(unix_time, 0)
+        let datetime = match unix_time.abs() > Self::MILLISECONDS_THRESHOLD {
+            true => DateTime::<Utc>::from_timestamp_millis(unix_time),
+            false => DateTime::<Utc>::from_timestamp(unix_time, 0),
+        };
+        datetime
             .ok_or(Error)
             .and_then(Date::try_from)
             .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(unix_time), &self))
     }
 
-    fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
+    fn visit_u64<E>(self, unix_time: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        i64::try_from(unix_time)
+            .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(unix_time), &self))
+            .and_then(|unix_time| self.visit_i64(unix_time))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
     where
         E: serde::de::Error,
     {
-        v.try_into()
-            .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
+        Self::parse(v).map_err(|_| E::invalid_value(serde::de::Unexpected::Str(v), &self))
     }
 }
 
@@ -151,6 +268,17 @@ mod tests {
         assert_eq!(default_date.0, now);
     }
 
+    #[test]
+    fn test_date_comparison() {
+        let earlier = Date::try_from("2023-12-31").expect("valid date");
+        let later = Date::try_from("2024-01-01").expect("valid date");
+        assert!(earlier < later);
+        assert!(earlier.is_before(&later));
+        assert!(later.is_after(&earlier));
+        assert!(!earlier.is_before(&earlier));
+        assert_eq!(std::cmp::max(earlier, later), later);
+    }
+
     #[test]
     fn test_date_try_from_naive_date() {
         let naive_date = NaiveDate::from_ymd_opt(2022, 1, 1).expect("valid date");
@@ -182,6 +310,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_date_deserialize_from_timestamps() {
+        let expected = NaiveDate::from_ymd_opt(2024, 2, 18).expect("valid date");
+        for serialized in [
+            "\"2024-02-18T09:30:00Z\"",
+            "\"2024-02-18T23:30:00-05:00\"",
+            "\"2024-02-18\"",
+            "1708248600",
+            "1708248600000",
+        ] {
+            let deserialized: Date = serde_json::from_str(serialized).expect("valid date");
+            assert_eq!(deserialized.0, expected, "{}", serialized);
+        }
+        assert!(serde_json::from_str::<Date>("\"3000-02-18T09:30:00Z\"").is_err());
+        assert!(serde_json::from_str::<Date>("32503680000000").is_err());
+    }
+
     #[test]
     fn test_date_not_in_future() {
         // Would be interesting, if the software is still used
@@ -195,6 +340,32 @@ mod tests {
         assert!(serde_json::from_str::<Date>(serialized).is_err());
     }
 
+    #[test]
+    fn test_future_date_accepts_future() {
+        let deserialized: FutureDate = serde_json::from_str("\"3000-02-18\"").unwrap();
+        assert_eq!(
+            deserialized.0,
+            NaiveDate::from_ymd_opt(3000, 2, 18).expect("valid date")
+        );
+        assert_eq!(
+            serde_json::to_string(&deserialized).unwrap(),
+            "\"3000-02-18\""
+        );
+    }
+
+    #[test]
+    fn test_future_date_constructors() {
+        let naive_date = NaiveDate::from_ymd_opt(3000, 2, 18).expect("valid date");
+        assert_eq!(
+            FutureDate::try_from("3000-02-18").expect("valid date"),
+            FutureDate::from(naive_date)
+        );
+        assert!(FutureDate::try_from("3000-02-30").is_err());
+
+        let past = Date::try_from("2022-02-18").expect("valid date");
+        assert_eq!(FutureDate::from(past).to_string(), past.to_string());
+    }
+
     #[test]
     fn test_optional_missing() {
         let serialized = "{}";
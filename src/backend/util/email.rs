@@ -0,0 +1,135 @@
+/// An e-mail address with a single '@' and a domain containing a dot.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Email(String);
+
+impl Email {
+    /// Get the address as it is.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for Email {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Email> for String {
+    fn from(value: Email) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<String> for Email {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (local, domain) = value.split_once('@').ok_or(Error)?;
+        let is_valid = !local.is_empty()
+            && !domain.contains('@')
+            && !value.chars().any(char::is_whitespace)
+            && domain
+                .split_once('.')
+                .is_some_and(|(host, _)| !host.is_empty())
+            && !domain.ends_with('.');
+        match is_valid {
+            true => Ok(Email(value)),
+            false => Err(Error),
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Email {
+    type Error = Error;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Self::try_from(String::from(value))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Email {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Email::try_from(value.as_str()).map_err(|_| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(&value),
+                &"an e-mail address like 'max@example.com'",
+            )
+        })
+    }
+}
+
+impl serde::Serialize for Email {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl rusqlite::ToSql for Email {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        self.0.to_sql()
+    }
+}
+
+impl rusqlite::types::FromSql for Email {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        // Addresses stored before the validation existed are still readable.
+        String::column_result(value).map(Email)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Error;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid e-mail address")
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::Email;
+
+    #[test]
+    fn test_try_from() {
+        for valid in ["max@example.com", "max.mustermann+news@mail.example.org"] {
+            assert_eq!(
+                Email::try_from(valid).expect("valid address").as_str(),
+                valid
+            );
+        }
+        for invalid in [
+            "not-an-email",
+            "@example.com",
+            "max@example",
+            "max@.com",
+            "max@example.",
+            "max@@example.com",
+            "max@mail@example.com",
+            "max mustermann@example.com",
+            "",
+        ] {
+            assert!(Email::try_from(invalid).is_err(), "{} is valid", invalid);
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let email: Email = serde_json::from_str("\"max@example.com\"").expect("valid address");
+        assert_eq!(
+            serde_json::to_string(&email).expect("valid json"),
+            "\"max@example.com\""
+        );
+        assert!(serde_json::from_str::<Email>("\"not-an-email\"").is_err());
+    }
+}
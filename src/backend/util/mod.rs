@@ -1,5 +1,13 @@
 mod date;
+mod email;
 mod pagination;
+mod timestamp;
+mod where_clause;
 
-pub use self::date::{Date, Error as DateError};
-pub use self::pagination::{Column, Error as PaginationError, Limit, Order, Pagination};
+pub use self::date::{Date, Error as DateError, FutureDate};
+pub use self::email::{Email, Error as EmailError};
+pub use self::pagination::{
+    Column, ColumnDescription, Error as PaginationError, Limit, Order, Pagination,
+};
+pub use self::timestamp::Timestamp;
+pub use self::where_clause::WhereClause;
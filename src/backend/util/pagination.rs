@@ -1,8 +1,12 @@
 use rocket::{
     data::ToByteUnit,
     form::{self, DataField, Errors, FromFormField, ValueField},
+    http::Status,
+    request::{FromRequest, Outcome},
+    Request,
 };
 
+use super::WhereClause;
 use crate::backend::database::Selectable;
 
 /// A subsection of selection results.
@@ -12,6 +16,8 @@ pub struct Pagination<T: Selectable> {
     pub limit: Limit,
     pub order: Order,
     pub column: Column<T>,
+    /// The primary key the page starts after. If set, the page is sorted by the primary key and the offset is ignored.
+    pub after: Option<i64>,
 }
 
 impl<T: Selectable> Clone for Pagination<T> {
@@ -21,6 +27,7 @@ impl<T: Selectable> Clone for Pagination<T> {
             limit: self.limit.clone(),
             order: self.order.clone(),
             column: self.column.clone(),
+            after: self.after,
         }
     }
 }
@@ -32,6 +39,7 @@ impl<T: Selectable> Default for Pagination<T> {
             limit: Default::default(),
             order: Default::default(),
             column: Default::default(),
+            after: None,
         }
     }
 }
@@ -49,16 +57,32 @@ impl<T: Selectable> Pagination<T> {
             limit,
             order,
             column,
+            after: None,
         })
     }
 
-    /// Generate the next pagination element if there appears to be elements left.
-    pub fn next(&self, num_recieved_elements: usize) -> Option<Self> {
-        if num_recieved_elements < self.limit.0 {
+    /// Select the page after the element with the primary key. Unlike offsets, such a cursor stays stable while elements are inserted.
+    pub fn from_cursor(after: i64, limit: Limit) -> Self {
+        Pagination {
+            offset: 0,
+            limit,
+            order: Order::Ascending,
+            column: Default::default(),
+            after: Some(after),
+        }
+    }
+
+    /// Generate the next pagination element if there appears to be elements left, given the primary keys of the recieved elements.
+    /// A cursor continues after the last element of the page.
+    pub fn next(&self, primary_keys: &[i64]) -> Option<Self> {
+        if primary_keys.len() < self.limit.0 {
             return None;
         }
         let mut next_pagination = self.clone();
-        next_pagination.offset += self.limit.0;
+        match self.after {
+            Some(_) => next_pagination.after = primary_keys.last().copied(),
+            None => next_pagination.offset += self.limit.0,
+        }
         Some(next_pagination)
     }
 
@@ -74,10 +98,52 @@ impl<T: Selectable> Pagination<T> {
         Some(last_pagination)
     }
 
+    /// Generate the pagination element of the first page.
+    pub fn first(&self) -> Self {
+        let mut first_pagination = self.clone();
+        first_pagination.offset = 0;
+        first_pagination
+    }
+
+    /// Generate the pagination element of the last page, given the total number of elements.
+    pub fn last(&self, total: usize) -> Self {
+        let mut last_pagination = self.clone();
+        last_pagination.offset = (self.total_pages(total) - 1) * self.limit.0;
+        last_pagination
+    }
+
+    /// The one-based number of the current page. An offset within a page counts as that page.
+    pub fn current_page(&self) -> usize {
+        self.offset / self.limit.0 + 1
+    }
+
+    /// The number of pages required for the total number of elements. There is always at least one, possibly empty, page.
+    pub fn total_pages(&self, total: usize) -> usize {
+        std::cmp::max(total.div_ceil(self.limit.0), 1)
+    }
+
     pub fn end_offset(&self) -> usize {
         self.offset + self.limit.0
     }
 
+    /// Check whether there are elements matching the filter after the current page without loading them.
+    pub fn has_more(
+        &self,
+        database: &crate::backend::database::Database,
+        filter: &WhereClause<T>,
+    ) -> Result<bool, crate::backend::database::Error> {
+        // Like the cursor, the offset is a number and therefore inserted directly.
+        let statement = format!(
+            "SELECT EXISTS (SELECT 1 FROM {} {} LIMIT 1 OFFSET {})",
+            T::TABLE_NAME,
+            filter.display_sql_paginated(self),
+            self.end_offset()
+        );
+        Ok(database
+            .connection
+            .query_row(&statement, filter.params(), |row| row.get(0))?)
+    }
+
     /// Prepare creating a SQL string.
     pub fn display_sql<'a>(&'a self) -> impl 'a + std::fmt::Display {
         DisplaySql(self)
@@ -89,10 +155,68 @@ impl<T: Selectable> Pagination<T> {
     }
 }
 
+/// Read the pagination from the query, i.e. `?sort_by=id&order=asc&limit=10&offset=20`. Missing values use their defaults.
+/// A cursor like `?after=42&limit=10` could not be combined with sorting or offsets.
+#[rocket::async_trait]
+impl<'r, T: Selectable> FromRequest<'r> for Pagination<T> {
+    type Error = Error;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        fn parse<'r, V: FromFormField<'r>>(
+            request: &'r Request<'_>,
+            name: &str,
+            error: Error,
+        ) -> Result<Option<V>, Error> {
+            request.query_value(name).transpose().map_err(|_| error)
+        }
+
+        // The configured maximum applies if the configuration is managed, i.e. outside of unit tests.
+        let maximum = request
+            .rocket()
+            .state::<crate::Config>()
+            .map_or(Limit::MAXIMUM, crate::Config::max_limit);
+        let pagination = (|| {
+            let limit = parse::<Limit>(request, "limit", Error::InvalidParameter)?
+                .unwrap_or_default()
+                .at_most(maximum);
+            if let Some(after) = parse(request, "after", Error::InvalidParameter)? {
+                return match ["offset", "order", "sort_by"]
+                    .iter()
+                    .any(|name| request.query_value::<&str>(name).is_some())
+                {
+                    true => Err(Error::InvalidParameter),
+                    false => Ok(Pagination::from_cursor(after, limit)),
+                };
+            }
+            Ok(Pagination {
+                offset: parse(request, "offset", Error::InvalidParameter)?.unwrap_or(0),
+                limit,
+                order: parse(request, "order", Error::InvalidParameter)?.unwrap_or_default(),
+                column: parse(request, "sort_by", Error::InvalidColumn)?.unwrap_or_default(),
+                after: None,
+            })
+        })();
+        match pagination {
+            Ok(pagination) => Outcome::Success(pagination),
+            Err(error) => Outcome::Error((Status::BadRequest, error)),
+        }
+    }
+}
+
 struct DisplaySql<'a, T: Selectable>(&'a Pagination<T>);
 
 impl<'a, T: Selectable> std::fmt::Display for DisplaySql<'a, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The condition on the primary key is part of the filter, see `WhereClause::display_sql_paginated`.
+        if self.0.after.is_some() {
+            return write!(
+                f,
+                "ORDER BY {} {} LIMIT {}",
+                <Column<T> as Default>::default(),
+                Order::Ascending,
+                self.0.limit
+            );
+        }
         write!(
             f,
             "ORDER BY {} {} LIMIT {} OFFSET {}",
@@ -105,29 +229,41 @@ struct DisplayUrl<'a, T: Selectable>(&'a Pagination<T>);
 
 impl<'a, T: Selectable> std::fmt::Display for DisplayUrl<'a, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("?")?;
+        if let Some(after) = self.0.after {
+            return write!(f, "after={}&limit={}", after, self.0.limit);
+        }
+        // Hidden columns like ids are rejected when parsed, so they are left to the default.
+        if T::PUBLIC_SORTABLE_COLUMNS.contains(&self.0.column.as_str()) {
+            write!(f, "sort_by={}&", self.0.column.as_str())?;
+        }
         write!(
             f,
-            "?column={}&order={}&limit={}&offset={}",
-            self.0.column.as_str(),
-            self.0.order,
-            self.0.limit,
-            self.0.offset
+            "order={}&limit={}&offset={}",
+            self.0.order, self.0.limit, self.0.offset
         )
     }
 }
 
-/// The number of samples within the pagination.
+/// The number of samples within the pagination. It is always capped, so API callers could not request unbounded result sets; full exports use `/admin/export.zip` instead.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Limit(usize);
 
 impl Limit {
     pub const DEFAULT: Limit = Limit(10);
-    pub const MAXIMUM: Limit = Limit(100);
+    /// The ceiling regardless of the configuration. Requests are usually capped below it by the configured maximum.
+    pub const MAXIMUM: Limit = Limit(1000);
+
+    /// Cap the limit to the given maximum.
+    pub fn at_most(self, maximum: Limit) -> Limit {
+        std::cmp::min(self, maximum)
+    }
 }
 
 impl From<usize> for Limit {
+    /// Clamp the value to the valid range. Pages are never empty, so the minimum is one.
     fn from(value: usize) -> Self {
-        std::cmp::min(Limit(value), Self::MAXIMUM)
+        Limit(value.clamp(1, Self::MAXIMUM.0))
     }
 }
 
@@ -158,11 +294,21 @@ impl PartialEq<usize> for Limit {
 #[rocket::async_trait]
 impl<'r> FromFormField<'r> for Limit {
     fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
-        usize::from_value(field).map(Limit::from)
+        Limit::parse(usize::from_value(field)?)
     }
 
     async fn from_data(field: DataField<'r, '_>) -> form::Result<'r, Self> {
-        usize::from_data(field).await.map(Limit::from)
+        Limit::parse(usize::from_data(field).await?)
+    }
+}
+
+impl Limit {
+    /// Reject a requested limit of zero, which would result in empty pages.
+    fn parse<'r>(value: usize) -> form::Result<'r, Self> {
+        match value {
+            0 => Err(form::Error::validation("the limit must be at least one"))?,
+            value => Ok(Limit::from(value)),
+        }
     }
 }
 
@@ -171,11 +317,16 @@ impl<'r> FromFormField<'r> for Limit {
 pub enum Error {
     /// The specified column does not exist.
     InvalidColumn,
+    /// The offset, the limit, or the order is malformed.
+    InvalidParameter,
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "The selected column does not exist")
+        match self {
+            Error::InvalidColumn => write!(f, "The selected column does not exist"),
+            Error::InvalidParameter => write!(f, "The pagination parameters are malformed"),
+        }
     }
 }
 
@@ -234,6 +385,47 @@ impl<T: Selectable> Column<T> {
     pub fn as_str(&self) -> &'static str {
         T::SORTABLE_COLUMNS[self.0]
     }
+
+    /// Return the database type of the column.
+    pub fn column_type(&self) -> &'static str {
+        T::SORTABLE_COLUMN_TYPES[self.0]
+    }
+
+    /// Parse a column requested by a client, which must be public.
+    pub fn parse_public(column: &str) -> Result<Self, Error> {
+        match T::PUBLIC_SORTABLE_COLUMNS.contains(&column) {
+            true => Column::try_from(column),
+            false => Err(Error::InvalidColumn),
+        }
+    }
+
+    /// Describe all the distinct columns clients could sort the table by.
+    pub fn describe_all() -> Vec<ColumnDescription> {
+        let mut descriptions: Vec<ColumnDescription> = Vec::new();
+        for column in
+            (0..T::SORTABLE_COLUMNS.len()).map(|index| Column::<T>(index, std::marker::PhantomData))
+        {
+            if T::PUBLIC_SORTABLE_COLUMNS.contains(&column.as_str())
+                && descriptions
+                    .iter()
+                    .all(|value| value.name != column.as_str())
+            {
+                descriptions.push(ColumnDescription {
+                    name: column.as_str(),
+                    column_type: column.column_type(),
+                });
+            }
+        }
+        descriptions
+    }
+}
+
+/// The name and the database type of a sortable column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct ColumnDescription {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub column_type: &'static str,
 }
 
 impl<'a, T: Selectable> TryFrom<&'a str> for Column<T> {
@@ -270,13 +462,13 @@ impl<T: Selectable> Clone for Column<T> {
 impl<'r, T: Selectable> FromFormField<'r> for Column<T> {
     fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
         String::from_value(field)
-            .and_then(|value| Column::try_from(value.as_str()).map_err(|_| Errors::new()))
+            .and_then(|value| Column::parse_public(value.as_str()).map_err(|_| Errors::new()))
     }
 
     async fn from_data(field: DataField<'r, '_>) -> form::Result<'r, Self> {
         String::from_data(field)
             .await
-            .and_then(|value| Column::try_from(value.as_str()).map_err(|_| Errors::new()))
+            .and_then(|value| Column::parse_public(value.as_str()).map_err(|_| Errors::new()))
     }
 }
 
@@ -301,6 +493,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_public_columns() {
+        assert_eq!(
+            Column::<User>::parse_public("id"),
+            Err(Error::InvalidColumn)
+        );
+        assert_eq!(
+            Column::<User>::parse_public("creation_date").map(|column| column.as_str()),
+            Ok("creation_date")
+        );
+        // Internally, the hidden column is still available.
+        assert!(Column::<User>::try_from("id").is_ok());
+    }
+
     #[test]
     fn test_sql() {
         assert_eq!(
@@ -315,6 +521,7 @@ mod tests {
     #[test]
     fn test_length_max_size() {
         assert_eq!(Limit::from(Limit::MAXIMUM.0 + 100), Limit::MAXIMUM);
+        assert_eq!(Limit::from(0), 1);
     }
 
     #[test]
@@ -331,9 +538,23 @@ mod tests {
         };
 
         // Lets simulate 5 existings elements in database
-        let next_pagination = pagination.next(3).expect("valid pagination");
+        let next_pagination = pagination.next(&[1, 2, 3]).expect("valid pagination");
         assert_eq!(next_pagination.offset, 3);
-        assert_eq!(pagination.next(2), None);
+        assert_eq!(pagination.next(&[4, 5]), None);
+    }
+
+    #[test]
+    fn test_next_cursor() {
+        let pagination = Pagination::<User>::from_cursor(2, Limit::from(3));
+
+        let next_pagination = pagination.next(&[3, 5, 8]).expect("valid pagination");
+        assert_eq!(next_pagination.after, Some(8));
+        assert_eq!(next_pagination.offset, 0);
+        assert_eq!(
+            next_pagination.display_url().to_string(),
+            "?after=8&limit=3"
+        );
+        assert_eq!(pagination.next(&[3, 5]), None);
     }
 
     #[test]
@@ -345,9 +566,62 @@ mod tests {
         };
 
         // Lets simulate 5 existings elements in database - however, we received more elements than we would expect given the limit.
-        let next_pagination = pagination.next(4).expect("valid pagination");
+        let next_pagination = pagination.next(&[1, 2, 3, 4]).expect("valid pagination");
         assert_eq!(next_pagination.offset, 3);
-        assert_eq!(pagination.next(2), None);
+        assert_eq!(pagination.next(&[1, 2]), None);
+    }
+
+    #[test]
+    fn test_has_more() {
+        use crate::backend::database::{Database, DefaultGenerator, Insertable};
+
+        let database = Database::in_memory().expect("valid database");
+        let pagination = Pagination::<User> {
+            offset: 0,
+            limit: Limit::from(3),
+            ..Default::default()
+        };
+
+        // Exactly one full page exists ...
+        for index in 0..3 {
+            let mut user = User::create_default(&database);
+            user.username = format!("User {}", index);
+            user.insert(&database).expect("valid user");
+        }
+        assert_eq!(
+            pagination.has_more(&database, &WhereClause::new()),
+            Ok(false)
+        );
+
+        // ... until another element is added.
+        let mut user = User::create_default(&database);
+        user.username = String::from("User 3");
+        user.insert(&database).expect("valid user");
+        assert_eq!(
+            pagination.has_more(&database, &WhereClause::new()),
+            Ok(true)
+        );
+
+        // Elements not matching the filter do not count.
+        assert_eq!(
+            pagination.has_more(
+                &database,
+                &WhereClause::new().equals(<Column<User> as Default>::default(), 4)
+            ),
+            Ok(false)
+        );
+
+        // Neither do the elements up to the cursor.
+        assert_eq!(
+            Pagination::<User>::from_cursor(1, Limit::from(3))
+                .has_more(&database, &WhereClause::new()),
+            Ok(false)
+        );
+        assert_eq!(
+            Pagination::<User>::from_cursor(0, Limit::from(3))
+                .has_more(&database, &WhereClause::new()),
+            Ok(true)
+        );
     }
 
     #[test]
@@ -358,7 +632,67 @@ mod tests {
             ..Default::default()
         };
 
-        let next_pagination = pagination.next(5).expect("valid pagination");
+        let next_pagination = pagination.next(&[1, 2, 3, 4, 5]).expect("valid pagination");
         assert_eq!(next_pagination.previous(), Some(pagination));
     }
+
+    #[test]
+    fn test_from_request() {
+        use rocket::local::blocking::Client;
+
+        let client = Client::untracked(rocket::custom(rocket::Config::debug_default()))
+            .expect("valid client");
+        let parse = |uri: &'static str| {
+            let request = client.get(uri);
+            match rocket::async_test(Pagination::<User>::from_request(request.inner())) {
+                Outcome::Success(pagination) => Ok(pagination),
+                Outcome::Error((_, error)) => Err(error),
+                Outcome::Forward(_) => panic!("unexpected forward"),
+            }
+        };
+
+        assert_eq!(
+            parse("/?sort_by=creation_date&order=asc&limit=5&offset=15"),
+            Ok(
+                Pagination::<User>::new("creation_date", 15, Limit::from(5), Order::Ascending)
+                    .expect("valid pagination")
+            )
+        );
+        assert_eq!(parse("/"), Ok(Pagination::default()));
+        assert_eq!(parse("/?sort_by=id"), Err(Error::InvalidColumn));
+        assert_eq!(parse("/?limit=many"), Err(Error::InvalidParameter));
+        assert_eq!(parse("/?limit=0"), Err(Error::InvalidParameter));
+        assert_eq!(
+            parse("/?after=42&limit=5"),
+            Ok(Pagination::from_cursor(42, Limit::from(5)))
+        );
+        assert_eq!(parse("/?after=42&offset=5"), Err(Error::InvalidParameter));
+    }
+
+    #[test]
+    fn test_cursor() {
+        let pagination = Pagination::<User>::from_cursor(42, Limit::from(5));
+        assert_eq!(
+            pagination.display_sql().to_string(),
+            "ORDER BY \"id\" ASC LIMIT 5"
+        );
+        assert_eq!(pagination.display_url().to_string(), "?after=42&limit=5");
+    }
+
+    #[test]
+    fn test_pages() {
+        let pagination = Pagination::<User> {
+            offset: 10,
+            limit: Limit::from(10),
+            ..Default::default()
+        };
+
+        assert_eq!(pagination.current_page(), 2);
+        assert_eq!(pagination.total_pages(25), 3);
+        assert_eq!(pagination.first().offset, 0);
+        assert_eq!(pagination.last(25).offset, 20);
+        assert_eq!(pagination.last(20).offset, 10);
+        assert_eq!(pagination.total_pages(0), 1);
+        assert_eq!(pagination.last(0).offset, 0);
+    }
 }
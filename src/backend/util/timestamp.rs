@@ -0,0 +1,86 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// A point in time in UTC, i.e. when a row was created. Other than a `Date`, it may lie in the future.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(transparent)]
+pub struct Timestamp(DateTime<Utc>);
+
+impl Timestamp {
+    /// The format SQLite uses for `strftime('%Y-%m-%d %H:%M:%f')`, which sorts chronologically as text.
+    const FORMAT: &'static str = "%Y-%m-%d %H:%M:%S%.3f";
+
+    /// Get the current point in time.
+    pub fn now() -> Timestamp {
+        Timestamp(Utc::now())
+    }
+}
+
+impl std::fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.format(Self::FORMAT))
+    }
+}
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(value: DateTime<Utc>) -> Self {
+        Timestamp(value)
+    }
+}
+
+impl rusqlite::ToSql for Timestamp {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        Ok(rusqlite::types::ToSqlOutput::from(self.to_string()))
+    }
+}
+
+impl rusqlite::types::FromSql for Timestamp {
+    fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+        NaiveDateTime::column_result(value).map(|value| Timestamp(value.and_utc()))
+    }
+}
+
+impl crate::backend::database::DatabaseType for Timestamp {
+    const RAW_COLUMN_VALUE: &'static str = "DATETIME";
+    const COLUMN_VALUE: &'static str = "DATETIME NOT NULL";
+    const IS_SORTABLE: bool = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timestamp;
+    use crate::backend::database::Database;
+
+    #[test]
+    fn test_sql_roundtrip() {
+        let database = Database::in_memory().expect("valid database");
+        let timestamp: Timestamp = database
+            .connection
+            .query_row(
+                "SELECT strftime('%Y-%m-%d %H:%M:%f', '2024-02-29 13:14:15.678')",
+                (),
+                |row| row.get(0),
+            )
+            .expect("valid timestamp");
+        assert_eq!(timestamp.to_string(), "2024-02-29 13:14:15.678");
+
+        let loaded: Timestamp = database
+            .connection
+            .query_row("SELECT ?", (timestamp,), |row| row.get(0))
+            .expect("valid timestamp");
+        assert_eq!(loaded, timestamp);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let timestamp: Timestamp =
+            serde_json::from_str("\"2024-02-29T13:14:15.678Z\"").expect("valid timestamp");
+        assert_eq!(timestamp.to_string(), "2024-02-29 13:14:15.678");
+        assert_eq!(
+            serde_json::to_string(&timestamp).expect("valid json"),
+            "\"2024-02-29T13:14:15.678Z\""
+        );
+        assert!(Timestamp::now() > timestamp);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::backend::{
+    database::{like_pattern, Selectable, LIKE_ESCAPE},
+    FutureDate,
+};
+
+use super::{Column, Pagination};
+
+/// A filter on a selection. Only validated columns are inserted into the statement; all values are bound as parameters.
+pub struct WhereClause<T: Selectable> {
+    conditions: Vec<String>,
+    params: Vec<Box<dyn rusqlite::ToSql>>,
+    marker: std::marker::PhantomData<fn() -> T>,
+}
+
+impl<T: Selectable> WhereClause<T> {
+    /// Create a filter matching all the elements.
+    pub fn new() -> Self {
+        Self {
+            conditions: Vec::new(),
+            params: Vec::new(),
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Only match elements where the column equals the value.
+    pub fn equals(mut self, column: Column<T>, value: impl rusqlite::ToSql + 'static) -> Self {
+        self.conditions.push(format!("{} = ?", column));
+        self.params.push(Box::new(value));
+        self
+    }
+
+    /// Only match elements where the column is within the inclusive range.
+    pub fn between<V: rusqlite::ToSql + 'static>(
+        mut self,
+        column: Column<T>,
+        from: V,
+        to: V,
+    ) -> Self {
+        self.conditions.push(format!("{} BETWEEN ? AND ?", column));
+        self.params.push(Box::new(from));
+        self.params.push(Box::new(to));
+        self
+    }
+
+    /// Only match elements whose date lies within the inclusive range, which may be open at either end.
+    /// The time of timestamps is ignored, such that the last day is matched completely.
+    pub fn within(
+        mut self,
+        column: Column<T>,
+        from: Option<FutureDate>,
+        to: Option<FutureDate>,
+    ) -> Self {
+        if let Some(from) = from {
+            self.conditions.push(format!("date({}) >= ?", column));
+            self.params.push(Box::new(from));
+        }
+        if let Some(to) = to {
+            self.conditions.push(format!("date({}) <= ?", column));
+            self.params.push(Box::new(to));
+        }
+        self
+    }
+
+    /// Only match elements containing the text literally within any of the columns, ignoring the case.
+    /// Unlike the other filters, the columns are fixed names, as texts are searched even if they could not be sorted by.
+    pub fn contains(mut self, columns: &[&'static str], text: &str) -> Self {
+        let conditions = columns
+            .iter()
+            .map(|column| format!("\"{}\" LIKE ? {}", column, LIKE_ESCAPE))
+            .collect::<Vec<_>>();
+        self.conditions
+            .push(format!("({})", conditions.join(" OR ")));
+        for _ in columns {
+            self.params.push(Box::new(like_pattern(text)));
+        }
+        self
+    }
+
+    /// Hide the archived elements, if the elements could be archived at all.
+    pub fn without_archived(mut self) -> Self {
+        if T::ARCHIVABLE {
+            self.conditions.push(String::from("archived = FALSE"));
+        }
+        self
+    }
+
+    /// Only match the archived elements, which never exist if the elements could not be archived.
+    pub fn only_archived(mut self) -> Self {
+        self.conditions.push(String::from(match T::ARCHIVABLE {
+            true => "archived = TRUE",
+            false => "FALSE",
+        }));
+        self
+    }
+
+    /// Check whether the filter matches all the elements.
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// The parameters in the order of their placeholders.
+    pub fn params(&self) -> impl rusqlite::Params + '_ {
+        rusqlite::params_from_iter(self.params.iter())
+    }
+
+    /// Prepare creating a SQL string, which is empty if all elements are matched.
+    pub fn display_sql<'a>(&'a self) -> impl 'a + std::fmt::Display {
+        DisplaySql(self, None)
+    }
+
+    /// Prepare creating a SQL string like `display_sql`, which also skips the elements up to the cursor of the pagination.
+    pub fn display_sql_paginated<'a>(
+        &'a self,
+        pagination: &Pagination<T>,
+    ) -> impl 'a + std::fmt::Display {
+        DisplaySql(self, pagination.after)
+    }
+}
+
+impl<T: Selectable> Default for WhereClause<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Selectable> std::fmt::Debug for WhereClause<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WhereClause")
+            .field("conditions", &self.conditions)
+            .finish()
+    }
+}
+
+struct DisplaySql<'a, T: Selectable>(&'a WhereClause<T>, Option<i64>);
+
+impl<'a, T: Selectable> std::fmt::Display for DisplaySql<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The cursor is a number and therefore inserted directly, keeping the parameters unchanged.
+        let cursor = self
+            .1
+            .map(|after| format!("{} > {}", <Column<T> as Default>::default(), after));
+        let conditions = self
+            .0
+            .conditions
+            .iter()
+            .map(String::as_str)
+            .chain(cursor.as_deref())
+            .collect::<Vec<_>>();
+        if conditions.is_empty() {
+            return Ok(());
+        }
+        write!(f, "WHERE {}", conditions.join(" AND "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{user::User, Date};
+
+    #[test]
+    fn test_sql() {
+        let column = Column::<User>::try_from("creation_date").expect("valid column");
+        let filter = WhereClause::<User>::new()
+            .equals(Column::default(), 42)
+            .between(column, Date::today(), Date::today());
+        assert_eq!(
+            filter.display_sql().to_string(),
+            "WHERE \"id\" = ? AND \"creation_date\" BETWEEN ? AND ?"
+        );
+        assert_eq!(WhereClause::<User>::new().display_sql().to_string(), "");
+    }
+
+    #[test]
+    fn test_sql_paginated() {
+        let cursor = Pagination::<User>::from_cursor(42, crate::backend::Limit::from(5));
+        assert_eq!(
+            WhereClause::<User>::new()
+                .display_sql_paginated(&cursor)
+                .to_string(),
+            "WHERE \"id\" > 42"
+        );
+        assert_eq!(
+            WhereClause::<User>::new()
+                .equals(Column::default(), 7)
+                .display_sql_paginated(&cursor)
+                .to_string(),
+            "WHERE \"id\" = ? AND \"id\" > 42"
+        );
+        assert_eq!(
+            WhereClause::<User>::new()
+                .display_sql_paginated(&Pagination::default())
+                .to_string(),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_within() {
+        let column = || Column::<User>::try_from("created_at").expect("valid column");
+        let date = FutureDate::try_from("2024-02-18").expect("valid date");
+        assert_eq!(
+            WhereClause::<User>::new()
+                .within(column(), Some(date), Some(date))
+                .display_sql()
+                .to_string(),
+            "WHERE date(\"created_at\") >= ? AND date(\"created_at\") <= ?"
+        );
+        assert_eq!(
+            WhereClause::<User>::new()
+                .within(column(), None, Some(date))
+                .display_sql()
+                .to_string(),
+            "WHERE date(\"created_at\") <= ?"
+        );
+    }
+}
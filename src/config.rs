@@ -1,22 +1,53 @@
 use std::{
     future::Future,
     path::{Path, PathBuf},
-    sync::Mutex,
+    time::Duration,
 };
 
-use crate::backend::database::Database;
+use crate::auth::CookieSettings;
+use crate::backend::{
+    database::{Database, Pool, PooledDatabase},
+    document::DocumentType,
+    Limit,
+};
+use crate::frontend::Locale;
 use base64::prelude::*;
 use rocket::fs::NamedFile;
 
 pub struct Config {
-    database: Mutex<Database>,
+    database: Pool,
+    read_only_database: Option<Pool>,
     public_assets: PathBuf,
     secret: [u8; 32],
+    default_locale: Locale,
+    cookie_settings: CookieSettings,
+    document_types: Vec<DocumentType>,
+    max_string_length: usize,
+    session_max_age: Duration,
+    max_limit: Limit,
 }
 
 impl Config {
     pub const ENV_VARIBLE_PATH: &'static str = "SHELBY_ASSETS";
     const ENV_SECRET: &'static str = "ROCKET_SECRET_KEY";
+    const ENV_LOCALE: &'static str = "SHELBY_LOCALE";
+    const ENV_COOKIE_NAME: &'static str = "SHELBY_COOKIE_NAME";
+    const ENV_COOKIE_SAME_SITE: &'static str = "SHELBY_COOKIE_SAME_SITE";
+    const ENV_COOKIE_SECURE: &'static str = "SHELBY_COOKIE_SECURE";
+    const ENV_DOCUMENT_TYPES: &'static str = "SHELBY_DOCUMENT_TYPES";
+    const ENV_MAX_STRING_LENGTH: &'static str = "SHELBY_MAX_STRING_LENGTH";
+    const ENV_TRACE_SQL: &'static str = "SHELBY_TRACE_SQL";
+    const ENV_SESSION_MAX_AGE: &'static str = "SHELBY_SESSION_MAX_AGE";
+    const ENV_MAX_LIMIT: &'static str = "SHELBY_MAX_LIMIT";
+
+    /// The number of characters a string value may have if not configured otherwise.
+    pub const DEFAULT_MAX_STRING_LENGTH: usize = 1024;
+
+    /// The time a login is valid if not configured otherwise.
+    pub const DEFAULT_SESSION_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// The number of elements a page may have if not configured otherwise.
+    pub const DEFAULT_MAX_LIMIT: usize = 100;
 
     pub fn from_env(database: Database) -> Result<Self, Error> {
         let public_assets = std::env::var(Self::ENV_VARIBLE_PATH)
@@ -43,13 +74,149 @@ impl Config {
             }
         };
 
+        // The locale used if the browser does not request a supported one
+        let default_locale = match std::env::var(Config::ENV_LOCALE) {
+            Ok(tag) => Locale::from_language_tag(&tag).ok_or(Error::InvalidLocale)?,
+            Err(_) => Locale::default(),
+        };
+
+        let cookie_settings = Self::cookie_settings_from_env()?;
+
+        // The file types accepted for documents, i.e. "pdf,png"
+        let document_types = match std::env::var(Config::ENV_DOCUMENT_TYPES) {
+            Ok(value) => value
+                .split(',')
+                .map(DocumentType::parse)
+                .collect::<Option<Vec<_>>>()
+                .ok_or(Error::InvalidDocumentTypes)?,
+            Err(_) => vec![DocumentType::Pdf],
+        };
+
+        let max_string_length = match std::env::var(Config::ENV_MAX_STRING_LENGTH) {
+            Ok(value) => value.parse().or(Err(Error::InvalidMaxStringLength))?,
+            Err(_) => Config::DEFAULT_MAX_STRING_LENGTH,
+        };
+
+        // The number of seconds a login is valid
+        let session_max_age = match std::env::var(Config::ENV_SESSION_MAX_AGE) {
+            Ok(value) => Duration::from_secs(value.parse().or(Err(Error::InvalidSessionMaxAge))?),
+            Err(_) => Config::DEFAULT_SESSION_MAX_AGE,
+        };
+
+        // The number of elements a page may have, which must not exceed the hard ceiling
+        let max_limit = match std::env::var(Config::ENV_MAX_LIMIT) {
+            Ok(value) => value
+                .parse::<usize>()
+                .ok()
+                .filter(|value| (1..=usize::from(Limit::MAXIMUM)).contains(value))
+                .ok_or(Error::InvalidMaxLimit)?,
+            Err(_) => Config::DEFAULT_MAX_LIMIT,
+        };
+
+        // Print all executed statements, i.e. for debugging
+        let trace_sql = match std::env::var(Config::ENV_TRACE_SQL) {
+            Ok(value) => value.parse().or(Err(Error::InvalidTraceSql))?,
+            Err(_) => false,
+        };
+        let tracer = trace_sql.then_some(Database::log_statement as fn(&str));
+
+        // Requests use their own connections instead of waiting for a single one
+        let read_only_database = database
+            .read_only_pool(tracer)
+            .or(Err(Error::ReadOnlyConnection))?;
+        let database = database.into_pool(tracer).or(Err(Error::ConnectionPool))?;
+
         Ok(Config {
-            database: Mutex::new(database),
+            database,
+            read_only_database,
             public_assets,
             secret,
+            default_locale,
+            cookie_settings,
+            document_types,
+            max_string_length,
+            session_max_age,
+            max_limit: Limit::from(max_limit),
         })
     }
 
+    /// Load the attributes of the login cookie, falling back to the defaults for unset variables.
+    fn cookie_settings_from_env() -> Result<CookieSettings, Error> {
+        let default = CookieSettings::default();
+        let name = std::env::var(Config::ENV_COOKIE_NAME).unwrap_or(default.name);
+        let same_site = match std::env::var(Config::ENV_COOKIE_SAME_SITE) {
+            Ok(value) => {
+                CookieSettings::parse_same_site(&value).ok_or(Error::InvalidCookieSettings)?
+            }
+            Err(_) => default.same_site,
+        };
+        let secure = match std::env::var(Config::ENV_COOKIE_SECURE) {
+            Ok(value) => value.parse().or(Err(Error::InvalidCookieSettings))?,
+            Err(_) => default.secure,
+        };
+        CookieSettings::new(name, same_site, secure).ok_or(Error::InvalidCookieSettings)
+    }
+
+    /// Replace the attributes of the login cookie.
+    pub fn with_cookie_settings(mut self, cookie_settings: CookieSettings) -> Self {
+        self.cookie_settings = cookie_settings;
+        self
+    }
+
+    /// Get the attributes of the login cookie.
+    pub fn cookie_settings(&self) -> &CookieSettings {
+        &self.cookie_settings
+    }
+
+    /// Replace the file types accepted for documents.
+    pub fn with_document_types(mut self, document_types: Vec<DocumentType>) -> Self {
+        self.document_types = document_types;
+        self
+    }
+
+    /// Get the file types accepted for documents.
+    pub fn document_types(&self) -> &[DocumentType] {
+        &self.document_types
+    }
+
+    /// Replace the number of characters a string value may have.
+    pub fn with_max_string_length(mut self, max_string_length: usize) -> Self {
+        self.max_string_length = max_string_length;
+        self
+    }
+
+    /// Get the number of characters a string value may have.
+    pub fn max_string_length(&self) -> usize {
+        self.max_string_length
+    }
+
+    /// Replace the time a login is valid.
+    pub fn with_session_max_age(mut self, session_max_age: Duration) -> Self {
+        self.session_max_age = session_max_age;
+        self
+    }
+
+    /// Get the time a login is valid.
+    pub fn session_max_age(&self) -> Duration {
+        self.session_max_age
+    }
+
+    /// Replace the number of elements a page may have.
+    pub fn with_max_limit(mut self, max_limit: Limit) -> Self {
+        self.max_limit = max_limit;
+        self
+    }
+
+    /// Get the number of elements a page may have.
+    pub fn max_limit(&self) -> Limit {
+        self.max_limit
+    }
+
+    /// Get the requested number of elements, capped to the configured maximum.
+    pub fn limit(&self, requested: Option<Limit>) -> Limit {
+        requested.unwrap_or_default().at_most(self.max_limit)
+    }
+
     /// Get a (safe) NamedFile for a public asset.
     pub fn send_asset(
         &self,
@@ -58,9 +225,29 @@ impl Config {
         NamedFile::open(self.public_assets.join(path))
     }
 
-    /// Get a handle to the database.
-    pub fn database(&self) -> std::sync::MutexGuard<'_, Database> {
-        self.database.lock().expect("database mutex")
+    /// Get the locale used for formatting if the client does not specify one.
+    pub fn default_locale(&self) -> Locale {
+        self.default_locale
+    }
+
+    /// Get a connection to the database from the pool. If none becomes available in time, the service is unavailable.
+    /// In-memory databases have a single connection shared with `read_only_database`, so no connection may be acquired while holding another.
+    pub fn database(&self) -> Result<PooledDatabase, crate::Error> {
+        self.database.get().map_err(Config::unavailable)
+    }
+
+    /// Get a connection to the database which is not able to modify it. In-memory databases use the regular pool.
+    pub fn read_only_database(&self) -> Result<PooledDatabase, crate::Error> {
+        match &self.read_only_database {
+            Some(database) => database.get().map_err(Config::unavailable),
+            None => self.database(),
+        }
+    }
+
+    /// Report a connection which could not be acquired, i.e. as all connections are in use.
+    fn unavailable(error: r2d2::Error) -> crate::Error {
+        eprintln!("Acquiring a database connection failed: {}", error);
+        crate::Error::from(rocket::http::Status::ServiceUnavailable)
     }
 }
 
@@ -69,6 +256,15 @@ pub enum Error {
     AssetsNotFound,
     RandomNotAvailable,
     InvalidSecretKey,
+    ReadOnlyConnection,
+    ConnectionPool,
+    InvalidLocale,
+    InvalidCookieSettings,
+    InvalidDocumentTypes,
+    InvalidMaxStringLength,
+    InvalidTraceSql,
+    InvalidSessionMaxAge,
+    InvalidMaxLimit,
 }
 
 impl std::fmt::Display for Error {
@@ -81,6 +277,48 @@ impl std::fmt::Display for Error {
             ),
             Error::RandomNotAvailable => f.write_str("unable to get random data for secret key"),
             Error::InvalidSecretKey => f.write_str("the specified secret key is invalid"),
+            Error::InvalidLocale => write!(
+                f,
+                "env variable {} does not contain a supported language",
+                Config::ENV_LOCALE
+            ),
+            Error::InvalidCookieSettings => write!(
+                f,
+                "the cookie settings in {}, {}, and {} are invalid; 'SameSite=None' requires a secure cookie",
+                Config::ENV_COOKIE_NAME,
+                Config::ENV_COOKIE_SAME_SITE,
+                Config::ENV_COOKIE_SECURE
+            ),
+            Error::InvalidDocumentTypes => write!(
+                f,
+                "env variable {} contains unsupported document types",
+                Config::ENV_DOCUMENT_TYPES
+            ),
+            Error::InvalidMaxStringLength => write!(
+                f,
+                "env variable {} is not a valid number",
+                Config::ENV_MAX_STRING_LENGTH
+            ),
+            Error::InvalidTraceSql => write!(
+                f,
+                "env variable {} is neither 'true' nor 'false'",
+                Config::ENV_TRACE_SQL
+            ),
+            Error::InvalidSessionMaxAge => write!(
+                f,
+                "env variable {} is not a valid number of seconds",
+                Config::ENV_SESSION_MAX_AGE
+            ),
+            Error::InvalidMaxLimit => write!(
+                f,
+                "env variable {} is not a number between 1 and {}",
+                Config::ENV_MAX_LIMIT,
+                Limit::MAXIMUM
+            ),
+            Error::ReadOnlyConnection => {
+                f.write_str("unable to open a read-only connection to the database")
+            }
+            Error::ConnectionPool => f.write_str("unable to open connections to the database"),
         }
     }
 }
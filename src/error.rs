@@ -1,5 +1,7 @@
 use rocket::{
+    data::{self, Data, FromData},
     http::{ContentType, Status},
+    outcome::Outcome,
     response::Responder,
     serde::json::Json,
     Request, Response,
@@ -11,7 +13,17 @@ pub enum Error {
     DatabaseError(crate::backend::database::Error),
     NotFound,
     ConstraintViolation,
+    /// The element which should be created exists already.
+    AlreadyExists,
     WrongPassword,
+    /// The credentials are valid, but the account is inactive.
+    InactiveUser,
+    /// A value was rejected for the given reason.
+    InvalidValue(String),
+    /// The change was refused for the given reason, as it conflicts with the remaining data.
+    Conflict(String),
+    /// An error while reading or writing files.
+    IoError(String),
     /// An error generated by an error handler.
     OtherError(rocket::http::Status),
 }
@@ -40,6 +52,19 @@ impl Error {
     }
 }
 
+impl Error {
+    /// The header containing the id of an internal server error, which is logged alongside the details.
+    pub const HEADER_ERROR_ID: &str = "X-Error-Id";
+
+    /// Generate a random id to correlate an error response with its log entry.
+    fn generate_error_id() -> String {
+        let mut random = [0u8; 8];
+        // The id is only used for correlation, so a failing random generator is no reason to fail the response.
+        let _ = getrandom::getrandom(&mut random);
+        random.map(|value| format!("{:02x}", value)).concat()
+    }
+}
+
 impl From<rocket::http::Status> for Error {
     fn from(value: rocket::http::Status) -> Self {
         Error::OtherError(value)
@@ -52,7 +77,11 @@ impl std::fmt::Display for Error {
             Error::DatabaseError(error) => write!(f, "database error: {}", error),
             Error::NotFound => write!(f, "element not found"),
             Error::ConstraintViolation => write!(f, "invalid value"),
-            Error::WrongPassword => write!(f, "invalid password"),
+            Error::AlreadyExists => write!(f, "element exists already"),
+            Error::WrongPassword => write!(f, "invalid credentials"),
+            Error::InactiveUser => write!(f, "account inactive"),
+            Error::InvalidValue(reason) | Error::Conflict(reason) => f.write_str(reason),
+            Error::IoError(error) => write!(f, "io error: {}", error),
             Error::OtherError(error) => f.write_str(error.reason_lossy()),
         }
     }
@@ -67,25 +96,55 @@ impl From<crate::backend::database::Error> for Error {
     }
 }
 
+/// Amounts are rejected like other invalid values, including sums too large to be stored.
+impl From<crate::backend::accounting::AmountError> for Error {
+    fn from(value: crate::backend::accounting::AmountError) -> Self {
+        Error::InvalidValue(value.to_string())
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::IoError(value.to_string())
+    }
+}
+
+impl Error {
+    /// Convert an error during the creation of an archive.
+    pub fn from_zip(value: zip::result::ZipError) -> Self {
+        Error::IoError(value.to_string())
+    }
+}
+
 impl std::error::Error for Error {}
 
 impl<'r, 'o: 'r> Responder<'r, 'o> for Error {
     fn respond_to(self, request: &'r rocket::Request<'_>) -> rocket::response::Result<'o> {
-        let details: String = format!("{}", self);
+        let status = match self {
+            Error::ConstraintViolation | Error::InvalidValue(_) => Status::BadRequest,
+            Error::AlreadyExists | Error::Conflict(_) => Status::Conflict,
+            Error::DatabaseError(_) | Error::IoError(_) => Status::InternalServerError,
+            Error::NotFound => Status::NotFound,
+            Error::WrongPassword => Status::Unauthorized,
+            Error::InactiveUser => Status::Locked,
+            Error::OtherError(error) => error,
+        };
+        if status != Status::InternalServerError {
+            return Error::generate_error(request, status, self.to_string());
+        }
+
+        // Internal details are only logged. The client gets an id to refer to the log entry instead.
+        let error_id = Error::generate_error_id();
+        eprintln!("error {}: {}", error_id, self);
         Error::generate_error(
             request,
-            match self {
-                Error::ConstraintViolation => Status::BadRequest,
-                Error::DatabaseError(database_error) => {
-                    eprintln!("{}", database_error);
-                    Status::InternalServerError
-                }
-                Error::NotFound => Status::NotFound,
-                Error::WrongPassword => Status::Unauthorized,
-                Error::OtherError(error) => error,
-            },
-            details,
+            status,
+            format!("internal server error (error id {})", error_id),
         )
+        .map(|mut response| {
+            response.set_raw_header(Error::HEADER_ERROR_ID, error_id);
+            response
+        })
     }
 }
 
@@ -94,6 +153,65 @@ pub async fn error_handler(status: rocket::http::Status, _: &rocket::Request<'_>
     status.into()
 }
 
+/// The reason a request body could not be parsed, cached for the catchers.
+struct ParseFailure(Option<String>);
+
+/// A data guard remembering why the wrapped guard failed, so the catchers could describe it.
+pub struct ReportParseFailure<T>(pub T);
+
+#[rocket::async_trait]
+impl<'r, T> FromData<'r> for ReportParseFailure<T>
+where
+    T: FromData<'r>,
+    T::Error: std::fmt::Display,
+{
+    type Error = T::Error;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        match T::from_data(req, data).await {
+            Outcome::Success(value) => Outcome::Success(ReportParseFailure(value)),
+            Outcome::Error((status, error)) => {
+                req.local_cache(|| ParseFailure(Some(error.to_string())));
+                Outcome::Error((status, error))
+            }
+            Outcome::Forward(value) => Outcome::Forward(value),
+        }
+    }
+}
+
+/// Describe invalid JSON input with an object instead of the plain status.
+fn describe_parse_failure(
+    status: Status,
+    request: &Request<'_>,
+) -> Result<(Status, Json<serde_json::Value>), Error> {
+    match request.content_type() {
+        Some(value) if value.is_json() => {
+            let details = &request.local_cache(|| ParseFailure(None)).0;
+            Ok((
+                status,
+                Json(serde_json::json!({ "error": status.reason_lossy(), "details": details })),
+            ))
+        }
+        _ => Err(status.into()),
+    }
+}
+
+#[catch(400)]
+pub async fn bad_request_handler(
+    status: Status,
+    request: &Request<'_>,
+) -> Result<(Status, Json<serde_json::Value>), Error> {
+    describe_parse_failure(status, request)
+}
+
+#[catch(422)]
+pub async fn unprocessable_entity_handler(
+    status: Status,
+    request: &Request<'_>,
+) -> Result<(Status, Json<serde_json::Value>), Error> {
+    describe_parse_failure(status, request)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::rocket;
@@ -117,6 +235,70 @@ mod tests {
         rocket::serde::json::from_str::<String>(&response).expect("valid json");
     }
 
+    #[test]
+    fn test_parse_failure_described() {
+        let client = crate::tests::login(rocket());
+        let response = client
+            .post("/persons")
+            .header(rocket::http::ContentType::JSON)
+            .body(r#"{"name": "Max", "address": 42}"#)
+            .dispatch();
+
+        assert_eq!(response.status(), rocket::http::Status::UnprocessableEntity);
+        assert_eq!(
+            response.content_type(),
+            Some(rocket::http::ContentType::JSON)
+        );
+
+        let response: serde_json::Value =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        assert_eq!(response["error"], "Unprocessable Entity");
+        assert!(response["details"]
+            .as_str()
+            .expect("details available")
+            .contains("expected a string"));
+    }
+
+    #[test]
+    fn test_syntax_error_described() {
+        let client = crate::tests::login(rocket());
+        let response = client
+            .post("/persons")
+            .header(rocket::http::ContentType::JSON)
+            .body(r#"{"name": "Max""#)
+            .dispatch();
+
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        let response: serde_json::Value =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        assert_eq!(response["error"], "Bad Request");
+        assert!(response["details"].is_string());
+    }
+
+    #[test]
+    fn test_internal_error_id() {
+        use crate::Error;
+        use rocket::response::Responder;
+
+        let client = Client::tracked(rocket()).expect("valid client");
+        let request = client.get("/").header(rocket::http::ContentType::JSON);
+        let mut response = Error::IoError(String::from("secret details"))
+            .respond_to(request.inner())
+            .expect("valid response");
+
+        assert_eq!(response.status(), rocket::http::Status::InternalServerError);
+        let error_id = response
+            .headers()
+            .get_one(Error::HEADER_ERROR_ID)
+            .expect("error id available")
+            .to_string();
+        assert_eq!(error_id.len(), 16);
+
+        let body = rocket::async_test(response.body_mut().to_string()).expect("valid body");
+        assert!(body.contains(&error_id));
+        assert!(!body.contains("secret details"));
+    }
+
     #[test]
     fn test_error_catching_html() {
         let client = Client::tracked(rocket()).expect("valid client");
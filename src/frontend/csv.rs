@@ -0,0 +1,332 @@
+use std::io::Write;
+
+use rocket::form::{self, DataField, Errors, FromFormField, ValueField};
+
+use crate::backend::{
+    accounting::{Account, Amount, Budget, Category, CostCenter, Entry},
+    database::{Database, Record, Selectable},
+    document::Document,
+    person::{Group, Person},
+    user::User,
+    Pagination, WhereClause,
+};
+
+/// An entry which could be exported as a row within a CSV file.
+pub trait CsvRenderable: Selectable {
+    /// The name of the columns.
+    const CSV_HEADER: &'static [&'static str];
+
+    /// Convert an output into the values of a row.
+    fn to_csv_row(output: Self::Output, dialect: CsvDialect) -> Vec<String>;
+
+    /// Write all the entries of the table including the header as CSV. Unlike the API, this is not limited to a page.
+    /// The entries are written while they are read, so the table is never loaded at once.
+    fn write_csv(
+        database: &Database,
+        mut writer: impl Write,
+        dialect: CsvDialect,
+    ) -> Result<(), crate::Error> {
+        write_csv_row(&mut writer, Self::CSV_HEADER, dialect)?;
+        Self::for_each(database, |output| {
+            write_csv_row(&mut writer, &Self::to_csv_row(output, dialect), dialect)
+                .map_err(crate::Error::from)
+        })
+    }
+
+    /// Write a page of the entries matching the filter including the header as CSV, limited like the API.
+    fn write_csv_page(
+        database: &Database,
+        writer: impl Write,
+        dialect: CsvDialect,
+        pagination: Pagination<Self>,
+        filter: &WhereClause<Self>,
+    ) -> Result<(), crate::Error> {
+        write_csv_outputs::<Self>(
+            writer,
+            Self::select_all_sorted(database, pagination, Some(filter))?,
+            dialect,
+        )
+    }
+}
+
+/// Write the header followed by a row for each of the outputs.
+fn write_csv_outputs<T: CsvRenderable>(
+    mut writer: impl Write,
+    outputs: Vec<T::Output>,
+    dialect: CsvDialect,
+) -> Result<(), crate::Error> {
+    write_csv_row(&mut writer, T::CSV_HEADER, dialect)?;
+    for output in outputs {
+        write_csv_row(&mut writer, &T::to_csv_row(output, dialect), dialect)?;
+    }
+    Ok(())
+}
+
+/// The conventions spreadsheet applications expect when importing a CSV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvDialect {
+    /// Fields are separated by `,` and amounts use `.` as decimal separator.
+    #[default]
+    Us,
+    /// Fields are separated by `;` and amounts use `,` as decimal separator, as expected by a German Excel.
+    ExcelDe,
+}
+
+impl CsvDialect {
+    /// The character separating two fields.
+    pub fn delimiter(&self) -> char {
+        match self {
+            CsvDialect::Us => ',',
+            CsvDialect::ExcelDe => ';',
+        }
+    }
+
+    /// Format an amount of money with the corresponding decimal separator.
+    pub fn format_amount(&self, amount: Amount) -> String {
+        match self {
+            CsvDialect::Us => amount.to_string(),
+            CsvDialect::ExcelDe => amount.to_string().replace('.', ","),
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            value if value.eq_ignore_ascii_case("us") => Some(CsvDialect::Us),
+            value if value.eq_ignore_ascii_case("excel-de") => Some(CsvDialect::ExcelDe),
+            _ => None,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for CsvDialect {
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        CsvDialect::parse(field.value).ok_or_else(|| Errors::new().with_name(field.name))
+    }
+
+    async fn from_data(field: DataField<'r, '_>) -> form::Result<'r, Self> {
+        String::from_data(field)
+            .await
+            .and_then(|value| CsvDialect::parse(&value).ok_or_else(Errors::new))
+    }
+}
+
+/// Write a single row, escaping the fields according to RFC 4180.
+pub fn write_csv_row(
+    writer: &mut impl Write,
+    fields: &[impl AsRef<str>],
+    dialect: CsvDialect,
+) -> std::io::Result<()> {
+    let delimiter = dialect.delimiter();
+    for (index, field) in fields.iter().enumerate() {
+        if index > 0 {
+            write!(writer, "{}", delimiter)?;
+        }
+
+        let field = field.as_ref();
+        if field.contains([delimiter, '"', '\r', '\n']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")
+}
+
+impl CsvRenderable for Person {
+    const CSV_HEADER: &'static [&'static str] =
+        &["id", "name", "address", "email", "birthday", "comment"];
+
+    fn to_csv_row(person: Record<Self>, _: CsvDialect) -> Vec<String> {
+        vec![
+            person.identifier.raw_index().to_string(),
+            person.value.name,
+            person.value.address,
+            person.value.email.map(String::from).unwrap_or_default(),
+            person
+                .value
+                .birthday
+                .map(|value| value.to_string())
+                .unwrap_or_default(),
+            person.value.comment.unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRenderable for Group {
+    const CSV_HEADER: &'static [&'static str] = &["id", "description"];
+
+    fn to_csv_row(group: Record<Self>, _: CsvDialect) -> Vec<String> {
+        vec![
+            group.identifier.raw_index().to_string(),
+            group.value.description,
+        ]
+    }
+}
+
+/// The document itself is not exported, only its metadata.
+impl CsvRenderable for Document {
+    const CSV_HEADER: &'static [&'static str] = &[
+        "id",
+        "processed_by",
+        "from_person",
+        "to_person",
+        "recieved",
+        "processed",
+        "description",
+    ];
+
+    fn to_csv_row(document: <Document as Selectable>::Output, _: CsvDialect) -> Vec<String> {
+        vec![
+            document.identifier.raw_index().to_string(),
+            document.processed_by.raw_index().to_string(),
+            document.from_person.raw_index().to_string(),
+            document.to_person.raw_index().to_string(),
+            document.recieved.to_string(),
+            document.processed.to_string(),
+            document.description,
+        ]
+    }
+}
+
+impl CsvRenderable for User {
+    const CSV_HEADER: &'static [&'static str] =
+        &["id", "username", "active", "creation_date", "related_to"];
+
+    fn to_csv_row(user: <User as Selectable>::Output, _: CsvDialect) -> Vec<String> {
+        vec![
+            user.identifier.raw_index().to_string(),
+            user.username,
+            user.active.to_string(),
+            user.creation_date.to_string(),
+            user.related_to
+                .map(|value| value.raw_index().to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRenderable for Category {
+    const CSV_HEADER: &'static [&'static str] = &["id", "description", "parent"];
+
+    fn to_csv_row(category: Record<Self>, _: CsvDialect) -> Vec<String> {
+        vec![
+            category.identifier.raw_index().to_string(),
+            category.value.description,
+            category
+                .value
+                .parent
+                .map(|value| value.raw_index().to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRenderable for CostCenter {
+    const CSV_HEADER: &'static [&'static str] = &["id", "description", "parent"];
+
+    fn to_csv_row(cost_center: Record<Self>, _: CsvDialect) -> Vec<String> {
+        vec![
+            cost_center.identifier.raw_index().to_string(),
+            cost_center.value.description,
+            cost_center
+                .value
+                .parent
+                .map(|value| value.raw_index().to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+impl CsvRenderable for Budget {
+    const CSV_HEADER: &'static [&'static str] =
+        &["id", "cost_center", "year", "target", "currency"];
+
+    fn to_csv_row(budget: Record<Self>, dialect: CsvDialect) -> Vec<String> {
+        vec![
+            budget.identifier.raw_index().to_string(),
+            budget.value.cost_center.raw_index().to_string(),
+            budget.value.year.to_string(),
+            dialect.format_amount(budget.value.target),
+            budget.value.currency.to_string(),
+        ]
+    }
+}
+
+impl CsvRenderable for Account {
+    const CSV_HEADER: &'static [&'static str] = &["id", "code", "category", "description", "kind"];
+
+    fn to_csv_row(account: Record<Self>, _: CsvDialect) -> Vec<String> {
+        vec![
+            account.identifier.raw_index().to_string(),
+            account.value.code.to_string(),
+            account.value.category.raw_index().to_string(),
+            account.value.description,
+            account.value.kind.to_string(),
+        ]
+    }
+}
+
+impl CsvRenderable for Entry {
+    const CSV_HEADER: &'static [&'static str] = &[
+        "id",
+        "evidence",
+        "account",
+        "cost_center",
+        "amount",
+        "description",
+        "reference_number",
+        "currency",
+    ];
+
+    fn to_csv_row(entry: Record<Self>, dialect: CsvDialect) -> Vec<String> {
+        vec![
+            entry.identifier.raw_index().to_string(),
+            entry.value.evidence.raw_index().to_string(),
+            entry.value.account.raw_index().to_string(),
+            entry.value.cost_center.raw_index().to_string(),
+            dialect.format_amount(entry.value.amount),
+            entry.value.description,
+            entry.value.reference_number.unwrap_or_default(),
+            entry.value.currency.to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_row_escaping() {
+        let mut output = Vec::new();
+        write_csv_row(
+            &mut output,
+            &["plain", "with,comma", "with \"quote\""],
+            CsvDialect::Us,
+        )
+        .expect("writing into memory");
+        assert_eq!(
+            String::from_utf8(output).expect("valid utf8"),
+            "plain,\"with,comma\",\"with \"\"quote\"\"\"\r\n"
+        );
+    }
+
+    #[test]
+    fn test_dialects() {
+        let amount = Amount::new(123, 45).expect("valid amount");
+        for (dialect, expected) in [
+            (CsvDialect::Us, "123.45,a;b\r\n"),
+            (CsvDialect::ExcelDe, "123,45;\"a;b\"\r\n"),
+        ] {
+            let mut output = Vec::new();
+            write_csv_row(
+                &mut output,
+                &[dialect.format_amount(amount), String::from("a;b")],
+                dialect,
+            )
+            .expect("writing into memory");
+            assert_eq!(String::from_utf8(output).expect("valid utf8"), expected);
+        }
+    }
+}
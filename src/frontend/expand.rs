@@ -0,0 +1,133 @@
+use rocket::form::{self, DataField, FromFormField, ValueField};
+use rocket::serde::Serialize;
+
+use super::util::{ForeignKeyStorage, Map};
+use crate::backend::{
+    accounting::{Account, Amount, CostCenter, Currency, Entry},
+    database::{Database, PrimaryKey, Referenceable, Selectable},
+    document::Document,
+    Pagination,
+};
+
+/// A foreign key which is optionally expanded with its human-readable name.
+#[derive(Debug, Serialize)]
+#[serde(untagged, bound = "")]
+pub enum Reference<T: Referenceable> {
+    Plain(PrimaryKey<T>),
+    Expanded {
+        id: PrimaryKey<T>,
+        name: Option<String>,
+    },
+}
+
+impl<T: Referenceable> Reference<T> {
+    fn new(
+        primary_key: PrimaryKey<T>,
+        expand: bool,
+        foreign_keys: &ForeignKeyStorage<'_, Map>,
+    ) -> Self {
+        match expand {
+            true => Reference::Expanded {
+                id: primary_key,
+                name: foreign_keys.get(primary_key).map(String::from),
+            },
+            false => Reference::Plain(primary_key),
+        }
+    }
+}
+
+/// The foreign keys of an entry which should be expanded, parsed from a list like "account,cost_center".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EntryExpansion {
+    pub evidence: bool,
+    pub account: bool,
+    pub cost_center: bool,
+}
+
+impl EntryExpansion {
+    fn parse(value: &str) -> Option<Self> {
+        let mut expansion: EntryExpansion = Default::default();
+        for field in value
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+        {
+            match field {
+                "evidence" => expansion.evidence = true,
+                "account" => expansion.account = true,
+                "cost_center" => expansion.cost_center = true,
+                _ => return None,
+            }
+        }
+        Some(expansion)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for EntryExpansion {
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        EntryExpansion::parse(field.value).ok_or_else(|| {
+            form::Error::validation("unsupported expansion")
+                .with_name(field.name)
+                .into()
+        })
+    }
+
+    async fn from_data(field: DataField<'r, '_>) -> form::Result<'r, Self> {
+        String::from_data(field).await.and_then(|value| {
+            EntryExpansion::parse(&value)
+                .ok_or_else(|| form::Error::validation("unsupported expansion").into())
+        })
+    }
+}
+
+/// An entry whose foreign keys may be replaced by objects containing their human-readable names.
+#[derive(Debug, Serialize)]
+pub struct ExpandedEntry {
+    pub identifier: PrimaryKey<Entry>,
+    pub evidence: Reference<Document>,
+    pub account: Reference<Account>,
+    pub cost_center: Reference<CostCenter>,
+    pub amount: Amount,
+    pub description: String,
+    pub reference_number: Option<String>,
+    pub currency: Currency,
+}
+
+impl ExpandedEntry {
+    /// Load the entries and resolve the names of the requested foreign keys.
+    pub fn load(
+        database: &Database,
+        pagination: Pagination<Entry>,
+        expansion: EntryExpansion,
+    ) -> Result<Vec<Self>, crate::backend::database::Error> {
+        let mut foreign_keys = ForeignKeyStorage::<Map>::from(database);
+        if expansion.evidence {
+            foreign_keys.add::<Document>()?;
+        }
+        if expansion.account {
+            foreign_keys.add::<Account>()?;
+        }
+        if expansion.cost_center {
+            foreign_keys.add::<CostCenter>()?;
+        }
+
+        Ok(Entry::select_all_sorted(database, pagination, None)?
+            .into_iter()
+            .map(|entry| ExpandedEntry {
+                identifier: entry.identifier,
+                evidence: Reference::new(entry.value.evidence, expansion.evidence, &foreign_keys),
+                account: Reference::new(entry.value.account, expansion.account, &foreign_keys),
+                cost_center: Reference::new(
+                    entry.value.cost_center,
+                    expansion.cost_center,
+                    &foreign_keys,
+                ),
+                amount: entry.value.amount,
+                description: entry.value.description,
+                reference_number: entry.value.reference_number,
+                currency: entry.value.currency,
+            })
+            .collect())
+    }
+}
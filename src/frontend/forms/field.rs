@@ -35,6 +35,8 @@ impl<'a, 'b> Serialize for Field {
             InputType::Text(meta, _)
             | InputType::Number(meta)
             | InputType::Email(meta)
+            | InputType::Tel(meta)
+            | InputType::Url(meta)
             | InputType::Date(meta)
             | InputType::Password(meta) => {
                 let mut result = serializer.serialize_struct("Field", NUM_GENERAL_ELEMENTS + 3)?;
@@ -61,7 +63,7 @@ impl<'a, 'b> Serialize for Field {
             }
             InputType::File(meta_data) => {
                 let mut result = serializer.serialize_struct("Field", NUM_GENERAL_ELEMENTS + 3)?;
-                result.serialize_field("accept", meta_data.extensions)?;
+                result.serialize_field("accept", &meta_data.extensions.join(","))?;
                 result.serialize_field("label", meta_data.label)?;
                 result.serialize_field("multiple", &meta_data.multiple)?;
                 result
@@ -80,6 +82,8 @@ impl<'a, 'b> Serialize for Field {
 pub enum InputType {
     Text(Metadata, bool),
     Email(Metadata),
+    Tel(Metadata),
+    Url(Metadata),
     Number(Metadata),
     Password(Metadata),
     Date(Metadata),
@@ -107,6 +111,8 @@ impl InputType {
             InputType::ForeignKey(_) => "select",
             InputType::Password(_) => "password",
             InputType::Email(_) => "email",
+            InputType::Tel(_) => "tel",
+            InputType::Url(_) => "url",
             InputType::Date(_) => "date",
             InputType::Hidden(_) => "hidden",
             InputType::File(_) => "file",
@@ -129,10 +135,11 @@ pub struct Metadata {
     pub required: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FileMetadata {
     pub label: &'static str,
-    pub extensions: &'static [&'static str],
+    /// The accepted extensions or MIME types.
+    pub extensions: std::borrow::Cow<'static, [&'static str]>,
     pub multiple: bool,
 }
 
@@ -191,3 +198,43 @@ impl ForeignKeyMetaData {
         self.list_name
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Field, InputType, Metadata};
+
+    #[test]
+    fn test_serialize_tel_and_url() {
+        for (input_type, html_value) in [
+            (
+                InputType::Tel(Metadata {
+                    label: "Telephone",
+                    placeholder: None,
+                    required: false,
+                }),
+                "tel",
+            ),
+            (
+                InputType::Url(Metadata {
+                    label: "Website",
+                    placeholder: Some("https://"),
+                    required: true,
+                }),
+                "url",
+            ),
+        ] {
+            let value = serde_json::to_value(Field::new("contact", input_type.clone()))
+                .expect("valid json");
+            assert_eq!(value["input_type"], html_value);
+            assert_eq!(value["element_type"], "input");
+            assert_eq!(value["name"], "contact");
+            match input_type {
+                InputType::Tel(meta) | InputType::Url(meta) => {
+                    assert_eq!(value["label"], meta.label);
+                    assert_eq!(value["required"], meta.required);
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
@@ -1,11 +1,13 @@
+use crate::backend::accounting::Hierarchical;
+use crate::backend::database::{Indexable, PrimaryKey};
 use crate::backend::person::Person;
 use crate::util::FormInputType;
-use crate::{auth::AuthenticatedUser, backend::database::Database};
+use crate::{auth::AuthenticatedUser, backend::database::Database, Config};
 
 use super::{Field, FileMetadata, InputType, InsertFormRenderer, Metadata};
 
 /// A database entry which might be inserted over a form.
-pub trait InsertableDatabaseEntry: Sized {
+pub trait InsertableDatabaseEntry: Sized + serde::Serialize {
     /// The method hwo data is send to the webserver.
     type PostMethod: for<'a> FormInputType<'a>;
     /// The type for storing the fields. Should be an array of fields. However, this avoids generic arguments in the trait definition.
@@ -13,6 +15,8 @@ pub trait InsertableDatabaseEntry: Sized {
 
     const NAME: &'static str;
     const FIELDS: Self::FieldsType;
+    /// Whether only administrators may insert or replace such entries.
+    const ADMIN_ONLY: bool = false;
 
     fn prepare_rendering<'a>(
         post_url: &'static str,
@@ -21,11 +25,77 @@ pub trait InsertableDatabaseEntry: Sized {
     ) -> InsertFormRenderer<'a, Self> {
         InsertFormRenderer::new(post_url, database, user)
     }
+
+    /// Check the entry against the configuration before it is inserted. By default, overlong strings are rejected.
+    fn validate(&self, config: &Config) -> Result<(), crate::Error> {
+        let value = serde_json::to_value(self).map_err(|_| rocket::http::Status::BadRequest)?;
+        check_string_lengths(&value, config.max_string_length())
+    }
+
+    /// Complete the entry before it replaces the existing one. By default, it is used as it is.
+    fn prepare_update(
+        &mut self,
+        _database: &Database,
+        _identifier: PrimaryKey<Self>,
+    ) -> Result<(), crate::Error>
+    where
+        Self: Indexable,
+    {
+        Ok(())
+    }
+
+    /// Check within the transaction whether replacing the entry, or removing it without a replacement, conflicts with the remaining entries.
+    /// Returns the reason for refusing the change. By default, no change conflicts.
+    fn conflicting_change(
+        _database: &Database,
+        _identifier: PrimaryKey<Self>,
+        _replacement: Option<&Self>,
+    ) -> Result<Option<&'static str>, crate::backend::database::Error>
+    where
+        Self: Indexable,
+    {
+        Ok(None)
+    }
+}
+
+/// Reject a parent which would nest the entry below itself.
+fn check_parent<T: Hierarchical>(
+    database: &Database,
+    identifier: PrimaryKey<T>,
+    parent: Option<PrimaryKey<T>>,
+) -> Result<(), crate::Error> {
+    match T::creates_cycle(database, identifier, parent)? {
+        true => Err(crate::Error::InvalidValue(String::from(
+            "the parent would create a cycle",
+        ))),
+        false => Ok(()),
+    }
+}
+
+/// Reject all the strings within the value which are longer than the maximum number of characters.
+fn check_string_lengths(value: &serde_json::Value, maximum: usize) -> Result<(), crate::Error> {
+    match value {
+        serde_json::Value::String(value) => check_string_length(value, maximum),
+        serde_json::Value::Array(values) => values
+            .iter()
+            .try_for_each(|value| check_string_lengths(value, maximum)),
+        serde_json::Value::Object(values) => values
+            .values()
+            .try_for_each(|value| check_string_lengths(value, maximum)),
+        _ => Ok(()),
+    }
+}
+
+fn check_string_length(value: &str, maximum: usize) -> Result<(), crate::Error> {
+    match value.chars().count() > maximum {
+        true => Err(rocket::http::Status::BadRequest.into()),
+        false => Ok(()),
+    }
 }
 
 impl InsertableDatabaseEntry for crate::backend::person::Person {
     const NAME: &'static str = "New person";
-    const FIELDS: [Field; 5] = [
+    const FIELDS: [Field; 10] = [
         Field::new(
             "name",
             InputType::Text(
@@ -53,7 +123,7 @@ impl InsertableDatabaseEntry for crate::backend::person::Person {
             InputType::Email(Metadata {
                 label: "E-Mail",
                 placeholder: Some("E-mail of the person"),
-                required: true,
+                required: false,
             }),
         ),
         Field::new(
@@ -75,10 +145,56 @@ impl InsertableDatabaseEntry for crate::backend::person::Person {
                 true,
             ),
         ),
+        Field::new(
+            "telephone",
+            InputType::Tel(Metadata {
+                label: "Telephone",
+                placeholder: Some("Telephone number of the person"),
+                required: false,
+            }),
+        ),
+        Field::new(
+            "mobile",
+            InputType::Tel(Metadata {
+                label: "Mobile",
+                placeholder: Some("Mobile number of the person"),
+                required: false,
+            }),
+        ),
+        Field::new(
+            "company",
+            InputType::Text(
+                Metadata {
+                    label: "Company",
+                    placeholder: Some("Company the person works for"),
+                    required: false,
+                },
+                false,
+            ),
+        ),
+        Field::new(
+            "role",
+            InputType::Text(
+                Metadata {
+                    label: "Role",
+                    placeholder: Some("Role of the person within the company"),
+                    required: false,
+                },
+                false,
+            ),
+        ),
+        Field::new(
+            "website",
+            InputType::Url(Metadata {
+                label: "Website",
+                placeholder: Some("Website of the person"),
+                required: false,
+            }),
+        ),
     ];
 
     type PostMethod = rocket::serde::json::Json<Self>;
-    type FieldsType = [Field; 5];
+    type FieldsType = [Field; 10];
 }
 
 impl InsertableDatabaseEntry for crate::backend::document::Document {
@@ -88,7 +204,7 @@ impl InsertableDatabaseEntry for crate::backend::document::Document {
             "document",
             InputType::File(FileMetadata {
                 label: "File",
-                extensions: &[".pdf"],
+                extensions: std::borrow::Cow::Borrowed(&[".pdf", "application/pdf"]),
                 multiple: false,
             }),
         ),
@@ -138,6 +254,18 @@ impl InsertableDatabaseEntry for crate::backend::document::Document {
         ),
     ];
 
+    /// Only the configured file types are accepted. The file itself is not serialized for checking the strings.
+    fn validate(&self, config: &Config) -> Result<(), crate::Error> {
+        check_string_length(&self.description, config.max_string_length())?;
+        match crate::backend::document::DocumentType::detect(
+            config.document_types(),
+            &self.document,
+        ) {
+            Some(_) => Ok(()),
+            None => Err(rocket::http::Status::UnsupportedMediaType.into()),
+        }
+    }
+
     type PostMethod = crate::util::FlexibleInput<Self>;
     type FieldsType = [Field; 7];
 }
@@ -162,6 +290,7 @@ impl InsertableDatabaseEntry for crate::backend::person::Group {
 
 impl InsertableDatabaseEntry for crate::backend::user::User {
     const NAME: &'static str = "New user";
+    const ADMIN_ONLY: bool = true;
     const FIELDS: [Field; 5] = [
         Field::new(
             "username",
@@ -197,44 +326,149 @@ impl InsertableDatabaseEntry for crate::backend::user::User {
         Field::new("active", InputType::new_hidden(|_| String::from("true"))),
     ];
 
+    /// Without a new password, the existing hash is kept. As the hash depends on the user name, renaming requires a new password.
+    fn prepare_update(
+        &mut self,
+        database: &Database,
+        identifier: PrimaryKey<Self>,
+    ) -> Result<(), crate::Error> {
+        if self.password_hash.is_valid() {
+            return Ok(());
+        }
+        let existing = crate::backend::user::User::select_record(database, identifier)?
+            .ok_or(crate::Error::NotFound)?
+            .into_inner();
+        if existing.username != self.username {
+            return Err(crate::Error::InvalidValue(String::from(
+                "changing the user name requires a new password",
+            )));
+        }
+        self.password_hash = existing.password_hash;
+        Ok(())
+    }
+
+    /// Without an active administrator, nobody could manage the users anymore.
+    fn conflicting_change(
+        database: &Database,
+        identifier: PrimaryKey<Self>,
+        replacement: Option<&Self>,
+    ) -> Result<Option<&'static str>, crate::backend::database::Error> {
+        let remains_admin = replacement.is_some_and(|user| user.active && user.is_admin);
+        if remains_admin
+            || crate::backend::user::User::count_other_active_admins(database, identifier)? > 0
+        {
+            return Ok(None);
+        }
+        Ok(Some("at least one active administrator must remain"))
+    }
+
     type PostMethod = rocket::serde::json::Json<Self>;
     type FieldsType = [Field; 5];
 }
 
 impl InsertableDatabaseEntry for crate::backend::accounting::Category {
     const NAME: &'static str = "New category";
-    const FIELDS: [Field; 1] = [Field::new(
-        "description",
-        InputType::Text(
-            Metadata {
-                label: "Description",
-                placeholder: Some("Description of the new category"),
-                required: true,
-            },
-            false,
+    const FIELDS: [Field; 2] = [
+        Field::new(
+            "description",
+            InputType::Text(
+                Metadata {
+                    label: "Description",
+                    placeholder: Some("Description of the new category"),
+                    required: true,
+                },
+                false,
+            ),
         ),
-    )];
+        Field::new(
+            "parent",
+            InputType::new_foreign::<crate::backend::accounting::Category>(Metadata {
+                label: "Parent",
+                placeholder: Some("The category this one is nested below"),
+                required: false,
+            }),
+        ),
+    ];
+
+    fn prepare_update(
+        &mut self,
+        database: &Database,
+        identifier: PrimaryKey<Self>,
+    ) -> Result<(), crate::Error> {
+        check_parent(database, identifier, self.parent)
+    }
 
     type PostMethod = rocket::serde::json::Json<Self>;
-    type FieldsType = [Field; 1];
+    type FieldsType = [Field; 2];
 }
 
 impl InsertableDatabaseEntry for crate::backend::accounting::CostCenter {
     const NAME: &'static str = "New cost center";
-    const FIELDS: [Field; 1] = [Field::new(
-        "description",
-        InputType::Text(
-            Metadata {
-                label: "Description",
-                placeholder: Some("Description of the new cost center"),
+    const FIELDS: [Field; 2] = [
+        Field::new(
+            "description",
+            InputType::Text(
+                Metadata {
+                    label: "Description",
+                    placeholder: Some("Description of the new cost center"),
+                    required: true,
+                },
+                false,
+            ),
+        ),
+        Field::new(
+            "parent",
+            InputType::new_foreign::<crate::backend::accounting::CostCenter>(Metadata {
+                label: "Parent",
+                placeholder: Some("The cost center this one is nested below"),
+                required: false,
+            }),
+        ),
+    ];
+
+    fn prepare_update(
+        &mut self,
+        database: &Database,
+        identifier: PrimaryKey<Self>,
+    ) -> Result<(), crate::Error> {
+        check_parent(database, identifier, self.parent)
+    }
+
+    type PostMethod = rocket::serde::json::Json<Self>;
+    type FieldsType = [Field; 2];
+}
+
+impl InsertableDatabaseEntry for crate::backend::accounting::Budget {
+    const NAME: &'static str = "New budget";
+    const FIELDS: [Field; 3] = [
+        Field::new(
+            "cost_center",
+            InputType::new_foreign::<crate::backend::accounting::CostCenter>(Metadata {
+                label: "Cost center",
+                placeholder: Some("The cost center this budget is planned for"),
                 required: true,
-            },
-            false,
+            }),
         ),
-    )];
+        Field::new(
+            "year",
+            InputType::Number(Metadata {
+                label: "Year",
+                placeholder: Some("Year"),
+                required: true,
+            }),
+        ),
+        Field::new(
+            "target",
+            InputType::Number(Metadata {
+                label: "Target",
+                placeholder: Some("Amount planned to be spent"),
+                required: true,
+            }),
+        ),
+    ];
 
     type PostMethod = rocket::serde::json::Json<Self>;
-    type FieldsType = [Field; 1];
+    type FieldsType = [Field; 3];
 }
 
 impl InsertableDatabaseEntry for crate::backend::accounting::Account {
@@ -242,11 +476,15 @@ impl InsertableDatabaseEntry for crate::backend::accounting::Account {
     const FIELDS: [Field; 3] = [
         Field::new(
             "code",
-            InputType::Number(Metadata {
-                label: "Code",
-                placeholder: Some("Code of the account"),
-                required: true,
-            }),
+            // A text field, as number inputs would lose codes with grouping separators like "4.200".
+            InputType::Text(
+                Metadata {
+                    label: "Code",
+                    placeholder: Some("Code of the account"),
+                    required: true,
+                },
+                false,
+            ),
         ),
         Field::new(
             "category",
@@ -275,7 +513,7 @@ impl InsertableDatabaseEntry for crate::backend::accounting::Account {
 
 impl InsertableDatabaseEntry for crate::backend::accounting::Entry {
     const NAME: &'static str = "New entry";
-    const FIELDS: [Field; 5] = [
+    const FIELDS: [Field; 6] = [
         Field::new(
             "evidence",
             InputType::new_foreign::<crate::backend::document::Document>(Metadata {
@@ -319,8 +557,36 @@ impl InsertableDatabaseEntry for crate::backend::accounting::Entry {
                 true,
             ),
         ),
+        Field::new(
+            "reference_number",
+            InputType::Text(
+                Metadata {
+                    label: "Reference number",
+                    placeholder: Some("Reference used on the bank statement"),
+                    required: false,
+                },
+                false,
+            ),
+        ),
     ];
 
+    /// Besides the strings, the account must be allowed for the cost center.
+    fn validate(&self, config: &Config) -> Result<(), crate::Error> {
+        let value = serde_json::to_value(self).map_err(|_| rocket::http::Status::BadRequest)?;
+        check_string_lengths(&value, config.max_string_length())?;
+        let database = config.read_only_database()?;
+        match crate::backend::accounting::AccountCostCenter::is_allowed(
+            &database,
+            self.account,
+            self.cost_center,
+        )? {
+            true => Ok(()),
+            false => Err(crate::Error::InvalidValue(String::from(
+                "the account is not allowed for the cost center",
+            ))),
+        }
+    }
+
     type PostMethod = rocket::serde::json::Json<Self>;
-    type FieldsType = [Field; 5];
+    type FieldsType = [Field; 6];
 }
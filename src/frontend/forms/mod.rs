@@ -12,13 +12,17 @@ pub use super::util::ForeignKeyStorage;
 
 use super::Renderable;
 use crate::auth::AuthenticatedUser;
-use crate::backend::database::{Database, Referenceable};
+use crate::backend::{
+    database::{Database, Referenceable},
+    document::DocumentType,
+};
 use crate::util::FormInputType;
 
 pub struct InsertFormRenderer<'a, T> {
     post_url: &'static str,
     database: &'a Database,
     user: AuthenticatedUser,
+    accepted_files: Option<Vec<&'static str>>,
     marker: std::marker::PhantomData<*const T>,
 }
 
@@ -28,9 +32,21 @@ impl<'a, T> InsertFormRenderer<'a, T> {
             post_url,
             database,
             user,
+            accepted_files: None,
             marker: std::marker::PhantomData,
         }
     }
+
+    /// Replace the extensions and MIME types accepted by the file inputs.
+    pub fn with_accepted_files(mut self, document_types: &[DocumentType]) -> Self {
+        self.accepted_files = Some(
+            document_types
+                .iter()
+                .flat_map(|value| [value.extension(), value.media_type()])
+                .collect(),
+        );
+        self
+    }
 }
 
 impl<'a, const N: usize, T: InsertableDatabaseEntry<FieldsType = [Field; N]>> Renderable
@@ -51,6 +67,11 @@ where
                         println!("Loading representations failed: {}", error);
                     }
                 }
+                InputType::File(file) => {
+                    if let Some(accepted_files) = &self.accepted_files {
+                        file.extensions = std::borrow::Cow::Owned(accepted_files.clone());
+                    }
+                }
                 _ => {}
             };
         }
@@ -1,9 +1,9 @@
-use std::collections::HashMap;
-
 use rocket::serde::Serialize;
 use rocket::{response::content::RawHtml, State};
 use rocket_dyn_templates::Template;
 
+mod csv;
+mod expand;
 mod forms;
 mod overviews;
 mod tables;
@@ -12,16 +12,19 @@ mod util;
 #[cfg(test)]
 mod tests;
 
-use crate::backend::accounting::Amount;
 use crate::backend::database::{PrimaryKey, SelectableByPrimaryKey};
 use crate::backend::person::Group;
+use crate::util::ZipOutput;
 use crate::{
-    auth::{AuthenticatedUser, Forward},
+    auth::{AdminUser, AuthenticatedUser, Forward},
     Config, Error,
 };
 
+pub use self::csv::{CsvDialect, CsvRenderable};
+pub use self::expand::{EntryExpansion, ExpandedEntry};
 pub use self::forms::{ForeignKeyStorage, InsertableDatabaseEntry};
 pub use self::tables::RenderableDatabaseEntry;
+pub use self::util::Locale;
 
 /// The current version of the package to present it in the frontend.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -40,37 +43,71 @@ pub trait Renderable: Sized {
 pub async fn index_protected(
     _user: AuthenticatedUser<Forward>,
     config: &State<Config>,
-) -> Result<Template, Error> {
-    let summaries = {
-        let database = &config.database();
-        crate::backend::accounting::AccountSummary::load_all(database)?
-    };
-
-    let mut cost_centers: HashMap<String, HashMap<String, Vec<(String, Amount)>>> = HashMap::new();
-    for summary in summaries {
-        cost_centers
-            .entry(summary.cost_center)
-            .or_default()
-            .entry(summary.category)
-            .or_default()
-            .push((summary.account, summary.amount));
-    }
-
-    Ok(Template::render(
-        "dashboard",
-        rocket_dyn_templates::context! { cost_centers: cost_centers, version: VERSION },
-    ))
+    if_modified_since: crate::IfModifiedSince,
+) -> Result<crate::Conditional<Template>, Error> {
+    let database = &config.read_only_database()?;
+    let last_modified = database.last_modified(&[
+        "entries",
+        "entry_allocations",
+        "accounts",
+        "categories",
+        "cost_centers",
+    ])?;
+    if_modified_since.respond(last_modified, || {
+        let summaries = crate::backend::accounting::AccountSummary::load_all(database)?;
+        Ok(self::overviews::DashboardView::new(summaries)?.render())
+    })
 }
 
-#[get("/groups/<group_id>", rank = 8)]
+#[get("/groups/<group_id>?<sort>", rank = 8)]
 pub async fn group_overview(
     _user: AuthenticatedUser<Forward>,
     config: &State<Config>,
     group_id: i64,
+    sort: Option<self::overviews::MemberOrder>,
     _expected_type: super::util::ExpectedFileType<super::util::Html>,
 ) -> Result<RawHtml<Template>, Error> {
-    let database = &config.database();
+    let database = &config.read_only_database()?;
     let group = Group::try_select(database, group_id)?.ok_or(Error::NotFound)?;
-    let summaries = self::overviews::GroupOverview::load(database, group)?;
+    let summaries = self::overviews::GroupOverview::load(database, group, sort)?;
     Ok(RawHtml(summaries.render()))
 }
+
+#[get("/admin/export.zip?<dialect>")]
+pub async fn export_database(
+    _user: AdminUser,
+    config: &State<Config>,
+    dialect: Option<CsvDialect>,
+) -> Result<ZipOutput, Error> {
+    use crate::backend::{accounting::*, document::Document, person::Person, user::User};
+
+    let database = &config.read_only_database()?;
+    let dialect = dialect.unwrap_or_default();
+    ZipOutput::create("export.zip", |archive| {
+        archive.add_file("persons.csv", |file| {
+            Person::write_csv(database, file, dialect)
+        })?;
+        archive.add_file("groups.csv", |file| {
+            Group::write_csv(database, file, dialect)
+        })?;
+        archive.add_file("documents.csv", |file| {
+            Document::write_csv(database, file, dialect)
+        })?;
+        archive.add_file("users.csv", |file| User::write_csv(database, file, dialect))?;
+        archive.add_file("categories.csv", |file| {
+            Category::write_csv(database, file, dialect)
+        })?;
+        archive.add_file("cost_centers.csv", |file| {
+            CostCenter::write_csv(database, file, dialect)
+        })?;
+        archive.add_file("budgets.csv", |file| {
+            Budget::write_csv(database, file, dialect)
+        })?;
+        archive.add_file("accounts.csv", |file| {
+            Account::write_csv(database, file, dialect)
+        })?;
+        archive.add_file("entries.csv", |file| {
+            Entry::write_csv(database, file, dialect)
+        })
+    })
+}
@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use crate::backend::accounting::{AccountSummary, Amount, AmountError, Currency};
+
+/// The name, balance, and currency of each account within a category.
+type CategoryAccounts = Vec<(String, Amount, Currency)>;
+
+/// The accounts of the dashboard, grouped by cost center and category in alphabetical order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DashboardView {
+    cost_centers: BTreeMap<String, BTreeMap<String, CategoryAccounts>>,
+    net_totals: Vec<(Amount, Currency)>,
+}
+
+impl DashboardView {
+    pub fn new(summaries: Vec<AccountSummary>) -> Result<Self, AmountError> {
+        let net_totals = AccountSummary::net_totals(&summaries)?
+            .into_iter()
+            .map(|(currency, amount)| (amount, currency))
+            .collect();
+        let mut cost_centers: BTreeMap<String, BTreeMap<String, CategoryAccounts>> =
+            BTreeMap::new();
+        for summary in summaries {
+            cost_centers
+                .entry(summary.cost_center)
+                .or_default()
+                .entry(summary.category)
+                .or_default()
+                .push((summary.account, summary.amount, summary.currency));
+        }
+
+        Ok(DashboardView {
+            cost_centers,
+            net_totals,
+        })
+    }
+}
+
+impl super::super::Renderable for DashboardView {
+    const TEMPLATE: &'static str = "dashboard";
+
+    fn generate_context(self) -> impl serde::Serialize {
+        rocket_dyn_templates::context! {
+            cost_centers: self.cost_centers,
+            net_totals: self.net_totals,
+            version: super::super::VERSION
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DashboardView;
+    use crate::backend::accounting::{AccountSummary, Amount, Currency};
+
+    fn summary(cost_center: &str, category: &str, account: &str, amount: i64) -> AccountSummary {
+        AccountSummary {
+            account: String::from(account),
+            cost_center: String::from(cost_center),
+            category: String::from(category),
+            amount: Amount::from(amount),
+            currency: Currency::Eur,
+        }
+    }
+
+    #[test]
+    fn test_grouped_deterministically() {
+        let view = DashboardView::new(vec![
+            summary("Sports", "Travel", "Train", 300),
+            summary("Admin", "Office", "Paper", 100),
+            summary("Sports", "Equipment", "Balls", 200),
+        ])
+        .expect("valid totals");
+
+        let structure: Vec<(&str, Vec<&str>)> = view
+            .cost_centers
+            .iter()
+            .map(|(cost_center, categories)| {
+                (
+                    cost_center.as_str(),
+                    categories.keys().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            structure,
+            vec![
+                ("Admin", vec!["Office"]),
+                ("Sports", vec!["Equipment", "Travel"])
+            ]
+        );
+        assert_eq!(view.net_totals, vec![(Amount::from(600), Currency::Eur)]);
+    }
+}
@@ -1,3 +1,4 @@
+use rocket::form::{self, DataField, Errors, FromFormField, ValueField};
 use serde::Serialize;
 
 use crate::backend::{
@@ -7,6 +8,10 @@ use crate::backend::{
 
 use super::{util::Map, ForeignKeyStorage};
 
+mod dashboard;
+
+pub use self::dashboard::DashboardView;
+
 pub struct GroupOverview<'a> {
     primary_key: PrimaryKey<Group>,
     foreign_keys: ForeignKeyStorage<'a, Map>,
@@ -21,15 +26,64 @@ pub struct MembershipOverview {
     pub comment: String,
 }
 
+/// The order of the members in the group overview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberOrder {
+    /// The members are sorted by the name of the person.
+    Name,
+    /// The members are sorted by the comment on their membership.
+    Comment,
+}
+
+impl MemberOrder {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            value if value.eq_ignore_ascii_case("name") => Some(MemberOrder::Name),
+            value if value.eq_ignore_ascii_case("comment") => Some(MemberOrder::Comment),
+            _ => None,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromFormField<'r> for MemberOrder {
+    fn from_value(field: ValueField<'r>) -> form::Result<'r, Self> {
+        MemberOrder::parse(field.value).ok_or_else(|| Errors::new().with_name(field.name))
+    }
+
+    async fn from_data(field: DataField<'r, '_>) -> form::Result<'r, Self> {
+        String::from_data(field)
+            .await
+            .and_then(|value| MemberOrder::parse(&value).ok_or_else(Errors::new))
+    }
+}
+
 impl<'a> GroupOverview<'a> {
-    pub fn load(database: &'a Database, group: Record<Group>) -> Result<Self, Error> {
-        let elements: Vec<_> = PersonMembership::find_all_members(database, group.identifier)?
+    /// Load the members of a group. Without an order, they are listed as they were added.
+    pub fn load(
+        database: &'a Database,
+        group: Record<Group>,
+        order: Option<MemberOrder>,
+    ) -> Result<Self, Error> {
+        let mut elements: Vec<_> = PersonMembership::find_all_members(database, group.identifier)?
             .into_iter()
             .map(|value| (value.person, value.comment.unwrap_or_default()))
             .collect();
 
         let mut foreign_keys = ForeignKeyStorage::from(database);
         foreign_keys.add::<Person>()?;
+
+        // The names are only known after resolving the persons, so the sorting happens here.
+        match order {
+            Some(MemberOrder::Name) => elements.sort_by_cached_key(|(person, _)| {
+                foreign_keys.get(*person).unwrap_or_default().to_lowercase()
+            }),
+            Some(MemberOrder::Comment) => {
+                elements.sort_by_cached_key(|(_, comment)| comment.to_lowercase())
+            }
+            None => {}
+        }
+
         Ok(GroupOverview {
             primary_key: group.identifier,
             foreign_keys,
@@ -66,3 +120,48 @@ impl<'a> super::Renderable for GroupOverview<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{GroupOverview, MemberOrder};
+    use crate::backend::database::{
+        Database, DefaultGenerator, Insertable, SelectableByPrimaryKey,
+    };
+    use crate::backend::person::{Group, Membership, Person};
+
+    #[test]
+    fn test_sorted_by_name() {
+        let database = Database::in_memory().expect("valid database");
+        let group = Group::create_default(&database)
+            .insert(&database)
+            .expect("valid group");
+        for name in ["Charlie", "alice", "Bob"] {
+            let person = Person {
+                name: String::from(name),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person");
+            Membership {
+                person,
+                group,
+                updated: None,
+                comment: None,
+            }
+            .insert(&database)
+            .expect("valid membership");
+        }
+
+        let group = Group::try_select(&database, group.raw_index())
+            .expect("valid query")
+            .expect("existing group");
+        let overview =
+            GroupOverview::load(&database, group, Some(MemberOrder::Name)).expect("valid overview");
+        let names: Vec<_> = overview
+            .elements
+            .iter()
+            .map(|(person, _)| overview.foreign_keys.get(*person).unwrap_or_default())
+            .collect();
+        assert_eq!(names, vec!["alice", "Bob", "Charlie"]);
+    }
+}
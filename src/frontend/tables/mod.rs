@@ -4,19 +4,70 @@ use crate::backend::{
     document::Document,
     person::{Group, Person},
     user::User,
-    Pagination,
+    Column, Order, Pagination, WhereClause,
 };
 use rocket::serde::Serialize;
 use rocket_dyn_templates::context;
 
-use super::Renderable;
+use super::{util::Locale, Renderable};
 
 type ForeignKeyStorage<'a> = super::util::ForeignKeyStorage<'a, super::util::Map>;
 
-pub struct TableRenderer<const N: usize, T: RenderableDatabaseEntry<N>>(
-    Vec<[String; N]>,
-    Pagination<T>,
-);
+pub struct TableRenderer<const N: usize, T: RenderableDatabaseEntry<N>> {
+    rows: Vec<[String; N]>,
+    pagination: Pagination<T>,
+    /// The number of elements matching the filter.
+    total: usize,
+    /// The following page, if elements matching the filter follow.
+    next: Option<Pagination<T>>,
+}
+
+/// The header of a column, which links to the table sorted by the column if possible.
+#[derive(Debug, Serialize)]
+struct Header {
+    name: &'static str,
+    url: Option<String>,
+    /// The current order if the table is sorted by the column.
+    order: Option<&'static str>,
+}
+
+impl<const N: usize, T: RenderableDatabaseEntry<N>> TableRenderer<N, T> {
+    /// Create the headers. Sorting by the current column again flips the order.
+    fn headers(&self) -> Vec<Header> {
+        let current = &self.pagination;
+        T::COLUMNS
+            .into_iter()
+            .zip(T::COLUMNS_SORTABLE)
+            .map(|(name, column)| {
+                let column = column.and_then(|column| Column::<T>::parse_public(column).ok());
+                let is_current = |column: &Column<T>| column.as_str() == current.column.as_str();
+                Header {
+                    name,
+                    order: column.as_ref().filter(|column| is_current(column)).map(
+                        |_| match current.order {
+                            Order::Ascending => "asc",
+                            Order::Descending => "desc",
+                        },
+                    ),
+                    url: column.map(|column| {
+                        let order = match is_current(&column) && current.order == Order::Ascending {
+                            true => Order::Descending,
+                            false => Order::Ascending,
+                        };
+                        let pagination = Pagination {
+                            offset: 0,
+                            limit: current.limit,
+                            order,
+                            column,
+                            after: None,
+                        };
+                        format!("{}{}", T::url(), pagination.display_url())
+                    }),
+                }
+            })
+            .collect()
+    }
+}
 
 impl<const N: usize, T: RenderableDatabaseEntry<N>> Renderable for TableRenderer<N, T>
 where
@@ -26,14 +77,20 @@ where
     const TEMPLATE: &'static str = "table";
 
     fn generate_context(self) -> impl Serialize {
-        let next_len = self.0.len();
+        let pagination = &self.pagination;
+        // The position of a page selected by a cursor is unknown, so it could neither be numbered nor be jumped around from.
+        let numbered = pagination.after.is_none();
         context! {
             title: &T::TITLE,
-            headers: T::COLUMNS.into_iter().zip(T::COLUMNS_SORTABLE.into_iter()).collect::<Vec<(&str, &str)>>(),
+            headers: self.headers(),
             url_add: &T::URL_ADD,
-            rows: self.0,
-            next_url: self.1.next(next_len).map(|value| format!("{}{}", T::url(), value.display_url())),
-            previous_url: self.1.previous().map(|value| format!("{}{}", T::url(), value.display_url())),
+            next_url: self.next.as_ref().map(|value| format!("{}{}", T::url(), value.display_url())),
+            previous_url: pagination.previous().map(|value| format!("{}{}", T::url(), value.display_url())),
+            first_url: numbered.then(|| format!("{}{}", T::url(), pagination.first().display_url())),
+            last_url: numbered.then(|| format!("{}{}", T::url(), pagination.last(self.total).display_url())),
+            current_page: numbered.then(|| pagination.current_page()),
+            total_pages: numbered.then(|| pagination.total_pages(self.total)),
+            rows: self.rows,
             version: super::VERSION
         }
     }
@@ -49,8 +106,8 @@ pub trait RenderableDatabaseEntry<const N: usize>: Selectable {
     /// The path to the form to create a new element.
     const URL_ADD: &'static str;
 
-    /// Map columns to sortable table names.
-    const COLUMNS_SORTABLE: [&'static str; N] = [""; N];
+    /// The sortable database column of each displayed column, if any.
+    const COLUMNS_SORTABLE: [Option<&'static str>; N] = [None; N];
 
     /// Load required foreign keys before generating the rows.
     fn load_required_foreign_keys(
@@ -59,25 +116,40 @@ pub trait RenderableDatabaseEntry<const N: usize>: Selectable {
         Ok(())
     }
 
-    /// Translate a record into a row of strings.
-    fn generate_table_row(entry: Self::Output, foreign_keys: &ForeignKeyStorage<'_>)
-        -> [String; N];
+    /// Translate a record into a row of strings. Values like dates are formatted according to the locale.
+    fn generate_table_row(
+        entry: Self::Output,
+        foreign_keys: &ForeignKeyStorage<'_>,
+        locale: Locale,
+    ) -> [String; N];
 
-    /// Create a list for rendering all elements.
+    /// Create a list for rendering all elements matching the filter.
     fn prepare_rendering_all(
         database: &Database,
         pagination: Pagination<Self>,
+        filter: &WhereClause<Self>,
+        locale: Locale,
     ) -> Result<TableRenderer<N, Self>, crate::backend::database::Error> {
         let mut foreign_keys = ForeignKeyStorage::from(database);
 
         Self::load_required_foreign_keys(&mut foreign_keys)?;
-        Ok(TableRenderer(
-            Self::select_all_sorted(database, pagination.clone())?
+        let entries = Self::select_all_sorted(database, pagination.clone(), Some(filter))?;
+        let primary_keys = entries
+            .iter()
+            .map(|entry| Self::primary_key(entry).raw_index())
+            .collect::<Vec<_>>();
+        Ok(TableRenderer {
+            rows: entries
                 .into_iter()
-                .map(|value| Self::generate_table_row(value, &foreign_keys))
+                .map(|value| Self::generate_table_row(value, &foreign_keys, locale))
                 .collect(),
+            total: Self::count_filtered(database, filter)?,
+            next: match pagination.has_more(database, filter)? {
+                true => pagination.next(&primary_keys),
+                false => None,
+            },
             pagination,
-        ))
+        })
     }
 
     /// Extract the URL of this form.
@@ -94,10 +166,19 @@ impl RenderableDatabaseEntry<3> for Person {
     const TITLE: &'static str = "Persons";
     const COLUMNS: [&'static str; 3] = ["Name", "Address", "E-Mail"];
     const URL_ADD: &'static str = "/persons/new";
+    const COLUMNS_SORTABLE: [Option<&'static str>; 3] = [Some("name"), Some("address"), None];
 
-    fn generate_table_row(entry: Record<Self>, _: &ForeignKeyStorage<'_>) -> [String; 3] {
+    fn generate_table_row(
+        entry: Record<Self>,
+        _: &ForeignKeyStorage<'_>,
+        _: Locale,
+    ) -> [String; 3] {
         let value = entry.value;
-        [value.name, value.address, value.email.unwrap_or_default()]
+        [
+            value.name,
+            value.address,
+            value.email.map(String::from).unwrap_or_default(),
+        ]
     }
 }
 
@@ -105,8 +186,13 @@ impl RenderableDatabaseEntry<1> for Group {
     const TITLE: &'static str = "Groups";
     const COLUMNS: [&'static str; 1] = ["Description"];
     const URL_ADD: &'static str = "/groups/new";
+    const COLUMNS_SORTABLE: [Option<&'static str>; 1] = [Some("description")];
 
-    fn generate_table_row(group: Record<Self>, _: &ForeignKeyStorage<'_>) -> [String; 1] {
+    fn generate_table_row(
+        group: Record<Self>,
+        _: &ForeignKeyStorage<'_>,
+        _: Locale,
+    ) -> [String; 1] {
         [format!(
             "<a href={}>{}</a>",
             group.identifier, group.value.description
@@ -114,12 +200,27 @@ impl RenderableDatabaseEntry<1> for Group {
     }
 }
 
-impl RenderableDatabaseEntry<6> for Document {
+impl RenderableDatabaseEntry<7> for Document {
     const TITLE: &'static str = "Documents";
-    const COLUMNS: [&'static str; 6] =
-        ["File", "Recieved", "Processed", "From", "To", "Description"];
+    const COLUMNS: [&'static str; 7] = [
+        "File",
+        "Size",
+        "Recieved",
+        "Processed",
+        "From",
+        "To",
+        "Description",
+    ];
     const URL_ADD: &'static str = "/documents/new";
-    const COLUMNS_SORTABLE: [&'static str; 6] = ["", "recieved", "processed", "", "", ""];
+    const COLUMNS_SORTABLE: [Option<&'static str>; 7] = [
+        None,
+        None,
+        Some("recieved"),
+        Some("processed"),
+        None,
+        None,
+        None,
+    ];
 
     fn load_required_foreign_keys(
         foreign_key_storage: &mut ForeignKeyStorage<'_>,
@@ -130,11 +231,13 @@ impl RenderableDatabaseEntry<6> for Document {
     fn generate_table_row(
         document: <Document as Selectable>::Output,
         foreign_keys: &ForeignKeyStorage<'_>,
-    ) -> [String; 6] {
+        locale: Locale,
+    ) -> [String; 7] {
         [
             format!("<a href=\"{}/pdf\">PDF</a>", document.identifier),
-            document.recieved.to_string(),
-            document.processed.to_string(),
+            locale.format_size(document.size_bytes),
+            locale.format_date(document.recieved),
+            locale.format_date(document.processed),
             foreign_keys
                 .get(document.from_person)
                 .map(String::from)
@@ -152,7 +255,7 @@ impl RenderableDatabaseEntry<3> for User {
     const TITLE: &'static str = "Users";
     const COLUMNS: [&'static str; 3] = ["Name", "Creation date", "Used by"];
     const URL_ADD: &'static str = "/users/new";
-    const COLUMNS_SORTABLE: [&'static str; 3] = ["", "creation_date", ""];
+    const COLUMNS_SORTABLE: [Option<&'static str>; 3] = [None, Some("creation_date"), None];
 
     fn load_required_foreign_keys(
         foreign_key_storage: &mut ForeignKeyStorage<'_>,
@@ -163,10 +266,11 @@ impl RenderableDatabaseEntry<3> for User {
     fn generate_table_row(
         user: <User as Selectable>::Output,
         foreign_keys: &ForeignKeyStorage<'_>,
+        locale: Locale,
     ) -> [String; 3] {
         [
             user.username.to_string(),
-            user.creation_date.to_string(),
+            locale.format_date(user.creation_date),
             user.related_to
                 .and_then(|value| foreign_keys.get(value).map(String::from))
                 .unwrap_or_default(),
@@ -174,30 +278,93 @@ impl RenderableDatabaseEntry<3> for User {
     }
 }
 
-impl RenderableDatabaseEntry<1> for crate::backend::accounting::Category {
+impl RenderableDatabaseEntry<2> for crate::backend::accounting::Category {
     const TITLE: &'static str = "Categories";
-    const COLUMNS: [&'static str; 1] = ["Description"];
+    const COLUMNS: [&'static str; 2] = ["Description", "Parent"];
     const URL_ADD: &'static str = "/categories/new";
+    const COLUMNS_SORTABLE: [Option<&'static str>; 2] = [Some("description"), None];
 
-    fn generate_table_row(category: Record<Self>, _: &ForeignKeyStorage<'_>) -> [String; 1] {
-        [category.value.description]
+    fn load_required_foreign_keys(
+        foreign_key_storage: &mut ForeignKeyStorage<'_>,
+    ) -> Result<(), crate::backend::database::Error> {
+        foreign_key_storage.add::<Self>()
+    }
+
+    fn generate_table_row(
+        category: Record<Self>,
+        foreign_keys: &ForeignKeyStorage<'_>,
+        _: Locale,
+    ) -> [String; 2] {
+        let parent = category
+            .value
+            .parent
+            .and_then(|value| foreign_keys.get(value).map(String::from))
+            .unwrap_or_default();
+        [category.value.description, parent]
     }
 }
 
-impl RenderableDatabaseEntry<1> for crate::backend::accounting::CostCenter {
+impl RenderableDatabaseEntry<2> for crate::backend::accounting::CostCenter {
     const TITLE: &'static str = "Cost centers";
-    const COLUMNS: [&'static str; 1] = ["Description"];
+    const COLUMNS: [&'static str; 2] = ["Description", "Parent"];
     const URL_ADD: &'static str = "/cost_centers/new";
+    const COLUMNS_SORTABLE: [Option<&'static str>; 2] = [Some("description"), None];
+
+    fn load_required_foreign_keys(
+        foreign_key_storage: &mut ForeignKeyStorage<'_>,
+    ) -> Result<(), crate::backend::database::Error> {
+        foreign_key_storage.add::<Self>()
+    }
 
-    fn generate_table_row(cost_center: Record<Self>, _: &ForeignKeyStorage<'_>) -> [String; 1] {
-        [cost_center.value.description]
+    fn generate_table_row(
+        cost_center: Record<Self>,
+        foreign_keys: &ForeignKeyStorage<'_>,
+        _: Locale,
+    ) -> [String; 2] {
+        let parent = cost_center
+            .value
+            .parent
+            .and_then(|value| foreign_keys.get(value).map(String::from))
+            .unwrap_or_default();
+        [cost_center.value.description, parent]
     }
 }
 
-impl RenderableDatabaseEntry<3> for crate::backend::accounting::Account {
+impl RenderableDatabaseEntry<3> for crate::backend::accounting::Budget {
+    const TITLE: &'static str = "Budgets";
+    const COLUMNS: [&'static str; 3] = ["Cost center", "Year", "Target"];
+    const COLUMNS_SORTABLE: [Option<&'static str>; 3] =
+        [Some("cost_center"), Some("year"), Some("target")];
+    const URL_ADD: &'static str = "/budgets/new";
+
+    fn load_required_foreign_keys(
+        foreign_key_storage: &mut ForeignKeyStorage<'_>,
+    ) -> Result<(), crate::backend::database::Error> {
+        foreign_key_storage.add::<CostCenter>()
+    }
+
+    fn generate_table_row(
+        budget: Record<Self>,
+        foreign_keys: &ForeignKeyStorage<'_>,
+        _: Locale,
+    ) -> [String; 3] {
+        [
+            foreign_keys
+                .get(budget.cost_center)
+                .map(String::from)
+                .unwrap_or_else(|| budget.cost_center.to_string()),
+            budget.year.to_string(),
+            format!("{} {}", budget.target, budget.currency),
+        ]
+    }
+}
+
+impl RenderableDatabaseEntry<4> for crate::backend::accounting::Account {
     const TITLE: &'static str = "Accounts";
-    const COLUMNS: [&'static str; 3] = ["Code", "Category", "Description"];
+    const COLUMNS: [&'static str; 4] = ["Code", "Category", "Description", "Kind"];
     const URL_ADD: &'static str = "/accounts/new";
+    const COLUMNS_SORTABLE: [Option<&'static str>; 4] =
+        [Some("code"), Some("category"), Some("description"), None];
 
     fn load_required_foreign_keys(
         foreign_key_storage: &mut ForeignKeyStorage<'_>,
@@ -208,7 +375,8 @@ impl RenderableDatabaseEntry<3> for crate::backend::accounting::Account {
     fn generate_table_row(
         account: Record<Self>,
         foreign_keys: &ForeignKeyStorage<'_>,
-    ) -> [String; 3] {
+        _: Locale,
+    ) -> [String; 4] {
         [
             account.value.code.to_string(),
             foreign_keys
@@ -216,6 +384,7 @@ impl RenderableDatabaseEntry<3> for crate::backend::accounting::Account {
                 .map(String::from)
                 .unwrap_or_else(|| account.category.to_string()),
             account.value.description,
+            account.value.kind.to_string(),
         ]
     }
 }
@@ -229,7 +398,13 @@ impl RenderableDatabaseEntry<5> for crate::backend::accounting::Entry {
         "Amount",
         "Description",
     ];
-    const COLUMNS_SORTABLE: [&'static str; 5] = ["", "account", "cost_center", "amount", ""];
+    const COLUMNS_SORTABLE: [Option<&'static str>; 5] = [
+        None,
+        Some("account"),
+        Some("cost_center"),
+        Some("amount"),
+        None,
+    ];
     const URL_ADD: &'static str = "/entries/new";
 
     fn load_required_foreign_keys(
@@ -243,6 +418,7 @@ impl RenderableDatabaseEntry<5> for crate::backend::accounting::Entry {
     fn generate_table_row(
         entry: Record<Self>,
         foreign_keys: &ForeignKeyStorage<'_>,
+        _: Locale,
     ) -> [String; 5] {
         [
             foreign_keys
@@ -266,6 +442,203 @@ impl RenderableDatabaseEntry<5> for crate::backend::accounting::Entry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::database::Insertable;
+
+    #[test]
+    fn test_page_context() {
+        let database = Database::in_memory().expect("valid database");
+        for _ in 0..25 {
+            Person::default().insert(&database).expect("valid person");
+        }
+        let pagination = Pagination::<Person> {
+            offset: 10,
+            limit: crate::backend::Limit::from(10),
+            ..Default::default()
+        };
+
+        let context = serde_json::to_value(
+            Person::prepare_rendering_all(
+                &database,
+                pagination,
+                &WhereClause::new(),
+                Locale::default(),
+            )
+            .expect("valid table")
+            .generate_context(),
+        )
+        .expect("valid context");
+        assert_eq!(context["current_page"], 2);
+        assert_eq!(context["total_pages"], 3);
+        assert_eq!(
+            context["first_url"],
+            "/persons?sort_by=id&order=DESC&limit=10&offset=0"
+        );
+        assert_eq!(
+            context["last_url"],
+            "/persons?sort_by=id&order=DESC&limit=10&offset=20"
+        );
+    }
+
+    #[test]
+    fn test_cursor_context() {
+        let database = Database::in_memory().expect("valid database");
+        let identifiers = [(); 5].map(|_| {
+            Person::default()
+                .insert(&database)
+                .expect("valid person")
+                .raw_index()
+        });
+        let context = |after: i64| {
+            let pagination = Pagination::from_cursor(after, crate::backend::Limit::from(2));
+            serde_json::to_value(
+                Person::prepare_rendering_all(
+                    &database,
+                    pagination,
+                    &WhereClause::new(),
+                    Locale::default(),
+                )
+                .expect("valid table")
+                .generate_context(),
+            )
+            .expect("valid context")
+        };
+
+        let first = context(identifiers[0]);
+        assert_eq!(first["rows"].as_array().map(Vec::len), Some(2));
+        assert_eq!(
+            first["next_url"],
+            format!("/persons?after={}&limit=2", identifiers[2])
+        );
+        for key in [
+            "previous_url",
+            "first_url",
+            "last_url",
+            "current_page",
+            "total_pages",
+        ] {
+            assert_eq!(first[key], serde_json::Value::Null, "{} is set", key);
+        }
+
+        // The last page is not followed by another one, even if it is full.
+        let last = context(identifiers[2]);
+        assert_eq!(last["rows"].as_array().map(Vec::len), Some(2));
+        assert_eq!(last["next_url"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn test_filtered_context() {
+        let database = Database::in_memory().expect("valid database");
+        for name in ["Alice", "Bob", "Bob"] {
+            Person {
+                name: String::from(name),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person");
+        }
+        let context = |filter: WhereClause<Person>, limit: usize| {
+            let pagination = Pagination {
+                limit: crate::backend::Limit::from(limit),
+                ..Default::default()
+            };
+            serde_json::to_value(
+                Person::prepare_rendering_all(&database, pagination, &filter, Locale::default())
+                    .expect("valid table")
+                    .generate_context(),
+            )
+            .expect("valid context")
+        };
+        let rows = |filter: WhereClause<Person>| {
+            context(filter, 10)["rows"]
+                .as_array()
+                .expect("valid rows")
+                .len()
+        };
+
+        let name = || Column::try_from("name").expect("valid column");
+        assert_eq!(rows(WhereClause::new()), 3);
+        assert_eq!(rows(WhereClause::new().equals(name(), "Bob")), 2);
+        assert_eq!(rows(WhereClause::new().equals(name(), "Carol")), 0);
+
+        // A full page is only followed by another one if more elements match.
+        assert_eq!(
+            context(WhereClause::new(), 2)["next_url"],
+            "/persons?sort_by=id&order=DESC&limit=2&offset=2"
+        );
+        assert_eq!(
+            context(WhereClause::new().equals(name(), "Bob"), 2)["next_url"],
+            serde_json::Value::Null
+        );
+    }
+
+    #[test]
+    fn test_headers() {
+        let database = Database::in_memory().expect("valid database");
+        let headers = |pagination: Pagination<Person>| {
+            let context = serde_json::to_value(
+                Person::prepare_rendering_all(
+                    &database,
+                    pagination,
+                    &WhereClause::new(),
+                    Locale::default(),
+                )
+                .expect("valid table")
+                .generate_context(),
+            )
+            .expect("valid context");
+            context["headers"].clone()
+        };
+
+        let unsorted = headers(Pagination::default());
+        assert_eq!(
+            unsorted[0],
+            serde_json::json!({"name": "Name", "url": "/persons?sort_by=name&order=ASC&limit=10&offset=0", "order": null})
+        );
+        assert_eq!(
+            unsorted[2],
+            serde_json::json!({"name": "E-Mail", "url": null, "order": null})
+        );
+
+        let sorted = headers(
+            Pagination::new(
+                "name",
+                20,
+                crate::backend::Limit::from(10),
+                Order::Ascending,
+            )
+            .expect("valid pagination"),
+        );
+        assert_eq!(sorted[0]["order"], "asc");
+        assert_eq!(
+            sorted[0]["url"],
+            "/persons?sort_by=name&order=DESC&limit=10&offset=0"
+        );
+        assert_eq!(sorted[1]["order"], serde_json::Value::Null);
+    }
+
+    /// Each sortable column must be known, as the header would not link to it otherwise.
+    #[test]
+    fn test_columns_sortable() {
+        fn check<const N: usize, T: RenderableDatabaseEntry<N>>() {
+            for column in T::COLUMNS_SORTABLE.into_iter().flatten() {
+                assert!(
+                    Column::<T>::parse_public(column).is_ok(),
+                    "{} is not sortable",
+                    column
+                );
+            }
+        }
+
+        check::<3, Person>();
+        check::<1, Group>();
+        check::<7, Document>();
+        check::<3, User>();
+        check::<2, Category>();
+        check::<2, CostCenter>();
+        check::<3, crate::backend::accounting::Budget>();
+        check::<4, Account>();
+        check::<5, crate::backend::accounting::Entry>();
+    }
 
     #[test]
     fn test_url() {
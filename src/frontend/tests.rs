@@ -24,9 +24,10 @@ fn test_group_json_and_html() {
         let engine = rocket();
         let primary_key = {
             let state: &State<Config> = State::get(&engine).expect("valid database");
-            let default_group = Group::create_default(&state.database());
+            let default_group =
+                Group::create_default(&state.database().expect("database connection"));
             default_group
-                .insert(&state.database())
+                .insert(&state.database().expect("database connection"))
                 .expect("Insert failed")
         };
         let client = crate::tests::login(engine);
@@ -64,3 +65,128 @@ fn test_group_json_and_html() {
         assert!(response.contains("<body"))
     }
 }
+
+#[test]
+fn test_export_zip() {
+    use crate::backend::person::Person;
+    use std::io::{BufRead, BufReader};
+
+    let engine = rocket();
+    {
+        let state: &State<Config> = State::get(&engine).expect("valid database");
+        let database = state.database().expect("database connection");
+        Person::create_default(&database)
+            .insert(&database)
+            .expect("Insert failed");
+    }
+    let client = crate::tests::login(engine);
+
+    let response = client.get("/admin/export.zip").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.content_type(),
+        Some(rocket::http::ContentType::ZIP)
+    );
+
+    let archive = response.into_bytes().expect("valid bytes");
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(archive)).expect("valid zip archive");
+    let persons = archive.by_name("persons.csv").expect("persons exported");
+    let lines: Vec<String> = BufReader::new(persons)
+        .lines()
+        .map(|line| line.expect("valid line"))
+        .collect();
+    assert_eq!(lines[0], "id,name,address,email,birthday,comment");
+    assert_eq!(lines.len(), 2);
+}
+
+#[test]
+fn test_export_zip_german_dialect() {
+    use crate::backend::accounting::Entry;
+    use std::io::Read;
+
+    let engine = rocket();
+    {
+        let state: &State<Config> = State::get(&engine).expect("valid database");
+        let database = state.database().expect("database connection");
+        Entry::create_default(&database)
+            .insert(&database)
+            .expect("Insert failed");
+    }
+    let client = crate::tests::login(engine);
+
+    let response = client.get("/admin/export.zip?dialect=excel-de").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let archive = response.into_bytes().expect("valid bytes");
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(archive)).expect("valid zip archive");
+    let mut entries = String::new();
+    archive
+        .by_name("entries.csv")
+        .expect("entries exported")
+        .read_to_string(&mut entries)
+        .expect("valid csv");
+    assert!(entries.starts_with("id;evidence;account;cost_center;amount;"));
+    assert!(entries.contains(";32,00;"));
+}
+
+#[test]
+fn test_localized_dates() {
+    use crate::backend::{document::Document, Date};
+    use rocket::http::Header;
+
+    let engine = rocket();
+    {
+        let state: &State<Config> = State::get(&engine).expect("valid database");
+        let database = state.database().expect("database connection");
+        let mut document = Document::create_default(&database);
+        document.processed = Date::try_from("2024-02-18").expect("valid date");
+        document.insert(&database).expect("Insert failed");
+    }
+    let client = login(engine);
+
+    let response = client
+        .get("/documents")
+        .header(Header::new("Accept-Language", "de-DE,de;q=0.9"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let response = response.into_string().expect("valid str");
+    assert!(response.contains("18.02.2024"));
+
+    let response = client
+        .get("/documents")
+        .dispatch()
+        .into_string()
+        .expect("valid str");
+    assert!(response.contains("2024-02-18"));
+}
+
+#[test]
+fn test_export_zip_unlimited() {
+    use crate::backend::person::Person;
+    use crate::backend::Limit;
+    use std::io::{BufRead, BufReader};
+
+    let count = usize::from(Limit::MAXIMUM) + 5;
+    let engine = rocket();
+    {
+        let state: &State<Config> = State::get(&engine).expect("valid database");
+        let database = state.database().expect("database connection");
+        for _ in 0..count {
+            Person::create_default(&database)
+                .insert(&database)
+                .expect("Insert failed");
+        }
+    }
+    let client = crate::tests::login(engine);
+
+    let response = client.get("/admin/export.zip").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let archive = response.into_bytes().expect("valid bytes");
+    let mut archive =
+        zip::ZipArchive::new(std::io::Cursor::new(archive)).expect("valid zip archive");
+    let persons = archive.by_name("persons.csv").expect("persons exported");
+    // All the rows in addition to the header are exported.
+    assert_eq!(BufReader::new(persons).lines().count(), count + 1);
+}
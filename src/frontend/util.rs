@@ -1,23 +1,30 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use rocket::serde::{Serialize, Serializer};
+use rocket::{
+    request::{FromRequest, Outcome},
+    serde::{Serialize, Serializer},
+    Request,
+};
 
-use crate::backend::database::{Database, Indexable, PrimaryKey, Referenceable};
+use crate::backend::{
+    database::{Database, Descriptor, Indexable, PrimaryKey, Referenceable},
+    Date,
+};
 
 /// The (non-generic) data structure used within the foreign key cache.
 pub trait Container: Serialize {
-    fn from<T: Indexable>(raw_container: Vec<(PrimaryKey<T>, String)>) -> Self;
+    fn from<T: Indexable>(raw_container: Vec<Descriptor<T>>) -> Self;
 }
 
-/// A ordered list of key and human-readable style.
-pub struct List(Vec<(String, String)>);
+/// A ordered list of key, human-readable label, and optional secondary information.
+pub struct List(Vec<(String, String, Option<String>)>);
 
 impl Container for List {
-    fn from<T: Indexable>(raw_container: Vec<(PrimaryKey<T>, String)>) -> Self {
+    fn from<T: Indexable>(raw_container: Vec<Descriptor<T>>) -> Self {
         Self(
             raw_container
                 .into_iter()
-                .map(|value| (value.0.to_string(), value.1))
+                .map(|value| (value.identifier.to_string(), value.label, value.secondary))
                 .collect(),
         )
     }
@@ -32,15 +39,15 @@ impl Serialize for List {
     }
 }
 
-/// A fast map which could be used for queries.
-pub struct Map(HashMap<i64, String>);
+/// A map which could be used for queries. It is ordered by the primary key.
+pub struct Map(BTreeMap<i64, String>);
 
 impl Container for Map {
-    fn from<T: Indexable>(raw_container: Vec<(PrimaryKey<T>, String)>) -> Self {
+    fn from<T: Indexable>(raw_container: Vec<Descriptor<T>>) -> Self {
         Self(
             raw_container
                 .into_iter()
-                .map(|value| (value.0 .0, value.1))
+                .map(|value| (value.identifier.raw_index(), value.to_string()))
                 .collect(),
         )
     }
@@ -55,18 +62,18 @@ impl Serialize for Map {
     }
 }
 
-/// A container for storing human-readable representations for foreign keys without generics.
+/// A container for storing human-readable representations for foreign keys without generics. The tables are ordered by name such that the serialization is stable.
 #[derive(Debug, Clone)]
 pub struct ForeignKeyStorage<'a, C: Container = List> {
     database: &'a Database,
-    cache: HashMap<&'static str, C>,
+    cache: BTreeMap<&'static str, C>,
 }
 
 impl<'a, C: Container> From<&'a Database> for ForeignKeyStorage<'a, C> {
     fn from(value: &'a Database) -> Self {
         Self {
             database: value,
-            cache: HashMap::new(),
+            cache: BTreeMap::new(),
         }
     }
 }
@@ -80,7 +87,7 @@ impl<'a, C: Container> ForeignKeyStorage<'a, C> {
 
         self.cache.insert(
             T::TABLE_NAME,
-            C::from(<T as Referenceable>::generate_descriptions(self.database)?),
+            C::from(<T as Referenceable>::generate_descriptors(self.database)?),
         );
 
         Ok(())
@@ -122,7 +129,11 @@ impl<'a> From<ForeignKeyStorage<'a, Map>> for ForeignKeyStorage<'a, List> {
                                  .0
                                 .into_iter()
                                 .map(|foreign_key| {
-                                    (format!("/{}/{}", table.0, foreign_key.0), foreign_key.1)
+                                    (
+                                        format!("/{}/{}", table.0, foreign_key.0),
+                                        foreign_key.1,
+                                        None,
+                                    )
                                 })
                                 .collect(),
                         ),
@@ -132,3 +143,141 @@ impl<'a> From<ForeignKeyStorage<'a, Map>> for ForeignKeyStorage<'a, List> {
         }
     }
 }
+
+/// The language conventions used for formatting values in the HTML frontend. JSON output always uses ISO formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    /// Dates are formatted according to ISO 8601, i.e. 2024-02-18.
+    #[default]
+    International,
+    /// Dates are formatted like 18.02.2024.
+    German,
+}
+
+impl Locale {
+    /// Parse a language tag like "de-DE". Unknown languages are not supported.
+    pub fn from_language_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        match language.trim() {
+            value if value.eq_ignore_ascii_case("de") => Some(Locale::German),
+            value if value.eq_ignore_ascii_case("en") => Some(Locale::International),
+            _ => None,
+        }
+    }
+
+    /// Select the first supported language of an Accept-Language header like "de-DE,de;q=0.9,en;q=0.8".
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        header
+            .split(',')
+            .filter_map(|language| language.split(';').next())
+            .find_map(Self::from_language_tag)
+    }
+
+    /// Format a date according to the conventions of the locale.
+    pub fn format_date(&self, date: Date) -> String {
+        match self {
+            Locale::International => date.to_string(),
+            Locale::German => date.format("%d.%m.%Y").to_string(),
+        }
+    }
+
+    /// Format a file size like "2.3 MB" with the decimal separator of the locale.
+    pub fn format_size(&self, bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+        if bytes < 1000 {
+            return format!("{} B", bytes);
+        }
+
+        let mut size = bytes as f64 / 1000.0;
+        let mut unit = 0;
+        while size >= 1000.0 && unit + 1 < UNITS.len() {
+            size /= 1000.0;
+            unit += 1;
+        }
+
+        let size = format!("{:.1} {}", size, UNITS[unit]);
+        match self {
+            Locale::International => size,
+            Locale::German => size.replace('.', ","),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Locale {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let locale = request
+            .headers()
+            .get_one("Accept-Language")
+            .and_then(Locale::from_accept_language)
+            .or_else(|| {
+                request
+                    .rocket()
+                    .state::<crate::Config>()
+                    .map(|config| config.default_locale())
+            })
+            .unwrap_or_default();
+        Outcome::Success(locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ForeignKeyStorage, List, Locale, Map};
+    use crate::backend::{
+        database::{Database, DefaultGenerator, Insertable},
+        person::{Group, Person},
+    };
+
+    #[test]
+    fn test_stable_serialization() {
+        let database = Database::in_memory().expect("valid database");
+        for _ in 0..8 {
+            Person::create_default(&database)
+                .insert(&database)
+                .expect("valid person");
+            Group::create_default(&database)
+                .insert(&database)
+                .expect("valid group");
+        }
+
+        let mut first = ForeignKeyStorage::<Map>::from(&database);
+        first.add::<Person>().expect("valid persons");
+        first.add::<Group>().expect("valid groups");
+
+        let mut second = ForeignKeyStorage::<Map>::from(&database);
+        second.add::<Group>().expect("valid groups");
+        second.add::<Person>().expect("valid persons");
+
+        assert_eq!(
+            serde_json::to_string(&first).expect("valid json"),
+            serde_json::to_string(&second).expect("valid json")
+        );
+        assert_eq!(
+            serde_json::to_string(&ForeignKeyStorage::<List>::from(first)).expect("valid json"),
+            serde_json::to_string(&ForeignKeyStorage::<List>::from(second)).expect("valid json")
+        );
+    }
+
+    #[test]
+    fn test_locale_from_accept_language() {
+        assert_eq!(
+            Locale::from_accept_language("de-DE,de;q=0.9,en;q=0.8"),
+            Some(Locale::German)
+        );
+        assert_eq!(
+            Locale::from_accept_language("fr-FR, en-US;q=0.5"),
+            Some(Locale::International)
+        );
+        assert_eq!(Locale::from_accept_language("fr"), None);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(Locale::International.format_size(512), "512 B");
+        assert_eq!(Locale::International.format_size(2_300_000), "2.3 MB");
+        assert_eq!(Locale::German.format_size(2_300_000), "2,3 MB");
+    }
+}
@@ -12,7 +12,19 @@ mod error;
 mod frontend;
 mod util;
 
-use backend::{database::Selectable, person::Membership, Column};
+use backend::{
+    accounting::{
+        Account, AccountCostCenter, AllocationOutcome, Amount, Budget, BudgetComparison, Category,
+        CostCenter, Entry, EntryAllocation, Hierarchical,
+    },
+    audit::AuditEntry,
+    database::Selectable,
+    document::Document,
+    person::{Group, GroupRemoval, Membership, MergeSummary, Person},
+    upload::{AppendOutcome, Upload, UploadedDocument},
+    user::{User, WhatsNew},
+    Column, ColumnDescription, WhereClause,
+};
 use rocket::{
     data::{Limits, ToByteUnit},
     form::Strict,
@@ -25,16 +37,27 @@ use rocket_dyn_templates::{context, Template};
 use std::ops::Deref;
 use std::path::PathBuf;
 
-use self::auth::{login, login_html, logout, AuthenticatedUser};
+use self::auth::{
+    change_password, current_user, login, login_html, logout, reset_password, AdminUser,
+    AuthenticatedUser,
+};
 use self::backend::{
-    database::{Database, DefaultGenerator, Insertable, PrimaryKey, SelectableByPrimaryKey},
-    Pagination,
+    database::{
+        Database, DatabaseEntry, DefaultGenerator, Insertable, PrimaryKey, Record,
+        SelectableByPrimaryKey,
+    },
+    FutureDate, Limit, Order, Pagination,
 };
 pub use self::frontend::{InsertableDatabaseEntry, Renderable, RenderableDatabaseEntry};
-pub use self::util::{FlexibleInput, PdfOutput};
+pub use self::util::{
+    AuditFilter, BackupOutput, Conditional, CsvOutput, FieldSelection, FlexibleInput,
+    IfModifiedSince, ListFilter, Page, PdfOutput, VcardOutput,
+};
 pub use self::{
     config::Config,
-    error::{error_handler, Error},
+    error::{
+        bad_request_handler, error_handler, unprocessable_entity_handler, Error, ReportParseFailure,
+    },
 };
 
 macro_rules! create_routes {
@@ -43,39 +66,158 @@ macro_rules! create_routes {
         add_json: $path: literal,
         add_frontend: $path_add: literal,
         get_single: $path_id: literal,
-        get_multiple: $path_multiple: literal
+        delete: $path_delete: literal,
+        get_multiple: $path_multiple: literal,
+        get_columns: $path_columns: literal,
+        get_csv: $path_csv: literal,
+        get_form: $path_form: literal,
+        archive: $path_archive: literal
     }) => {
         mod $function_name {
-            use crate::backend::database::{Insertable, Selectable};
+            use crate::backend::{
+                audit::AuditEntry,
+                database::{Deletable, Insertable, Selectable, Timestamped, Updatable},
+            };
             use rocket::{response::status, serde::json::Json, State};
             use rocket_dyn_templates::Template;
 
             use crate::{
                 auth::AuthenticatedUser,
-                frontend::{InsertableDatabaseEntry, Renderable, RenderableDatabaseEntry},
+                frontend::{
+                    CsvDialect, CsvRenderable, InsertableDatabaseEntry, Locale, Renderable,
+                    RenderableDatabaseEntry,
+                },
                 *,
             };
 
             type DatabaseEntry = $database_entry;
             type InputType = <$database_entry as InsertableDatabaseEntry>::PostMethod;
 
+            /// The table whose changes are recorded in the audit log.
+            const TABLE_NAME: &str =
+                <DatabaseEntry as crate::backend::database::DatabaseEntry>::TABLE_NAME;
+
             #[post($path, data = "<database_entry>", rank = 3)]
             pub fn add(
-                _user: AuthenticatedUser,
-                database_entry: InputType,
+                user: AuthenticatedUser,
+                database_entry: ReportParseFailure<InputType>,
                 state: &State<Config>,
             ) -> Result<status::Created<String>, Error> {
-                database_entry
-                    .into_inner()
-                    .insert(&state.database())
+                let database_entry = database_entry.0.into_inner();
+                database_entry.validate(state)?;
+
+                let database = &state.database()?;
+                if DatabaseEntry::ADMIN_ONLY && !user.is_admin(database)? {
+                    return Err(Error::from(rocket::http::Status::Forbidden));
+                }
+                database
+                    .transaction(false, |database| {
+                        let primary_key = database_entry.insert(database)?;
+                        AuditEntry::record(
+                            database,
+                            user.user,
+                            TABLE_NAME,
+                            primary_key.raw_index(),
+                            AuditEntry::INSERT,
+                        )?;
+                        Ok(primary_key)
+                    })
                     .map(|primary_key| status::Created::new(primary_key.to_string()))
                     .map_err(Error::from)
             }
 
+            /// Replace an existing entry, returning it like `get_by_id` does. Archived entries are only found like there.
+            #[put($path_id, data = "<database_entry>")]
+            pub fn update(
+                user: AuthenticatedUser,
+                id: i64,
+                fields: Option<&str>,
+                include_archived: Option<bool>,
+                database_entry: ReportParseFailure<InputType>,
+                state: &State<Config>,
+            ) -> Result<Json<FieldSelection<<DatabaseEntry as Selectable>::Output>>, Error> {
+                let mut database_entry = database_entry.0.into_inner();
+                database_entry.validate(state)?;
+
+                let database = &state.database()?;
+                if DatabaseEntry::ADMIN_ONLY && !user.is_admin(database)? {
+                    return Err(Error::from(rocket::http::Status::Forbidden));
+                }
+                if DatabaseEntry::try_select(database, id)?.is_none()
+                    || (!include_archived.unwrap_or(false) && DatabaseEntry::is_archived(database, id)?)
+                {
+                    return Err(Error::NotFound);
+                }
+                let identifier = PrimaryKey::from(id);
+                database_entry.prepare_update(database, identifier)?;
+                database
+                    .transaction(false, |database| {
+                        if let Some(reason) = DatabaseEntry::conflicting_change(
+                            database,
+                            identifier,
+                            Some(&database_entry),
+                        )? {
+                            return Ok(Err(reason));
+                        }
+                        database_entry.update(database, identifier)?;
+                        AuditEntry::record(database, user.user, TABLE_NAME, id, AuditEntry::UPDATE)?;
+                        Ok(Ok(()))
+                    })?
+                    .map_err(|reason| Error::Conflict(String::from(reason)))?;
+                Ok(Json(FieldSelection::new::<DatabaseEntry>(
+                    DatabaseEntry::select(database, identifier)?,
+                    fields,
+                )?))
+            }
+
+            /// Remove an entry, which is restricted to administrators. Entries still referenced by others are kept.
+            #[delete($path_delete, rank = 2)]
+            pub fn delete(
+                user: AdminUser,
+                id: i64,
+                dry_run: Option<bool>,
+                state: &State<Config>,
+            ) -> Result<Json<Changes<Removal>>, Error> {
+                let dry_run = dry_run.unwrap_or(false);
+                let existed = state.database()?.transaction(dry_run, |database| {
+                    let identifier = PrimaryKey::from(id);
+                    if let Some(reason) = DatabaseEntry::conflicting_change(database, identifier, None)? {
+                        return Ok(Err(reason));
+                    }
+                    let existed = DatabaseEntry::delete(database, identifier)?;
+                    if existed {
+                        AuditEntry::record(database, user.0.user, TABLE_NAME, id, AuditEntry::DELETE)?;
+                    }
+                    Ok(Ok(existed))
+                })?;
+                match existed {
+                    Ok(true) => Ok(Json(Changes::removed(dry_run, 1))),
+                    Ok(false) => Err(Error::NotFound),
+                    Err(reason) => Err(Error::Conflict(String::from(reason))),
+                }
+            }
+
             #[get($path_add, rank = 2)]
-            pub fn add_frontend(user: AuthenticatedUser, state: &State<Config>) -> Template {
-                let database_entry = state.database();
-                DatabaseEntry::prepare_rendering($path, database_entry.deref(), user).render()
+            pub fn add_frontend(user: AuthenticatedUser, state: &State<Config>) -> Result<Template, Error> {
+                let database_entry = state.read_only_database()?;
+                Ok(DatabaseEntry::prepare_rendering($path, database_entry.deref(), user)
+                    .with_accepted_files(state.document_types())
+                    .render())
+            }
+
+            /// Describe the fields of the insertion form, such that other frontends could render the same form.
+            #[get($path_form)]
+            pub fn get_form(
+                user: AuthenticatedUser,
+                state: &State<Config>,
+            ) -> Result<Json<serde_json::Value>, Error> {
+                let database_entry = state.read_only_database()?;
+                let context = DatabaseEntry::prepare_rendering($path, database_entry.deref(), user)
+                    .with_accepted_files(state.document_types())
+                    .generate_context();
+                serde_json::to_value(context)
+                    .map(Json)
+                    .map_err(|_| Error::OtherError(rocket::http::Status::InternalServerError))
             }
 
             #[get($path_multiple, rank = 3)]
@@ -83,46 +225,150 @@ macro_rules! create_routes {
                 _user: AuthenticatedUser,
                 state: &State<Config>,
                 content_type: Option<&rocket::http::ContentType>,
-                limit: Option<crate::backend::Limit>,
-                offset: Option<usize>,
-                order: Option<crate::backend::Order>,
-                sort_by: Option<Column<DatabaseEntry>>,
-            ) -> Result<Result<Template, Json<Vec<<DatabaseEntry as Selectable>::Output>>>, Error>
-            {
-                // For some reason, putting pagination directly does not work. We generate it manually.
-                let pagination = Pagination {
-                    limit: limit.unwrap_or_default(),
-                    column: sort_by.unwrap_or_default(),
-                    order: order.unwrap_or_default(),
-                    offset: offset.unwrap_or(0),
-                };
-                let database = &state.database();
+                pagination: Pagination<DatabaseEntry>,
+                filter: ListFilter<'_>,
+                locale: Locale,
+                if_modified_since: IfModifiedSince,
+            ) -> Result<
+                Result<
+                    Template,
+                    Conditional<Json<Page<FieldSelection<Vec<Timestamped<<DatabaseEntry as Selectable>::Output>>>>>>,
+                >,
+                Error,
+            > {
+                let where_clause = filter.where_clause::<DatabaseEntry>()?;
+                let database = &state.read_only_database()?;
 
                 Ok(match content_type {
                     Some(value) if value.0.is_json() => {
-                        Err(Json(<$database_entry>::select_all_sorted(
-                            &database, pagination, /*.into_inner()*/
-                        )?))
+                        let last_modified = database.last_modified(&[
+                            <DatabaseEntry as crate::backend::database::DatabaseEntry>::TABLE_NAME,
+                        ])?;
+                        Err(if_modified_since.respond(last_modified, || {
+                            let entries = <$database_entry>::select_all_timestamped(
+                                &database,
+                                pagination.clone(),
+                                Some(&where_clause),
+                            )?;
+                            let primary_keys = entries
+                                .iter()
+                                .map(|entry| DatabaseEntry::primary_key(&entry.value).raw_index())
+                                .collect::<Vec<_>>();
+                            let entries = FieldSelection::new::<DatabaseEntry>(entries, filter.fields)?;
+                            let total = match filter.with_count.unwrap_or(false) {
+                                true => Some(DatabaseEntry::count_filtered(&database, &where_clause)?),
+                                false => None,
+                            };
+                            Ok::<_, Error>(Json(match (pagination.after, total) {
+                                (Some(_), total) => {
+                                    let has_more = pagination.has_more(&database, &where_clause)?;
+                                    Page::cursor(entries, &primary_keys, total, has_more, &pagination)
+                                }
+                                (None, Some(total)) => Page::counted(entries, total, &pagination),
+                                (None, None) => Page::Items(entries),
+                            }))
+                        })?)
                     }
                     _ => Ok(<$database_entry>::prepare_rendering_all(
-                        &database, pagination, //.into_inner(),
+                        &database, pagination, &where_clause, locale,
                     )?
                     .render()),
                 })
             }
 
+            /// List the columns the entries could be sorted by, including their types.
+            #[get($path_columns)]
+            pub fn get_columns(_user: AuthenticatedUser) -> Json<Vec<ColumnDescription>> {
+                Json(Column::<DatabaseEntry>::describe_all())
+            }
+
+            /// Download a page of the entries as CSV, i.e. for opening them in a spreadsheet. The entries are filtered like in `get_all`.
+            #[get($path_csv)]
+            pub fn get_csv(
+                _user: AuthenticatedUser,
+                state: &State<Config>,
+                pagination: Pagination<DatabaseEntry>,
+                dialect: Option<CsvDialect>,
+                filter: ListFilter<'_>,
+            ) -> Result<CsvOutput, Error> {
+                let where_clause = filter.where_clause::<DatabaseEntry>()?;
+                let database = state.read_only_database()?;
+                CsvOutput::create(
+                    const_format::concatcp!(
+                        <DatabaseEntry as crate::backend::database::DatabaseEntry>::TABLE_NAME,
+                        ".csv"
+                    ),
+                    |content| {
+                        DatabaseEntry::write_csv_page(
+                            &database,
+                            content,
+                            dialect.unwrap_or_default(),
+                            pagination,
+                            &where_clause,
+                        )
+                    },
+                )
+            }
+
+            /// Get a single entry. Archived entries are only found with `?include_archived=true`.
             #[get($path_id, rank = 9)]
             pub fn get_by_id(
                 _user: AuthenticatedUser,
                 id: i64,
+                fields: Option<&str>,
+                include_archived: Option<bool>,
                 state: &State<Config>,
-            ) -> Result<Json<<DatabaseEntry as Selectable>::Output>, Error> {
-                match DatabaseEntry::try_select(&state.database(), id)? {
-                    Some(value) => Ok(Json(value)),
+            ) -> Result<Json<FieldSelection<Timestamped<<DatabaseEntry as Selectable>::Output>>>, Error> {
+                let database = &state.read_only_database()?;
+                if !include_archived.unwrap_or(false) && DatabaseEntry::is_archived(database, id)? {
+                    return Err(Error::NotFound);
+                }
+                match DatabaseEntry::try_select_timestamped(database, id)? {
+                    Some(value) => Ok(Json(FieldSelection::new::<DatabaseEntry>(value, fields)?)),
                     None => Err(Error::NotFound),
                 }
             }
 
+            /// Archive an entry, which hides it without removing it. Entries which could not be archived are not found.
+            #[post($path_archive)]
+            pub fn archive(
+                user: AuthenticatedUser,
+                id: i64,
+                state: &State<Config>,
+            ) -> Result<status::NoContent, Error> {
+                let exists = state.database()?.transaction(false, |database| {
+                    let exists = DatabaseEntry::set_archived(database, PrimaryKey::from(id), true)?;
+                    if exists {
+                        AuditEntry::record(database, user.user, TABLE_NAME, id, AuditEntry::ARCHIVE)?;
+                    }
+                    Ok(exists)
+                })?;
+                match exists {
+                    true => Ok(status::NoContent),
+                    false => Err(Error::NotFound),
+                }
+            }
+
+            /// Restore an archived entry.
+            #[delete($path_archive)]
+            pub fn unarchive(
+                user: AuthenticatedUser,
+                id: i64,
+                state: &State<Config>,
+            ) -> Result<status::NoContent, Error> {
+                let exists = state.database()?.transaction(false, |database| {
+                    let exists = DatabaseEntry::set_archived(database, PrimaryKey::from(id), false)?;
+                    if exists {
+                        AuditEntry::record(database, user.user, TABLE_NAME, id, AuditEntry::UNARCHIVE)?;
+                    }
+                    Ok(exists)
+                })?;
+                match exists {
+                    true => Ok(status::NoContent),
+                    false => Err(Error::NotFound),
+                }
+            }
+
             #[cfg(test)]
             mod tests {
                 use crate::backend::database::{
@@ -155,6 +401,72 @@ macro_rules! create_routes {
                     json::from_str(&response).expect("valid json")
                 }
 
+                #[test]
+                fn test_get_columns() {
+                    let client = crate::tests::login(rocket());
+                    let response = client.get($path_columns).dispatch();
+                    assert_eq!(response.status(), Status::Ok);
+                    let response = response.into_string().expect("valid str");
+                    assert!(response.starts_with(&format!(
+                        "[{{\"name\":\"{}\"",
+                        TargetEntity::PUBLIC_SORTABLE_COLUMNS[0]
+                    )));
+                }
+
+                #[test]
+                fn test_get_csv() {
+                    let client = {
+                        let engine = rocket();
+                        let state: &State<Config> = State::get(&engine).expect("valid database");
+                        for _ in 0..3 {
+                            let database = &state.database().expect("database connection");
+                            TargetEntity::create_default(database)
+                                .insert(database)
+                                .expect("valid insert");
+                        }
+                        crate::tests::login(engine)
+                    };
+
+                    let response = client
+                        .get(format!("{}/csv?limit=2", ACCESS_POINT))
+                        .dispatch();
+                    assert_eq!(response.status(), Status::Ok);
+                    assert_eq!(
+                        response.content_type(),
+                        Some(rocket::http::ContentType::CSV)
+                    );
+                    assert_eq!(
+                        response.headers().get_one("Content-Disposition"),
+                        Some(
+                            format!(
+                                "attachment; filename=\"{}.csv\"",
+                                <TargetEntity as crate::backend::database::DatabaseEntry>::TABLE_NAME
+                            )
+                            .as_str()
+                        )
+                    );
+
+                    let response = response.into_string().expect("valid str");
+                    let lines: Vec<_> = response.split_terminator("\r\n").collect();
+                    assert_eq!(
+                        lines[0],
+                        <TargetEntity as crate::frontend::CsvRenderable>::CSV_HEADER.join(",")
+                    );
+                    assert_eq!(lines.len(), 3);
+                }
+
+                #[test]
+                fn test_get_form() {
+                    let client = crate::tests::login(rocket());
+                    let response = client.get($path_form).dispatch();
+                    assert_eq!(response.status(), Status::Ok);
+                    let response: serde_json::Value = response.into_json().expect("valid json");
+                    assert_eq!(response["post_url"], ACCESS_POINT);
+                    assert!(response["fields"]
+                        .as_array()
+                        .is_some_and(|fields| !fields.is_empty()));
+                }
+
                 #[test]
                 fn test_get_empty() {
                     let client = crate::tests::login(rocket());
@@ -200,7 +512,7 @@ macro_rules! create_routes {
                     let engine = rocket();
                     let example = {
                         let state: &State<Config> = State::get(&engine).expect("valid database");
-                        TargetEntity::create_default(&state.database())
+                        TargetEntity::create_default(&state.database().expect("database connection"))
                     };
 
                     let client = crate::tests::login(engine);
@@ -224,7 +536,7 @@ macro_rules! create_routes {
                     let engine = rocket();
                     let example = {
                         let state: &State<Config> = State::get(&engine).expect("valid database");
-                        TargetEntity::create_default(&state.database())
+                        TargetEntity::create_default(&state.database().expect("database connection"))
                     };
 
                     let (client, num_elements) =
@@ -248,7 +560,7 @@ macro_rules! create_routes {
 
                         // Insert some entities
                         for _ in 0..4 {
-                            let database = &state.database();
+                            let database = &state.database().expect("database connection");
                             TargetEntity::create_default(database)
                                 .insert(database)
                                 .expect("valid insert");
@@ -261,6 +573,35 @@ macro_rules! create_routes {
                     assert_eq!(response_json.len(), 1);
                 }
 
+                #[test]
+                fn test_get_all_json_with_count() {
+                    let client = {
+                        let engine = rocket();
+                        let state: &State<Config> = State::get(&engine).expect("valid database");
+
+                        // Insert some entities
+                        for _ in 0..4 {
+                            let database = &state.database().expect("database connection");
+                            TargetEntity::create_default(database)
+                                .insert(database)
+                                .expect("valid insert");
+                        }
+
+                        crate::tests::login(engine)
+                    };
+
+                    let response: serde_json::Value = client
+                        .get(format!("{}?limit=3&offset=1&with_count=true", ACCESS_POINT))
+                        .header(rocket::http::ContentType::JSON)
+                        .dispatch()
+                        .into_json()
+                        .expect("valid json");
+                    assert_eq!(response["items"].as_array().map(Vec::len), Some(3));
+                    assert!(response["total"].as_u64().expect("valid total") >= 4);
+                    assert_eq!(response["offset"], 1);
+                    assert_eq!(response["limit"], 3);
+                }
+
                 #[test]
                 fn test_get_all_json_order() {
                     let client = {
@@ -269,7 +610,7 @@ macro_rules! create_routes {
 
                         // Insert some entities
                         for _ in 0..4 {
-                            let database = &state.database();
+                            let database = &state.database().expect("database connection");
                             TargetEntity::create_default(database)
                                 .insert(database)
                                 .expect("valid insert");
@@ -297,7 +638,7 @@ macro_rules! create_routes {
 
                         // Insert some entities
                         for _ in 0..4 {
-                            let database = &state.database();
+                            let database = &state.database().expect("database connection");
                             TargetEntity::create_default(database)
                                 .insert(database)
                                 .expect("valid insert");
@@ -317,7 +658,7 @@ macro_rules! create_routes {
                     let engine = rocket();
                     let example = {
                         let state: &State<Config> = State::get(&engine).expect("valid database");
-                        TargetEntity::create_default(&state.database())
+                        TargetEntity::create_default(&state.database().expect("database connection"))
                     };
 
                     let client = crate::tests::login(engine);
@@ -361,6 +702,101 @@ macro_rules! create_routes {
                     assert_eq!(response.status(), Status::NotFound);
                 }
 
+                #[test]
+                fn test_update() {
+                    let engine = rocket();
+                    let example = {
+                        let state: &State<Config> = State::get(&engine).expect("valid database");
+                        TargetEntity::create_default(&state.database().expect("database connection"))
+                    };
+
+                    let client = crate::tests::login(engine);
+                    let creation_response = client.post(ACCESS_POINT).json(&example).dispatch();
+                    assert_eq!(creation_response.status(), Status::Created, "post");
+                    let primary_key_path = creation_response
+                        .headers()
+                        .get_one("Location")
+                        .expect("valid string");
+
+                    // The replacement differs, so an update without any effect is noticed.
+                    let changed = crate::tests::Changed::changed(example.clone());
+                    assert_ne!(changed, example);
+                    let response = client.put(primary_key_path).json(&changed).dispatch();
+                    assert_eq!(response.status(), Status::Ok, "put");
+                    let response = response.into_string().expect("valid str");
+                    let response_json: <TargetEntity as Selectable>::Output =
+                        json::from_str(&response).expect("valid json");
+                    assert_eq!(
+                        response_json,
+                        <TargetEntity as Selectable>::Output::from(Record {
+                            identifier: <PrimaryKey<_> as std::str::FromStr>::from_str(
+                                primary_key_path
+                            )
+                            .expect("valid key"),
+                            value: changed
+                        })
+                    );
+                }
+
+                #[test]
+                fn test_update_not_found() {
+                    let engine = rocket();
+                    let example = {
+                        let state: &State<Config> = State::get(&engine).expect("valid database");
+                        TargetEntity::create_default(&state.database().expect("database connection"))
+                    };
+
+                    let client = crate::tests::login(engine);
+                    let response = client
+                        .put(format!("{}/42", ACCESS_POINT))
+                        .json(&example)
+                        .dispatch();
+                    assert_eq!(response.status(), Status::NotFound);
+                }
+
+                #[test]
+                fn test_delete() {
+                    let engine = rocket();
+                    let example = {
+                        let state: &State<Config> = State::get(&engine).expect("valid database");
+                        TargetEntity::create_default(&state.database().expect("database connection"))
+                    };
+
+                    let client = crate::tests::login(engine);
+                    let creation_response = client.post(ACCESS_POINT).json(&example).dispatch();
+                    assert_eq!(creation_response.status(), Status::Created, "post");
+                    let primary_key_path = creation_response
+                        .headers()
+                        .get_one("Location")
+                        .expect("valid string");
+
+                    let response = client
+                        .delete(format!("{}?dry_run=true", primary_key_path))
+                        .dispatch();
+                    assert_eq!(response.status(), Status::Ok, "dry run");
+                    let changes: serde_json::Value =
+                        serde_json::from_str(&response.into_string().expect("valid str"))
+                            .expect("valid json");
+                    assert_eq!(
+                        (&changes["dry_run"], &changes["removed"]),
+                        (&serde_json::json!(true), &serde_json::json!(1)),
+                        "dry run"
+                    );
+                    let response = client.get(primary_key_path).dispatch();
+                    assert_eq!(response.status(), Status::Ok, "get after dry run");
+
+                    let response = client.delete(primary_key_path).dispatch();
+                    assert_eq!(response.status(), Status::Ok, "delete");
+                    let changes: serde_json::Value =
+                        serde_json::from_str(&response.into_string().expect("valid str"))
+                            .expect("valid json");
+                    assert_eq!(changes["dry_run"], false, "delete");
+                    let response = client.get(primary_key_path).dispatch();
+                    assert_eq!(response.status(), Status::NotFound, "get");
+                    let response = client.delete(primary_key_path).dispatch();
+                    assert_eq!(response.status(), Status::NotFound, "delete again");
+                }
+
                 #[test]
                 fn test_get_empty_unauthorized() {
                     let client = Client::tracked(rocket()).expect("valid client");
@@ -372,7 +808,7 @@ macro_rules! create_routes {
                 fn test_get_unauthorized() {
                     let engine = rocket();
                     let state: &State<Config> = State::get(&engine).expect("valid database");
-                    let example = TargetEntity::create_default(&state.database());
+                    let example = TargetEntity::create_default(&state.database().expect("database connection"));
 
                     let client = Client::tracked(engine).expect("valid client");
                     let creation_response = client.post(ACCESS_POINT).json(&example).dispatch();
@@ -406,7 +842,7 @@ macro_rules! create_routes {
 macro_rules! write_routes {
     ($($function_name: ident),* + ($($additional: ident),*)) => { paste::paste! {
         routes![$($additional),*, $(
-            $function_name::add, $function_name::get_all, $function_name::get_by_id, $function_name::add_frontend
+            $function_name::add, $function_name::update, $function_name::delete, $function_name::get_all, $function_name::get_by_id, $function_name::add_frontend, $function_name::get_columns, $function_name::get_csv, $function_name::get_form, $function_name::archive, $function_name::unarchive
         ),*]
     }};
 }
@@ -427,115 +863,958 @@ create_routes!(crate::backend::person::Person {
     module: person,
     add_json: "/persons",
     add_frontend: "/persons/new",
-    get_single: "/persons/<id>",
-    get_multiple: "/persons?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/persons/<id>?<fields>&<include_archived>",
+    delete: "/persons/<id>?<dry_run>",
+    get_multiple: "/persons?<filter..>",
+    get_columns: "/persons/columns",
+    get_csv: "/persons/csv?<dialect>&<filter..>",
+    get_form: "/persons/form",
+    archive: "/persons/<id>/archive"
 });
 
+/// The changes of a destructive operation. In a dry run, they were computed but not applied.
+/// Destructive routes accept `?dry_run=true` and respond with their changes either way.
+#[derive(serde::Serialize)]
+struct Changes<T> {
+    dry_run: bool,
+    #[serde(flatten)]
+    changes: T,
+}
+
+/// The number of removed entries, including those removed along with them.
+#[derive(serde::Serialize)]
+struct Removal {
+    removed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memberships: Option<usize>,
+}
+
+impl Changes<Removal> {
+    fn removed(dry_run: bool, removed: usize) -> Self {
+        Changes {
+            dry_run,
+            changes: Removal {
+                removed,
+                memberships: None,
+            },
+        }
+    }
+}
+
+/// Merge a duplicated person into another one, moving all references to the remaining person.
+/// As the duplicate is removed, this is restricted to administrators like any other removal.
+#[post("/persons/<id>/merge/<duplicate>?<dry_run>")]
+async fn merge_persons(
+    id: i64,
+    duplicate: i64,
+    dry_run: Option<bool>,
+    state: &State<Config>,
+    user: AdminUser,
+) -> Result<Json<Changes<MergeSummary>>, Error> {
+    if id == duplicate {
+        return Err(Error::ConstraintViolation);
+    }
+    let dry_run = dry_run.unwrap_or(false);
+    let changes = state.database()?.transaction(dry_run, |database| {
+        let changes = Person::merge(database, PrimaryKey::from(id), PrimaryKey::from(duplicate))?;
+        if changes.is_some() {
+            AuditEntry::record_details(
+                database,
+                user.0.user,
+                Person::TABLE_NAME,
+                id,
+                AuditEntry::UPDATE,
+                format!("merged person {}", duplicate),
+            )?;
+            AuditEntry::record_details(
+                database,
+                user.0.user,
+                Person::TABLE_NAME,
+                duplicate,
+                AuditEntry::DELETE,
+                format!("merged into person {}", id),
+            )?;
+        }
+        Ok(changes)
+    })?;
+    match changes {
+        Some(changes) => Ok(Json(Changes { dry_run, changes })),
+        None => Err(Error::NotFound),
+    }
+}
+
+/// Find the persons containing the query within their name, address, email, or comment, i.e. `?query=doe&limit=10`.
+/// Archived persons are only found with `?include_archived=true`, like in the listing.
+#[get("/persons/search?<query>&<filter..>")]
+async fn search_persons(
+    query: Option<&str>,
+    filter: ListFilter<'_>,
+    pagination: Pagination<Person>,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<Record<Person>>>, Error> {
+    let where_clause = filter.where_clause::<Person>()?;
+    let database = state.read_only_database()?;
+    match query {
+        Some(query) if !query.is_empty() => Ok(Json(Person::search(
+            &database,
+            query,
+            where_clause,
+            pagination,
+        )?)),
+        _ => Err(Error::InvalidValue(String::from(
+            "the search query is empty",
+        ))),
+    }
+}
+
+/// Insert many persons at once, i.e. when migrating from another system. If any of them is invalid, none is inserted.
+#[post("/persons/import", data = "<persons>")]
+async fn import_persons(
+    persons: ReportParseFailure<Json<Vec<Person>>>,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Created<Json<Vec<PrimaryKey<Person>>>>, Error> {
+    let persons = persons.0.into_inner();
+    for (index, person) in persons.iter().enumerate() {
+        person.validate(state).map_err(|error| {
+            Error::InvalidValue(format!("person {} is invalid: {}", index, error))
+        })?;
+    }
+
+    let mut current = 0;
+    let identifiers = state
+        .database()?
+        .transaction(false, |database| {
+            persons
+                .iter()
+                .enumerate()
+                .map(|(index, person)| {
+                    current = index;
+                    let identifier = person.insert(database)?;
+                    AuditEntry::record(
+                        database,
+                        user.user,
+                        Person::TABLE_NAME,
+                        identifier.raw_index(),
+                        AuditEntry::INSERT,
+                    )?;
+                    Ok(identifier)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|error| match Error::from(error) {
+            Error::ConstraintViolation => {
+                Error::InvalidValue(format!("person {} violates a constraint", current))
+            }
+            error => error,
+        })?;
+    Ok(Created::new("/persons").body(Json(identifiers)))
+}
+
+/// Download a person as vCard, i.e. for importing it into the address book of a phone.
+/// Archived persons are only found with `?include_archived=true`, like in `get_by_id`.
+#[get("/persons/<id>/vcard?<include_archived>")]
+async fn person_vcard(
+    id: i64,
+    include_archived: Option<bool>,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<VcardOutput, Error> {
+    let database = state.read_only_database()?;
+    if !include_archived.unwrap_or(false) && Person::is_archived(&database, id)? {
+        return Err(Error::NotFound);
+    }
+    let person = Person::try_select(&database, id)?.ok_or(Error::NotFound)?;
+    Ok(VcardOutput::new(
+        person.value.to_vcard(person.identifier),
+        person.value.name,
+    ))
+}
+
+/// Download a page of the persons as a single file containing all their vCards. The persons are filtered like in the listing.
+#[get("/persons/vcard?<filter..>")]
+async fn persons_vcard(
+    pagination: Pagination<Person>,
+    filter: ListFilter<'_>,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<VcardOutput, Error> {
+    let where_clause = filter.where_clause::<Person>()?;
+    let database = state.read_only_database()?;
+    let persons = Person::select_all_sorted(&database, pagination, Some(&where_clause))?;
+    Ok(VcardOutput::new(
+        persons
+            .into_iter()
+            .map(|person| person.value.to_vcard(person.identifier))
+            .collect(),
+        "persons",
+    ))
+}
+
+/// List the groups a person is not a member of yet.
+#[get("/persons/<id>/joinable-groups")]
+async fn joinable_groups(
+    id: i64,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<Record<Group>>>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(Group::available_for_person(
+        &database,
+        PrimaryKey::from(id),
+    )?))
+}
+
 create_routes!(crate::backend::person::Group {
     module: group,
     add_json: "/groups",
     add_frontend: "/groups/new",
-    get_single: "/groups/<id>",
-    get_multiple: "/groups?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/groups/<id>?<fields>&<include_archived>",
+    delete: "/groups/<id>?<dry_run>",
+    get_multiple: "/groups?<filter..>",
+    get_columns: "/groups/columns",
+    get_csv: "/groups/csv?<dialect>&<filter..>",
+    get_form: "/groups/form",
+    archive: "/groups/<id>/archive"
 });
 
-#[post("/groups/<group_id>/<person_id>")]
+/// Remove a group. A group which still has members is only removed if forced, dropping its memberships as well.
+/// This takes precedence over the generic route, which would fail for groups with members.
+#[delete("/groups/<id>?<force>&<dry_run>")]
+async fn remove_group(
+    id: i64,
+    force: Option<bool>,
+    dry_run: Option<bool>,
+    state: &State<Config>,
+    user: AdminUser,
+) -> Result<Json<Changes<Removal>>, Error> {
+    let dry_run = dry_run.unwrap_or(false);
+    let removal = state.database()?.transaction(dry_run, |database| {
+        let removal = Group::remove(database, PrimaryKey::from(id), force.unwrap_or(false))?;
+        if let Some(GroupRemoval::Removed(memberships)) = removal {
+            AuditEntry::record_details(
+                database,
+                user.0.user,
+                Group::TABLE_NAME,
+                id,
+                AuditEntry::DELETE,
+                format!("removed {} memberships", memberships),
+            )?;
+        }
+        Ok(removal)
+    })?;
+    match removal {
+        Some(GroupRemoval::Removed(memberships)) => Ok(Json(Changes {
+            dry_run,
+            changes: Removal {
+                removed: 1,
+                memberships: Some(memberships),
+            },
+        })),
+        Some(GroupRemoval::HasMembers(_)) => Err(Error::from(rocket::http::Status::Conflict)),
+        None => Err(Error::NotFound),
+    }
+}
+
+/// Add a person to a group. Adding an existing member again returns the existing membership, unless strict is set.
+#[post("/groups/<group_id>/<person_id>?<strict>")]
 async fn add_member_to_group(
     group_id: i64,
     person_id: i64,
+    strict: Option<bool>,
     state: &State<Config>,
-    _user: AuthenticatedUser,
-) -> Result<Created<String>, Error> {
-    Membership {
-        person: PrimaryKey::from(person_id),
-        group: PrimaryKey::from(group_id),
+    user: AuthenticatedUser,
+) -> Result<Result<Created<Json<(PrimaryKey<Person>, PrimaryKey<Group>)>>, Json<Membership>>, Error>
+{
+    let database = &state.database()?;
+    let (person, group) = (PrimaryKey::from(person_id), PrimaryKey::from(group_id));
+    if let Some(membership) = Membership::find(database, person, group)? {
+        return match strict.unwrap_or(false) {
+            true => Err(Error::AlreadyExists),
+            false => Ok(Err(Json(membership))),
+        };
+    }
+
+    let membership = Membership {
+        person,
+        group,
         updated: None,
         comment: None,
-    }
-    .insert(&state.database())
-    .map_err(Error::from)
-    .and_then(|value| match value {
-        1 => Ok(Created::new(format!("/groups/{}/{}", group_id, person_id))),
-        _ => Err(Error::ConstraintViolation),
-    })
+    };
+    database
+        .transaction(false, |database| {
+            let key = membership.insert(database)?;
+            record_membership(database, user.user, &membership, AuditEntry::INSERT)?;
+            Ok(key)
+        })
+        .map_err(Error::from)
+        .map(|key| Ok(Created::new(format!("/groups/{}/{}", group_id, person_id)).body(Json(key))))
+}
+
+/// Record a change of a membership in the audit log. Memberships have no identifier, so the person is named in the details.
+fn record_membership(
+    database: &Database,
+    user: PrimaryKey<User>,
+    membership: &Membership,
+    action: &str,
+) -> Result<(), backend::database::Error> {
+    AuditEntry::record_details(
+        database,
+        user,
+        Group::TABLE_NAME,
+        membership.group.raw_index(),
+        action,
+        format!("membership of person {}", membership.person.raw_index()),
+    )
 }
 
-#[delete("/groups/<group_id>/<person_id>")]
+/// List the members of a group who joined or changed most recently.
+#[get("/groups/<group_id>/members/recent?<limit>")]
+async fn recent_group_members(
+    group_id: i64,
+    limit: Option<Limit>,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<Membership>>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(Membership::recent_members(
+        &database,
+        PrimaryKey::from(group_id),
+        state.limit(limit),
+    )?))
+}
+
+#[delete("/groups/<group_id>/<person_id>?<dry_run>")]
 async fn remove_member_from_group(
     group_id: i64,
     person_id: i64,
+    dry_run: Option<bool>,
+    state: &State<Config>,
+    user: AdminUser,
+) -> Result<Json<Changes<Removal>>, Error> {
+    let dry_run = dry_run.unwrap_or(false);
+    let removed = state.database()?.transaction(dry_run, |database| {
+        remove_membership_recorded(database, user.0.user, person_id, group_id)
+    })?;
+    match removed {
+        0 => Err(Error::NotFound),
+        removed => Ok(Json(Changes::removed(dry_run, removed))),
+    }
+}
+
+/// Add a person to a group, given as `{"person": ..., "group": ..., "comment": ...}`. Existing memberships are rejected.
+#[post("/memberships", data = "<membership>")]
+async fn add_membership(
+    membership: ReportParseFailure<Json<Membership>>,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Created<Json<(PrimaryKey<Person>, PrimaryKey<Group>)>>, Error> {
+    let membership = membership.0.into_inner();
+    let (person, group) = state.database()?.transaction(false, |database| {
+        let key = membership.insert(database)?;
+        record_membership(database, user.user, &membership, AuditEntry::INSERT)?;
+        Ok(key)
+    })?;
+    Ok(Created::new(format!(
+        "/memberships/{}/{}",
+        person.raw_index(),
+        group.raw_index()
+    ))
+    .body(Json((person, group))))
+}
+
+#[delete("/memberships/<person_id>/<group_id>?<dry_run>")]
+async fn remove_membership(
+    person_id: i64,
+    group_id: i64,
+    dry_run: Option<bool>,
+    state: &State<Config>,
+    user: AdminUser,
+) -> Result<Json<Changes<Removal>>, Error> {
+    let dry_run = dry_run.unwrap_or(false);
+    match state.database()?.transaction(dry_run, |database| {
+        remove_membership_recorded(database, user.0.user, person_id, group_id)
+    })? {
+        0 => Err(Error::NotFound),
+        removed => Ok(Json(Changes::removed(dry_run, removed))),
+    }
+}
+
+/// Remove a membership and record it in the audit log, returning the number of removed memberships.
+fn remove_membership_recorded(
+    database: &Database,
+    user: PrimaryKey<User>,
+    person_id: i64,
+    group_id: i64,
+) -> Result<usize, backend::database::Error> {
+    let (person, group) = (PrimaryKey::from(person_id), PrimaryKey::from(group_id));
+    let removed = Membership::remove(person, group, database)?;
+    if removed > 0 {
+        let membership = Membership {
+            person,
+            group,
+            updated: None,
+            comment: None,
+        };
+        record_membership(database, user, &membership, AuditEntry::DELETE)?;
+    }
+    Ok(removed)
+}
+
+/// List the memberships of a person in all of the groups.
+#[get("/persons/<id>/memberships")]
+async fn person_memberships(
+    id: i64,
     state: &State<Config>,
     _user: AuthenticatedUser,
-) -> Result<NoContent, Error> {
-    Membership::remove(
-        PrimaryKey::from(person_id),
-        PrimaryKey::from(group_id),
-        &state.database(),
-    )
-    .map_err(Error::from)
-    .and_then(|value| match value {
-        1 => Ok(NoContent),
-        _ => Err(Error::NotFound),
-    })
+) -> Result<Json<Vec<Membership>>, Error> {
+    let database = &state.read_only_database()?;
+    Person::try_select(database, id)?.ok_or(Error::NotFound)?;
+    Ok(Json(Membership::find_all_memberships(
+        database,
+        PrimaryKey::from(id),
+    )?))
 }
 
 create_routes!(crate::backend::document::Document {
     module: document,
     add_json: "/documents",
     add_frontend: "/documents/new",
-    get_single: "/documents/<id>",
-    get_multiple: "/documents?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/documents/<id>?<fields>&<include_archived>",
+    delete: "/documents/<id>?<dry_run>",
+    get_multiple: "/documents?<filter..>",
+    get_columns: "/documents/columns",
+    get_csv: "/documents/csv?<dialect>&<filter..>",
+    get_form: "/documents/form",
+    archive: "/documents/<id>/archive"
 });
 
-#[get("/documents/<id>/pdf")]
+/// View a document in the browser, or download it with `?download=true`.
+#[get("/documents/<id>/pdf?<download>")]
 async fn download_document(
     id: i64,
+    download: Option<bool>,
     state: &State<Config>,
     _user: AuthenticatedUser,
 ) -> Result<PdfOutput, Error> {
-    PdfOutput::new(&state.database(), PrimaryKey::from(id))
+    let database = state.read_only_database()?;
+    let output = PdfOutput::new(&database, PrimaryKey::from(id))?;
+    Ok(match download.unwrap_or(false) {
+        true => output.as_attachment(format!("document-{}", id)),
+        false => output,
+    })
+}
+
+/// Replace the file of a document, i.e. after a re-scan, while keeping its metadata and identifier.
+#[put("/documents/<id>/file", data = "<file>")]
+async fn replace_document_file(
+    id: i64,
+    file: rocket::Data<'_>,
+    limits: &Limits,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<NoContent, Error> {
+    let file = file
+        .open(limits.get("file").unwrap_or(1.mebibytes()))
+        .into_bytes()
+        .await?;
+    if !file.is_complete() {
+        return Err(Error::from(rocket::http::Status::PayloadTooLarge));
+    }
+    if crate::backend::document::DocumentType::detect(state.document_types(), &file).is_none() {
+        return Err(Error::from(rocket::http::Status::UnsupportedMediaType));
+    }
+
+    let replaced = state.database()?.transaction(false, |database| {
+        let replaced = Document::replace_file(database, PrimaryKey::from(id), &file)?;
+        if replaced {
+            AuditEntry::record_details(
+                database,
+                user.user,
+                Document::TABLE_NAME,
+                id,
+                AuditEntry::UPDATE,
+                "replaced the file",
+            )?;
+        }
+        Ok(replaced)
+    })?;
+    match replaced {
+        true => Ok(NoContent),
+        false => Err(Error::NotFound),
+    }
+}
+
+/// Start uploading a document in chunks, i.e. for large files over unreliable connections.
+#[post("/documents/uploads")]
+async fn start_document_upload(
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Created<()>, Error> {
+    let upload = state.database()?.transaction(false, |database| {
+        let upload = Upload::start(database, user.user)?;
+        AuditEntry::record(
+            database,
+            user.user,
+            Upload::TABLE_NAME,
+            upload,
+            AuditEntry::INSERT,
+        )?;
+        Ok(upload)
+    })?;
+    Ok(Created::new(format!("/documents/uploads/{}", upload)))
+}
+
+/// Report how many bytes were uploaded, i.e. for resuming an interrupted upload.
+#[get("/documents/uploads/<id>", rank = 1)]
+async fn get_document_upload(
+    id: i64,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, Error> {
+    let database = state.read_only_database()?;
+    match Upload::length(&database, id, user.user)? {
+        Some(length) => Ok(Json(serde_json::json!({ "length": length }))),
+        None => Err(Error::NotFound),
+    }
+}
+
+/// Append a chunk to an upload. The offset must match the bytes uploaded so far, otherwise 409 is returned.
+/// Like a regular file, the complete upload must not exceed the "file" limit, otherwise 413 is returned.
+#[patch("/documents/uploads/<id>?<offset>", data = "<chunk>")]
+async fn append_document_upload(
+    id: i64,
+    offset: u64,
+    chunk: rocket::Data<'_>,
+    limits: &Limits,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Json<serde_json::Value>, Error> {
+    let max_length = limits.get("file").unwrap_or(1.mebibytes());
+    let chunk = chunk.open(max_length).into_bytes().await?;
+    if !chunk.is_complete() {
+        return Err(Error::from(rocket::http::Status::PayloadTooLarge));
+    }
+
+    let outcome = state.database()?.transaction(false, |database| {
+        let outcome = Upload::append(database, id, user.user, offset, &chunk, max_length.as_u64())?;
+        if let Some(AppendOutcome::Appended(length)) = outcome {
+            AuditEntry::record_details(
+                database,
+                user.user,
+                Upload::TABLE_NAME,
+                id,
+                AuditEntry::UPDATE,
+                format!("appended {} bytes at offset {}", length - offset, offset),
+            )?;
+        }
+        Ok(outcome)
+    })?;
+    match outcome {
+        Some(AppendOutcome::Appended(length)) => Ok(Json(serde_json::json!({ "length": length }))),
+        Some(AppendOutcome::OffsetMismatch(_)) => Err(Error::from(rocket::http::Status::Conflict)),
+        Some(AppendOutcome::TooLarge(_)) => Err(Error::from(rocket::http::Status::PayloadTooLarge)),
+        None => Err(Error::NotFound),
+    }
+}
+
+/// Turn a complete upload into a document with the given metadata.
+#[post("/documents/uploads/<id>/complete", data = "<metadata>")]
+async fn complete_document_upload(
+    id: i64,
+    metadata: ReportParseFailure<Json<UploadedDocument>>,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Created<()>, Error> {
+    let database = state.database()?;
+    let file = Upload::load(&database, id, user.user)?.ok_or(Error::NotFound)?;
+    let document = metadata.0.into_inner().into_document(file);
+    frontend::InsertableDatabaseEntry::validate(&document, state)?;
+
+    let document = database.transaction(false, |database| {
+        let document = document.insert(database)?;
+        Upload::remove(database, id)?;
+        AuditEntry::record_details(
+            database,
+            user.user,
+            Document::TABLE_NAME,
+            document.raw_index(),
+            AuditEntry::INSERT,
+            format!("completed upload {}", id),
+        )?;
+        Ok(document)
+    })?;
+    Ok(Created::new(document.to_string()))
 }
 
 create_routes!(crate::backend::user::User {
     module: user,
     add_json: "/users",
     add_frontend: "/users/new",
-    get_single: "/users/<id>",
-    get_multiple: "/users?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/users/<id>?<fields>&<include_archived>",
+    delete: "/users/<id>?<dry_run>",
+    get_multiple: "/users?<filter..>",
+    get_columns: "/users/columns",
+    get_csv: "/users/csv?<dialect>&<filter..>",
+    get_form: "/users/form",
+    archive: "/users/<id>/archive"
 });
 
 create_routes!(crate::backend::accounting::Account {
     module: account,
     add_json: "/accounts",
     add_frontend: "/accounts/new",
-    get_single: "/accounts/<id>",
-    get_multiple: "/accounts?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/accounts/<id>?<fields>&<include_archived>",
+    delete: "/accounts/<id>?<dry_run>",
+    get_multiple: "/accounts?<filter..>",
+    get_columns: "/accounts/columns",
+    get_csv: "/accounts/csv?<dialect>&<filter..>",
+    get_form: "/accounts/form",
+    archive: "/accounts/<id>/archive"
 });
 
 create_routes!(crate::backend::accounting::Category {
     module: category,
     add_json: "/categories",
     add_frontend: "/categories/new",
-    get_single: "/categories/<id>",
-    get_multiple: "/categories?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/categories/<id>?<fields>&<include_archived>",
+    delete: "/categories/<id>?<dry_run>",
+    get_multiple: "/categories?<filter..>",
+    get_columns: "/categories/columns",
+    get_csv: "/categories/csv?<dialect>&<filter..>",
+    get_form: "/categories/form",
+    archive: "/categories/<id>/archive"
 });
 
 create_routes!(crate::backend::accounting::CostCenter {
     module: cost_center,
     add_json: "/cost_centers",
     add_frontend: "/cost_centers/new",
-    get_single: "/cost_centers/<id>",
-    get_multiple: "/cost_centers?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/cost_centers/<id>?<fields>&<include_archived>",
+    delete: "/cost_centers/<id>?<dry_run>",
+    get_multiple: "/cost_centers?<filter..>",
+    get_columns: "/cost_centers/columns",
+    get_csv: "/cost_centers/csv?<dialect>&<filter..>",
+    get_form: "/cost_centers/form",
+    archive: "/cost_centers/<id>/archive"
+});
+
+/// Move a category or cost center below another one, or to the top level with `null`.
+fn move_hierarchical<T: Hierarchical + SelectableByPrimaryKey>(
+    database: &Database,
+    user: PrimaryKey<User>,
+    id: i64,
+    parent: Option<PrimaryKey<T>>,
+) -> Result<NoContent, Error> {
+    T::try_select(database, id)?.ok_or(Error::NotFound)?;
+    let moved = database.transaction(false, |database| {
+        let moved = T::set_parent(database, PrimaryKey::from(id), parent)?;
+        if moved {
+            AuditEntry::record_details(
+                database,
+                user,
+                T::TABLE_NAME,
+                id,
+                AuditEntry::UPDATE,
+                match parent {
+                    Some(parent) => format!("moved below {}", parent.raw_index()),
+                    None => String::from("moved to the top level"),
+                },
+            )?;
+        }
+        Ok(moved)
+    })?;
+    match moved {
+        true => Ok(NoContent),
+        false => Err(Error::InvalidValue(String::from(
+            "the parent would create a cycle",
+        ))),
+    }
+}
+
+#[put("/categories/<id>/parent", data = "<parent>")]
+async fn move_category(
+    id: i64,
+    parent: Json<Option<PrimaryKey<Category>>>,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<NoContent, Error> {
+    let database = state.database()?;
+    move_hierarchical(&database, user.user, id, parent.into_inner())
+}
+
+#[put("/cost_centers/<id>/parent", data = "<parent>")]
+async fn move_cost_center(
+    id: i64,
+    parent: Json<Option<PrimaryKey<CostCenter>>>,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<NoContent, Error> {
+    let database = state.database()?;
+    move_hierarchical(&database, user.user, id, parent.into_inner())
+}
+
+#[get("/cost_centers/<id>/monthly?<year>")]
+async fn monthly_cost_center_totals(
+    id: i64,
+    year: i32,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<(u32, backend::accounting::Currency, Amount)>>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(Entry::monthly_totals(
+        &database,
+        PrimaryKey::from(id),
+        year,
+    )?))
+}
+
+create_routes!(crate::backend::accounting::Budget {
+    module: budget,
+    add_json: "/budgets",
+    add_frontend: "/budgets/new",
+    get_single: "/budgets/<id>?<fields>&<include_archived>",
+    delete: "/budgets/<id>?<dry_run>",
+    get_multiple: "/budgets?<filter..>",
+    get_columns: "/budgets/columns",
+    get_csv: "/budgets/csv?<dialect>&<filter..>",
+    get_form: "/budgets/form",
+    archive: "/budgets/<id>/archive"
 });
 
+/// Allow booking an account on a cost center. Once any pairing exists, only allowed pairs could be used by entries.
+#[post("/cost_centers/<id>/accounts/<account>")]
+async fn allow_account_for_cost_center(
+    id: i64,
+    account: i64,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Created<()>, Error> {
+    state.database()?.transaction(false, |database| {
+        AccountCostCenter {
+            account: PrimaryKey::from(account),
+            cost_center: PrimaryKey::from(id),
+        }
+        .insert(database)?;
+        AuditEntry::record_details(
+            database,
+            user.user,
+            CostCenter::TABLE_NAME,
+            id,
+            AuditEntry::INSERT,
+            format!("allowed account {}", account),
+        )
+    })?;
+    Ok(Created::new(format!(
+        "/cost_centers/{}/accounts/{}",
+        id, account
+    )))
+}
+
+#[delete("/cost_centers/<id>/accounts/<account>?<dry_run>")]
+async fn disallow_account_for_cost_center(
+    id: i64,
+    account: i64,
+    dry_run: Option<bool>,
+    state: &State<Config>,
+    user: AdminUser,
+) -> Result<Json<Changes<Removal>>, Error> {
+    let dry_run = dry_run.unwrap_or(false);
+    let removed = state.database()?.transaction(dry_run, |database| {
+        let removed =
+            AccountCostCenter::remove(PrimaryKey::from(account), PrimaryKey::from(id), database)?;
+        if removed == 1 {
+            AuditEntry::record_details(
+                database,
+                user.0.user,
+                CostCenter::TABLE_NAME,
+                id,
+                AuditEntry::DELETE,
+                format!("disallowed account {}", account),
+            )?;
+        }
+        Ok(removed)
+    })?;
+    match removed {
+        1 => Ok(Json(Changes::removed(dry_run, 1))),
+        _ => Err(Error::NotFound),
+    }
+}
+
+#[get("/cost_centers/<id>/budget-vs-actual?<year>")]
+async fn budget_vs_actual(
+    id: i64,
+    year: u32,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<BudgetComparison>, Error> {
+    let database = state.read_only_database()?;
+    Budget::compare(&database, PrimaryKey::from(id), year)?
+        .map(Json)
+        .ok_or(Error::NotFound)
+}
+
 create_routes!(crate::backend::accounting::Entry {
     module: entry,
     add_json: "/entries",
     add_frontend: "/entries/new",
-    get_single: "/entries/<id>",
-    get_multiple: "/entries?<sort_by>&<limit>&<offset>&<order>"
+    get_single: "/entries/<id>?<fields>&<include_archived>",
+    delete: "/entries/<id>?<dry_run>",
+    get_multiple: "/entries?<filter..>",
+    get_columns: "/entries/columns",
+    get_csv: "/entries/csv?<dialect>&<filter..>",
+    get_form: "/entries/form",
+    archive: "/entries/<id>/archive"
 });
 
+#[get("/entries?<expand>", rank = 1)]
+async fn get_entries_expanded(
+    expand: frontend::EntryExpansion,
+    pagination: Pagination<Entry>,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<frontend::ExpandedEntry>>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(frontend::ExpandedEntry::load(
+        &database, pagination, expand,
+    )?))
+}
+
+/// List the documents which are not used as evidence by any entry.
+#[get("/admin/orphans")]
+async fn find_orphaned_documents(
+    state: &State<Config>,
+    _user: AdminUser,
+) -> Result<Json<Vec<crate::backend::document::Metadata>>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(crate::backend::document::Document::find_orphans(
+        &database,
+    )?))
+}
+
+/// Check the database for corruption. Returns "ok" or the list of problems found.
+#[get("/admin/integrity")]
+async fn check_integrity(
+    state: &State<Config>,
+    _user: AdminUser,
+) -> Result<Json<serde_json::Value>, Error> {
+    let problems = state.read_only_database()?.integrity_check()?;
+    Ok(Json(match problems.is_empty() {
+        true => serde_json::Value::from("ok"),
+        false => serde_json::Value::from(problems),
+    }))
+}
+
+/// List the changes made through the API, starting with the most recent one. They might be restricted to a user, a table, or a period.
+#[get("/audit?<limit>&<offset>&<filter..>")]
+async fn audit_log(
+    limit: Option<Limit>,
+    offset: Option<usize>,
+    filter: AuditFilter<'_>,
+    state: &State<Config>,
+    _user: AdminUser,
+) -> Result<Json<Vec<Record<AuditEntry>>>, Error> {
+    let filter = filter.where_clause()?;
+    let database = state.read_only_database()?;
+    Ok(Json(AuditEntry::select_latest(
+        &database,
+        &filter,
+        state.limit(limit),
+        offset.unwrap_or(0),
+    )?))
+}
+
+/// Download a consistent copy of the whole database, i.e. for backups while the server is running.
+#[get("/admin/backup")]
+async fn backup(state: &State<Config>, _user: AdminUser) -> Result<BackupOutput, Error> {
+    let database = state.database()?;
+    // The copy is taken from the writing connection, so no change interferes with it.
+    BackupOutput::create(&database)
+}
+
+/// Count the records created since the login before the current one, i.e. for highlighting them.
+#[get("/whats-new")]
+async fn whats_new(
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<Json<WhatsNew>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(User::whats_new(
+        &database,
+        user.user,
+        &[
+            Person::TABLE_NAME,
+            Group::TABLE_NAME,
+            User::TABLE_NAME,
+            Document::TABLE_NAME,
+            Category::TABLE_NAME,
+            CostCenter::TABLE_NAME,
+            Account::TABLE_NAME,
+            Entry::TABLE_NAME,
+            Budget::TABLE_NAME,
+        ],
+    )?))
+}
+
+#[get("/entries?<reference>", rank = 2)]
+async fn find_entries_by_reference(
+    reference: String,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<Record<Entry>>>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(Entry::find_by_reference(&database, reference)?))
+}
+
+#[get("/entries/<id>/allocations")]
+async fn get_entry_allocations(
+    id: i64,
+    state: &State<Config>,
+    _user: AuthenticatedUser,
+) -> Result<Json<Vec<EntryAllocation>>, Error> {
+    let database = state.read_only_database()?;
+    Ok(Json(EntryAllocation::load(
+        &database,
+        PrimaryKey::from(id),
+    )?))
+}
+
+/// Split an entry across cost centers. The allocations must sum up to the amount of the entry, while an empty list removes the split.
+#[put("/entries/<id>/allocations", data = "<allocations>")]
+async fn allocate_entry(
+    id: i64,
+    allocations: Json<Vec<EntryAllocation>>,
+    state: &State<Config>,
+    user: AuthenticatedUser,
+) -> Result<NoContent, Error> {
+    let outcome = state.database()?.transaction(false, |database| {
+        let outcome = EntryAllocation::replace(database, PrimaryKey::from(id), &allocations)?;
+        if let Some(AllocationOutcome::Allocated) = outcome {
+            AuditEntry::record_details(
+                database,
+                user.user,
+                Entry::TABLE_NAME,
+                id,
+                AuditEntry::UPDATE,
+                format!("allocated to {} cost centers", allocations.len()),
+            )?;
+        }
+        Ok(outcome)
+    })?;
+    match outcome {
+        Some(AllocationOutcome::Allocated) => Ok(NoContent),
+        Some(AllocationOutcome::SumMismatch(amount)) => Err(Error::InvalidValue(format!(
+            "the allocations must sum up to {}",
+            amount
+        ))),
+        Some(AllocationOutcome::NotAllowed(_)) => Err(Error::InvalidValue(String::from(
+            "the account is not allowed for the cost center",
+        ))),
+        None => Err(Error::NotFound),
+    }
+}
+
 /// Read a value from STDIN and return it without whitespace.
 fn read_value(message: &'static str) -> String {
     let mut input = String::new();
@@ -554,8 +1833,25 @@ fn read_value(message: &'static str) -> String {
     }
 }
 
+/// The environment variable which prevents creating the first user, i.e. while a backup is restored.
+const ENV_NO_BOOTSTRAP: &str = "SHELBY_NO_BOOTSTRAP";
+
+/// Check whether the first user needs to be created. Without bootstrapping, a database without users is rejected.
+fn requires_first_user(database: &Database, bootstrap: bool) -> Result<bool, String> {
+    let users = backend::user::User::count(database).map_err(|error| error.to_string())?;
+    match (users, bootstrap) {
+        (0, true) => Ok(true),
+        (0, false) => Err(format!(
+            "the database contains no users while {} is set",
+            ENV_NO_BOOTSTRAP
+        )),
+        _ => Ok(false),
+    }
+}
+
 /// Load the database, insert a default user if not specified, or kill the application on failure.
 fn load_database() -> Database {
+    let bootstrap = std::env::var_os(ENV_NO_BOOTSTRAP).is_none();
     let command_line_args: Vec<String> = std::env::args().collect();
     let (new_user, database) = match &command_line_args.as_slice() {
         &[_, path] => {
@@ -569,26 +1865,33 @@ fn load_database() -> Database {
             };
 
             // Prepare the first user, if not specified
-            match backend::user::User::select_all(&database) {
-                Ok(users) if users.len() == 0 => {
+            match requires_first_user(&database, bootstrap) {
+                Ok(true) => {
                     let user_name = read_value("Please enter the first user name: ");
                     let password = read_value("Please enter the password: ");
                     (Some((user_name, password)), database)
                 }
+                Ok(false) => (None, database),
                 Err(error) => {
-                    eprintln!("Creating the database failed: {}", error);
+                    eprintln!("Loading the database failed: {}", error);
                     std::process::exit(-1)
                 }
-                _ => (None, database),
             }
         }
         _ => {
             // Create the database in memory and prepare the default user
             let database = Database::in_memory().expect("valid database");
-            (
-                Some((String::from("admin"), String::from("test1234"))),
-                database,
-            )
+            match requires_first_user(&database, bootstrap) {
+                Ok(true) => (
+                    Some((String::from("admin"), String::from("test1234"))),
+                    database,
+                ),
+                Ok(false) => (None, database),
+                Err(error) => {
+                    eprintln!("Loading the database failed: {}", error);
+                    std::process::exit(-1)
+                }
+            }
         }
     };
 
@@ -596,6 +1899,7 @@ fn load_database() -> Database {
         let mut admin = crate::backend::user::User::create_default(&database);
         admin.password_hash = crate::backend::user::PasswordHash::new(&username, &password);
         admin.username = username;
+        admin.is_admin = true;
         admin.insert(&database).expect("unable to add Admin user");
     }
 
@@ -604,7 +1908,7 @@ fn load_database() -> Database {
 
 #[launch]
 fn rocket() -> _ {
-    use self::frontend::{group_overview, index_protected};
+    use self::frontend::{export_database, group_overview, index_protected};
 
     let database = load_database();
     let config = match Config::from_env(database) {
@@ -618,7 +1922,14 @@ fn rocket() -> _ {
     rocket::build()
         .manage(config)
         .attach(Template::fairing())
-        .register("/", catchers![error_handler])
+        .register(
+            "/",
+            catchers![
+                error_handler,
+                bad_request_handler,
+                unprocessable_entity_handler
+            ],
+        )
         .mount(
             "/",
             write_routes!(
@@ -628,6 +1939,7 @@ fn rocket() -> _ {
                 user,
                 category,
                 cost_center,
+                budget,
                 entry,
                 account
                     + (
@@ -637,10 +1949,45 @@ fn rocket() -> _ {
                         login,
                         login_html,
                         logout,
+                        change_password,
+                        reset_password,
+                        current_user,
                         download_document,
+                        replace_document_file,
+                        start_document_upload,
+                        get_document_upload,
+                        append_document_upload,
+                        complete_document_upload,
                         group_overview,
                         add_member_to_group,
-                        remove_member_from_group
+                        remove_member_from_group,
+                        remove_group,
+                        recent_group_members,
+                        add_membership,
+                        remove_membership,
+                        person_memberships,
+                        merge_persons,
+                        search_persons,
+                        import_persons,
+                        person_vcard,
+                        persons_vcard,
+                        joinable_groups,
+                        find_entries_by_reference,
+                        whats_new,
+                        get_entry_allocations,
+                        allocate_entry,
+                        find_orphaned_documents,
+                        check_integrity,
+                        backup,
+                        audit_log,
+                        get_entries_expanded,
+                        move_category,
+                        move_cost_center,
+                        monthly_cost_center_totals,
+                        budget_vs_actual,
+                        allow_account_for_cost_center,
+                        disallow_account_for_cost_center,
+                        export_database
                     )
             ),
         )
@@ -650,12 +1997,43 @@ fn rocket() -> _ {
 mod tests {
     use super::{auth, rocket, Config};
     use crate::backend::{
-        database::{DefaultGenerator, Insertable, PrimaryKey},
+        database::{DefaultGenerator, Insertable, PrimaryKey, SelectableByPrimaryKey},
         person::{Group, Membership, Person},
     };
     use rocket::{http::ContentType, local::blocking::Client, State};
     use rocket_dyn_templates::context;
 
+    /// An entry changed visibly, such that replacing the original with it could not go unnoticed.
+    pub trait Changed {
+        fn changed(self) -> Self;
+    }
+
+    /// Implement `Changed` by assigning a new value to one of the fields.
+    macro_rules! impl_changed {
+        ($($entity: ty: $field: ident = $value: expr),* $(,)?) => {
+            $(
+                impl Changed for $entity {
+                    fn changed(mut self) -> Self {
+                        self.$field = $value;
+                        self
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_changed!(
+        Person: name = String::from("Changed"),
+        Group: description = String::from("Changed"),
+        crate::backend::document::Document: description = String::from("Changed"),
+        crate::backend::user::User: must_change_password = true,
+        crate::backend::accounting::Account: description = String::from("Changed"),
+        crate::backend::accounting::Category: description = String::from("Changed"),
+        crate::backend::accounting::CostCenter: description = String::from("Changed"),
+        crate::backend::accounting::Budget: year = 2025,
+        crate::backend::accounting::Entry: description = String::from("Changed"),
+    );
+
     fn add_user<P: rocket::Phase>(
         engine: rocket::Rocket<P>,
         credentials: &auth::Credentials,
@@ -671,13 +2049,21 @@ mod tests {
     ) -> (Client, T) {
         let result = {
             let database_container: &State<Config> = State::get(&engine).expect("valid database");
-            let database = database_container.database();
+            let database = database_container.database().expect("database connection");
 
             let mut user = crate::backend::user::User::create_default(&database);
             user.username = String::from(&credentials.user);
             user.password_hash =
                 crate::backend::user::PasswordHash::new(&credentials.user, &credentials.password);
             user.insert(&database).expect("user insertion sucessfull");
+            // The login picks any user of that name, including ones generated as dependencies.
+            database
+                .connection
+                .execute(
+                    "UPDATE users SET is_admin = TRUE WHERE username = ?",
+                    (&credentials.user,),
+                )
+                .expect("valid update");
 
             callback(&database)
         };
@@ -719,7 +2105,8 @@ mod tests {
     ) -> (PrimaryKey<Person>, PrimaryKey<Group>) {
         let database = State::<Config>::get(&engine)
             .expect("valid database")
-            .database();
+            .database()
+            .expect("database connection");
         let person = Person::create_default(&database)
             .insert(&database)
             .expect("valid person");
@@ -738,6 +2125,122 @@ mod tests {
         login(rocket());
     }
 
+    #[test]
+    fn test_whats_new() {
+        let credentials = auth::Credentials {
+            user: String::from("Chris"),
+            password: String::from("test1234"),
+        };
+        let engine = rocket();
+        let client = add_user(engine, &credentials);
+        let login = || {
+            // The creation times are only precise up to milliseconds.
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            let response = client
+                .post("/users/login")
+                .header(ContentType::Form)
+                .body("user=Chris&password=test1234")
+                .dispatch();
+            assert_eq!(response.status(), rocket::http::Status::SeeOther);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+
+        login();
+        let response = client.post("/persons").json(&Person::default()).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        login();
+
+        let response = client.get("/whats-new").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response: serde_json::Value = response.into_json().expect("valid json");
+        assert!(response["since"].is_string());
+        assert_eq!(response["created"]["persons"], 1);
+        assert_eq!(response["created"]["users"], 0);
+    }
+
+    #[test]
+    fn test_person_form() {
+        let client = crate::tests::login(rocket());
+        let response = client.get("/persons/form").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+
+        let response: serde_json::Value = response.into_json().expect("valid json");
+        let email = response["fields"]
+            .as_array()
+            .and_then(|fields| fields.iter().find(|field| field["name"] == "email"))
+            .expect("email field");
+        assert_eq!(email["required"], false);
+        assert_eq!(email["input_type"], "email");
+    }
+
+    #[test]
+    fn test_person_without_email() {
+        let client = crate::tests::login(rocket());
+        for body in [
+            r#"{"name": "Max", "address": "Street 1"}"#,
+            r#"{"name": "Erika", "address": "Street 2", "email": null}"#,
+        ] {
+            let response = client
+                .post("/persons")
+                .header(ContentType::JSON)
+                .body(body)
+                .dispatch();
+            assert_eq!(response.status(), rocket::http::Status::Created);
+        }
+
+        let persons: Vec<crate::backend::person::Person> = client
+            .get("/persons")
+            .header(ContentType::JSON)
+            .dispatch()
+            .into_json()
+            .expect("valid json");
+        assert_eq!(persons.len(), 2);
+        assert!(persons.iter().all(|person| person.email.is_none()));
+
+        let response = client.get("/persons").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+    }
+
+    #[test]
+    fn test_login_next() {
+        let credentials = auth::Credentials {
+            user: String::from("Chris"),
+            password: String::from("test1234"),
+        };
+        let client = add_user(rocket(), &credentials);
+
+        let response = client.get("/users/login?next=%2Fpersons").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert!(response
+            .into_string()
+            .expect("valid str")
+            .contains("action=\"/users/login?next=%2Fpersons\""));
+
+        // External targets are rejected without logging in ...
+        for next in ["https%3A%2F%2Fexample.com", "%2F%2Fexample.com"] {
+            let response = client
+                .post(format!("/users/login?next={}", next))
+                .header(ContentType::Form)
+                .body("user=Chris&password=test1234")
+                .dispatch();
+            assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        }
+        let response = client.get("/persons").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
+
+        // ... while local paths are used after the login.
+        let response = client
+            .post("/users/login?next=%2Fpersons%3Flimit%3D5")
+            .header(ContentType::Form)
+            .body("user=Chris&password=test1234")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::SeeOther);
+        assert_eq!(
+            response.headers().get_one("Location"),
+            Some("/persons?limit=5")
+        );
+    }
+
     #[test]
     fn test_login_fail() {
         let credentials = auth::Credentials {
@@ -764,74 +2267,503 @@ mod tests {
     }
 
     #[test]
-    fn test_logout_without_login() {
+    fn test_login_unknown_user() {
         let client = Client::tracked(rocket()).expect("valid client");
-        let login_response = client.get("/users/logout").dispatch();
-        // We may thing about changing that
-        assert_eq!(login_response.status(), rocket::http::Status::SeeOther);
+        let response = client
+            .post("/users/login")
+            .header(ContentType::Form)
+            .body("user=Unknown&password=test1234")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
     }
 
     #[test]
-    fn test_login_page() {
+    fn test_login_inactive_user() {
         let credentials = auth::Credentials {
             user: String::from("Chris"),
             password: String::from("test1234"),
         };
+        let (client, _) = add_user_with_callback(rocket(), &credentials, |database| {
+            database
+                .connection
+                .execute("UPDATE users SET active = FALSE", ())
+                .expect("valid update")
+        });
 
-        let client = add_user(rocket(), &credentials);
-        let recieved_login_page = client
-            .get("/")
-            .dispatch()
-            .into_string()
-            .expect("valid string");
-
-        // Simulate login
-        let creation_response = client
+        // The account is only reported as inactive with the right password ...
+        let response = client
             .post("/users/login")
             .header(ContentType::Form)
             .body("user=Chris&password=test1234")
             .dispatch();
-        assert_eq!(creation_response.status(), rocket::http::Status::SeeOther);
-
-        // Now we get the dashboard!
-        let recieved_dashboard = client
-            .get("/")
-            .dispatch()
-            .into_string()
-            .expect("valid string");
+        assert_eq!(response.status(), rocket::http::Status::Locked);
+        let response = client
+            .post("/users/login")
+            .header(ContentType::Form)
+            .body("user=Chris&password=WRONG_PASSWORD")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
 
-        assert_ne!(recieved_login_page, recieved_dashboard);
+        // ... and the user is not logged in.
+        let response = client.get("/persons").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
     }
 
     #[test]
-    fn test_error_catching_html() {
+    fn test_logout_without_login() {
         let client = Client::tracked(rocket()).expect("valid client");
-        let response = client.get("/invalid_page").dispatch();
+        let login_response = client.get("/users/logout").dispatch();
+        // We may thing about changing that
+        assert_eq!(login_response.status(), rocket::http::Status::SeeOther);
+    }
 
-        assert_eq!(response.status(), rocket::http::Status::NotFound);
+    #[test]
+    fn test_must_change_password() {
+        let (client, user) = crate::tests::login_with_callback(rocket(), |database| {
+            let user = crate::backend::user::User::select_by_name(database, "Chris")
+                .expect("valid query")
+                .expect("existing user");
+            crate::backend::user::User::require_password_change(database, user.identifier, true)
+                .expect("valid update");
+            user.identifier
+        });
 
-        // Ensure we got a custom error page mentioning "Shelby"
-        let response = response.into_string().expect("valid string");
-        assert!(response.find("Shelby").is_some())
+        // The flagged user is blocked from everything else ...
+        let response = client.get("/persons").header(ContentType::JSON).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Forbidden);
+
+        // ... but could change the password, ...
+        let response = client
+            .post(auth::AuthenticatedUser::<auth::Fail>::CHANGE_PASSWORD_PATH)
+            .header(ContentType::Form)
+            .body("password=test1234&new_password=changed5678")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NoContent);
+
+        // ... which lifts the restriction.
+        let response = client.get("/persons").header(ContentType::JSON).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+
+        let database = client
+            .rocket()
+            .state::<Config>()
+            .expect("valid database")
+            .database()
+            .expect("database connection");
+        let record = crate::backend::user::User::select_record(&database, user)
+            .expect("valid query")
+            .expect("existing user");
+        assert!(record.password_hash.matches("Chris", "changed5678"));
+        assert!(!record.must_change_password);
     }
 
     #[test]
-    fn test_document_pdf() {
-        let engine = rocket();
-        let example_data = vec![42u8, 41, 40];
-        let example = {
-            let state: &State<Config> = State::get(&engine).expect("valid database");
+    fn test_reset_password() {
+        use crate::backend::user::User;
 
-            let mut example = crate::backend::document::Document::create_default(&state.database());
-            example.document = example_data.clone();
-            example
+        let (client, user) = crate::tests::login_with_callback(rocket(), |database| {
+            let mut user = User::create_default(database);
+            user.username = String::from("Max");
+            user.insert(database).expect("valid user")
+        });
+        let response = client
+            .post(format!("/users/{}/reset-password", user.raw_index()))
+            .header(ContentType::Form)
+            .body("new_password=reset5678&must_change_password=true")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NoContent);
+
+        // The connection is released before the next request.
+        let select_record = || {
+            let database = client
+                .rocket()
+                .state::<Config>()
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            User::select_record(&database, user)
+                .expect("valid query")
+                .expect("existing user")
         };
+        let record = select_record();
+        assert!(record.password_hash.matches("Max", "reset5678"));
+        assert!(record.must_change_password);
 
-        let client = crate::tests::login(engine);
-        let creation_response = client.post("/documents").json(&example).dispatch();
-        assert_eq!(creation_response.status(), rocket::http::Status::Created);
+        // Without the flag, the new password is used as it is.
+        let response = client
+            .post(format!("/users/{}/reset-password", user.raw_index()))
+            .header(ContentType::Form)
+            .body("new_password=again5678")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NoContent);
+        let record = select_record();
+        assert!(record.password_hash.matches("Max", "again5678"));
+        assert!(!record.must_change_password);
 
-        // Generate the URL for the PDF
+        let response = client
+            .post(format!("/users/{}/reset-password", user.raw_index() + 1))
+            .header(ContentType::Form)
+            .body("new_password=reset5678")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+    }
+
+    #[test]
+    fn test_reset_password_admin_only() {
+        use crate::backend::{database::Updatable, user::User};
+
+        let (client, user) = crate::tests::login_with_callback(rocket(), |database| {
+            let mut user = User::select_by_name(database, "Chris")
+                .expect("valid query")
+                .expect("existing user");
+            user.value.is_admin = false;
+            user.value
+                .update(database, user.identifier)
+                .expect("valid update");
+            user.identifier
+        });
+        let response = client
+            .post(format!("/users/{}/reset-password", user.raw_index()))
+            .header(ContentType::Form)
+            .body("new_password=reset5678")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Forbidden);
+    }
+
+    #[test]
+    fn test_change_password_wrong_password() {
+        let client = crate::tests::login(rocket());
+        let response = client
+            .post("/users/password")
+            .header(ContentType::Form)
+            .body("password=WRONG_PASSWORD&new_password=changed5678")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_current_user() {
+        let client = crate::tests::login(rocket());
+        let response = client.get("/users/me").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let current_user: auth::CurrentUser =
+            rocket::serde::json::from_str(&response.into_string().expect("valid str"))
+                .expect("valid json");
+        assert_eq!(current_user.user.username, "Chris");
+        assert!(current_user.can_create);
+        assert!(current_user.can_delete);
+    }
+
+    #[test]
+    fn test_last_admin() {
+        use crate::backend::user::User;
+
+        let (client, mut admin) = crate::tests::login_with_callback(rocket(), |database| {
+            let admin = User::select_by_name(database, "Chris")
+                .expect("valid query")
+                .expect("existing user");
+            database
+                .connection
+                .execute(
+                    "UPDATE users SET is_admin = FALSE WHERE id != ?",
+                    (admin.identifier.raw_index(),),
+                )
+                .expect("valid update");
+            admin
+        });
+        let url = format!("/users/{}", admin.identifier.raw_index());
+
+        // Neither demoting, deactivating, nor removing the sole administrator is possible ...
+        admin.value.is_admin = false;
+        let response = client.put(&url).json(&admin.value).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Conflict);
+        admin.value.is_admin = true;
+        admin.value.active = false;
+        let response = client.put(&url).json(&admin.value).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Conflict);
+        let response = client.delete(&url).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Conflict);
+
+        let response = client.get("/users/me").dispatch();
+        let current_user: auth::CurrentUser =
+            rocket::serde::json::from_str(&response.into_string().expect("valid str"))
+                .expect("valid json");
+        assert!(current_user.user.is_admin);
+        assert!(current_user.user.active);
+
+        // ... unless another administrator remains.
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        let mut other = User::create_default(&database);
+        other.username = String::from("Max");
+        other.is_admin = true;
+        let response = client.post("/users").json(&other).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        admin.value.active = true;
+        admin.value.is_admin = false;
+        let response = client.put(&url).json(&admin.value).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+    }
+
+    #[test]
+    fn test_admin_only() {
+        use crate::backend::{database::Updatable, user::User};
+
+        let (client, person) = crate::tests::login_with_callback(rocket(), |database| {
+            let mut user = User::select_by_name(database, "Chris")
+                .expect("valid query")
+                .expect("existing user");
+            user.value.is_admin = false;
+            user.value
+                .update(database, user.identifier)
+                .expect("valid update");
+            Person::create_default(database)
+                .insert(database)
+                .expect("valid person")
+        });
+
+        let response = client.get("/users/me").dispatch();
+        let current_user: auth::CurrentUser =
+            rocket::serde::json::from_str(&response.into_string().expect("valid str"))
+                .expect("valid json");
+        assert!(!current_user.user.is_admin);
+        assert!(!current_user.can_create);
+        assert!(!current_user.can_delete);
+
+        // Other users could neither be created nor changed ...
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        let mut user = User::create_default(&database);
+        user.username = String::from("Max");
+        let response = client.post("/users").json(&user).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Forbidden);
+        let response = client.put("/users/1").json(&user).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Forbidden);
+
+        // ... nor could anything be removed, not even by merging it into another entry, ...
+        let response = client
+            .delete(format!("/persons/{}", person.raw_index()))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Forbidden);
+        let response = client
+            .post(format!(
+                "/persons/{}/merge/{}",
+                person.raw_index() + 1,
+                person.raw_index()
+            ))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Forbidden);
+
+        // ... while other entries are created as usual.
+        let response = client.post("/persons").json(&Person::default()).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+    }
+
+    #[test]
+    fn test_no_bootstrap() {
+        let database = crate::backend::database::Database::in_memory().expect("valid database");
+        assert_eq!(super::requires_first_user(&database, true), Ok(true));
+        assert!(super::requires_first_user(&database, false).is_err());
+
+        crate::backend::user::User::create_default(&database)
+            .insert(&database)
+            .expect("valid user");
+        assert_eq!(super::requires_first_user(&database, false), Ok(false));
+    }
+
+    #[test]
+    fn test_login_cookie_settings() {
+        let settings = auth::CookieSettings::new(
+            String::from("custom_auth"),
+            rocket::http::SameSite::Strict,
+            true,
+        )
+        .expect("valid settings");
+        let config = Config::from_env(
+            crate::backend::database::Database::in_memory().expect("valid database"),
+        )
+        .expect("valid config")
+        .with_cookie_settings(settings);
+        let engine = rocket::build()
+            .manage(config)
+            .mount("/", rocket::routes![auth::login]);
+
+        let credentials = auth::Credentials {
+            user: String::from("Chris"),
+            password: String::from("test1234"),
+        };
+        let client = add_user(engine, &credentials);
+        let response = client
+            .post("/users/login")
+            .header(ContentType::Form)
+            .body("user=Chris&password=test1234")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::SeeOther);
+
+        let cookie = response
+            .headers()
+            .get_one("Set-Cookie")
+            .expect("cookie set on login");
+        assert!(cookie.starts_with("custom_auth="));
+        assert!(cookie.contains("SameSite=Strict"));
+        assert!(cookie.contains("Secure"));
+    }
+
+    #[test]
+    fn test_max_limit() {
+        use crate::backend::database::DefaultGenerator;
+
+        let config = Config::from_env(
+            crate::backend::database::Database::in_memory().expect("valid database"),
+        )
+        .expect("valid config")
+        .with_max_limit(crate::backend::Limit::from(3));
+        let engine = rocket::build()
+            .manage(config)
+            .attach(rocket_dyn_templates::Template::fairing())
+            .mount("/", rocket::routes![auth::login, super::person::get_all]);
+
+        let credentials = auth::Credentials {
+            user: String::from("Chris"),
+            password: String::from("test1234"),
+        };
+        let (client, _) = add_user_with_callback(engine, &credentials, |database| {
+            for _ in 0..5 {
+                Person::create_default(database)
+                    .insert(database)
+                    .expect("valid person");
+            }
+        });
+        let response = client
+            .post("/users/login")
+            .header(ContentType::Form)
+            .body("user=Chris&password=test1234")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::SeeOther);
+
+        let response = client
+            .get("/persons?limit=500")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let persons: Vec<serde_json::Value> = response.into_json().expect("valid json");
+        assert_eq!(persons.len(), 3);
+    }
+
+    #[test]
+    fn test_zero_limit() {
+        let client = crate::tests::login(rocket());
+        for content_type in [ContentType::HTML, ContentType::JSON] {
+            let response = client
+                .get("/persons?limit=0")
+                .header(content_type)
+                .dispatch();
+            assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        }
+    }
+
+    #[test]
+    fn test_login_expired() {
+        let config = Config::from_env(
+            crate::backend::database::Database::in_memory().expect("valid database"),
+        )
+        .expect("valid config")
+        .with_session_max_age(std::time::Duration::ZERO);
+        let engine = rocket::build()
+            .manage(config)
+            .mount("/", rocket::routes![auth::login, auth::current_user]);
+
+        let credentials = auth::Credentials {
+            user: String::from("Chris"),
+            password: String::from("test1234"),
+        };
+        let client = add_user(engine, &credentials);
+        let response = client
+            .post("/users/login")
+            .header(ContentType::Form)
+            .body("user=Chris&password=test1234")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::SeeOther);
+
+        // The session expired right after the login.
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let response = client.get("/users/me").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_insecure_same_site_none_rejected() {
+        assert_eq!(
+            auth::CookieSettings::new(
+                String::from("shelby_auth"),
+                rocket::http::SameSite::None,
+                false
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_login_page() {
+        let credentials = auth::Credentials {
+            user: String::from("Chris"),
+            password: String::from("test1234"),
+        };
+
+        let client = add_user(rocket(), &credentials);
+        let recieved_login_page = client
+            .get("/")
+            .dispatch()
+            .into_string()
+            .expect("valid string");
+
+        // Simulate login
+        let creation_response = client
+            .post("/users/login")
+            .header(ContentType::Form)
+            .body("user=Chris&password=test1234")
+            .dispatch();
+        assert_eq!(creation_response.status(), rocket::http::Status::SeeOther);
+
+        // Now we get the dashboard!
+        let recieved_dashboard = client
+            .get("/")
+            .dispatch()
+            .into_string()
+            .expect("valid string");
+
+        assert_ne!(recieved_login_page, recieved_dashboard);
+    }
+
+    #[test]
+    fn test_error_catching_html() {
+        let client = Client::tracked(rocket()).expect("valid client");
+        let response = client.get("/invalid_page").dispatch();
+
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+
+        // Ensure we got a custom error page mentioning "Shelby"
+        let response = response.into_string().expect("valid string");
+        assert!(response.find("Shelby").is_some())
+    }
+
+    #[test]
+    fn test_document_pdf() {
+        let engine = rocket();
+        let example_data = b"%PDF-1.4 example".to_vec();
+        let example = {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+
+            let mut example = crate::backend::document::Document::create_default(
+                &state.database().expect("database connection"),
+            );
+            example.document = example_data.clone();
+            example
+        };
+
+        let client = crate::tests::login(engine);
+        let creation_response = client.post("/documents").json(&example).dispatch();
+        assert_eq!(creation_response.status(), rocket::http::Status::Created);
+
+        // Generate the URL for the PDF
         let pdf_url = format!(
             "{}/pdf",
             creation_response
@@ -840,116 +2772,1970 @@ mod tests {
                 .expect("valid string")
         );
 
-        let response = client.get(pdf_url).dispatch();
-        // Check that the PDF should be viewed in the browser
+        let response = client.get(pdf_url.clone()).dispatch();
+        // Check that the PDF should be viewed in the browser
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("inline")
+        );
+        assert_eq!(response.content_type(), Some(ContentType::PDF));
+        assert_eq!(response.into_bytes().expect("valid bytes"), example_data);
+
+        // The download is forced on request
+        let response = client.get(format!("{}?download=true", pdf_url)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("attachment; filename=\"document-1.pdf\"")
+        );
+    }
+
+    #[test]
+    fn test_document_types_configured() {
+        use crate::backend::document::DocumentType;
+
+        let config = Config::from_env(
+            crate::backend::database::Database::in_memory().expect("valid database"),
+        )
+        .expect("valid config")
+        .with_document_types(vec![DocumentType::Pdf, DocumentType::Png]);
+        let engine = rocket::build()
+            .manage(config)
+            .attach(rocket_dyn_templates::Template::fairing())
+            .mount(
+                "/",
+                rocket::routes![
+                    auth::login,
+                    super::document::add,
+                    super::document::add_frontend
+                ],
+            );
+        let example = {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+            crate::backend::document::Document::create_default(
+                &state.database().expect("database connection"),
+            )
+        };
+
+        let client = crate::tests::login(engine);
+        let mut png = example.clone();
+        png.document = b"\x89PNG\r\n\x1a\nexample".to_vec();
+        let response = client.post("/documents").json(&png).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+
+        let mut gif = example;
+        gif.document = b"GIF89a example".to_vec();
+        let response = client.post("/documents").json(&gif).dispatch();
+        assert_eq!(
+            response.status(),
+            rocket::http::Status::UnsupportedMediaType
+        );
+
+        let form = client
+            .get("/documents/new")
+            .dispatch()
+            .into_string()
+            .expect("valid form");
+        assert!(form.contains(r#"accept=".pdf,application&#x2F;pdf,.png,image&#x2F;png""#));
+    }
+
+    #[test]
+    fn test_document_replace_file() {
+        let engine = rocket();
+        let (identifier, metadata) = {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+            let database = state.database().expect("database connection");
+            let mut example = crate::backend::document::Document::create_default(&database);
+            example.document = b"%PDF-1.4 original".to_vec();
+            example.description = String::from("Invoice");
+            let identifier = example.insert(&database).expect("valid document");
+            let metadata = crate::backend::document::Document::select(&database, identifier)
+                .expect("valid metadata");
+            (identifier, metadata)
+        };
+        let client = crate::tests::login(engine);
+        let url = format!("/documents/{}/file", identifier.raw_index());
+
+        // Only PDF files are accepted ...
+        let response = client.put(&url).body("no pdf").dispatch();
+        assert_eq!(
+            response.status(),
+            rocket::http::Status::UnsupportedMediaType
+        );
+
+        // ... which replace the file only.
+        let response = client.put(&url).body("%PDF-1.7 rescan").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NoContent);
+
+        let database = client
+            .rocket()
+            .state::<Config>()
+            .expect("valid database")
+            .database()
+            .expect("database connection");
+        let replacement = b"%PDF-1.7 rescan".to_vec();
+        assert_eq!(
+            crate::backend::document::Document::select(&database, identifier),
+            Ok(crate::backend::document::Metadata {
+                size_bytes: replacement.len() as u64,
+                ..metadata
+            })
+        );
+        assert_eq!(
+            crate::backend::document::Document::load_into_memory(&database, identifier),
+            Ok(replacement)
+        );
+    }
+
+    #[test]
+    fn test_get_all_in_range() {
+        let engine = rocket();
+        let identifiers = {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+            let database = state.database().expect("database connection");
+            ["2024-01-10", "2024-02-10", "2024-03-10"].map(|recieved| {
+                let mut document = crate::backend::document::Document::create_default(&database);
+                document.recieved = crate::backend::Date::try_from(recieved).expect("valid date");
+                document.insert(&database).expect("valid document")
+            })
+        };
+        let client = crate::tests::login(engine);
+        let select = |query: &str| {
+            let response = client
+                .get(format!("/documents?sort_by=recieved&order=asc&{}", query))
+                .header(ContentType::JSON)
+                .dispatch();
+            assert_eq!(response.status(), rocket::http::Status::Ok);
+            rocket::serde::json::from_str::<Vec<crate::backend::document::Metadata>>(
+                &response.into_string().expect("valid str"),
+            )
+            .expect("valid json")
+            .into_iter()
+            .map(|document| document.identifier)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            select("from=2024-02-01"),
+            vec![identifiers[1], identifiers[2]]
+        );
+        assert_eq!(
+            select("to=2024-02-10"),
+            vec![identifiers[0], identifiers[1]]
+        );
+
+        // The total only counts the documents within the range.
+        let response: serde_json::Value = client
+            .get("/documents?from=2024-02-01&with_count=true")
+            .header(ContentType::JSON)
+            .dispatch()
+            .into_json()
+            .expect("valid json");
+        assert_eq!(response["total"], 2);
+        assert_eq!(response["has_more"], false);
+
+        // Tables without a date and malformed ranges are rejected.
+        for url in [
+            "/groups?from=2024-02-01",
+            "/documents?from=2024-02-31",
+            "/documents?to=tomorrow",
+            "/documents?from=2024-03-01&to=2024-02-01",
+        ] {
+            let response = client.get(url).header(ContentType::JSON).dispatch();
+            assert_eq!(
+                response.status(),
+                rocket::http::Status::BadRequest,
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_get_all_cursor() {
+        let engine = rocket();
+        let identifiers = {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+            let database = state.database().expect("database connection");
+            [(); 5].map(|_| {
+                Person::create_default(&database)
+                    .insert(&database)
+                    .expect("valid person")
+                    .raw_index()
+            })
+        };
+        let client = crate::tests::login(engine);
+        let page = |query: String| {
+            client
+                .get(format!("/persons?{}", query))
+                .header(ContentType::JSON)
+                .dispatch()
+                .into_json::<serde_json::Value>()
+                .expect("valid json")
+        };
+
+        let first = page(format!("after={}&limit=2&with_count=true", identifiers[0]));
+        assert_eq!(first["items"].as_array().map(Vec::len), Some(2));
+        assert_eq!(first["total"], 5);
+        assert_eq!(first["next_cursor"], identifiers[2]);
+
+        // The browser listing continues after the same element.
+        let response = client
+            .get(format!("/persons?after={}&limit=2", identifiers[0]))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let html = response.into_string().expect("valid str");
+        assert!(html.contains(&format!(
+            "&#x2F;persons?after={}&amp;limit=2",
+            identifiers[2]
+        )));
+        assert!(!html.contains("Page 1 of"));
+
+        // Changes to rows before the cursor do not shift the following pages.
+        {
+            let state = client.rocket().state::<Config>().expect("valid database");
+            let database = state.database().expect("database connection");
+            database
+                .connection
+                .execute("UPDATE persons SET id = 0 WHERE id = ?", (identifiers[0],))
+                .expect("valid update");
+        }
+        let second = page(format!("after={}&limit=2", identifiers[2]));
+        assert_eq!(second["items"].as_array().map(Vec::len), Some(2));
+        // The page is full, but nothing follows it.
+        assert_eq!(second["next_cursor"], serde_json::Value::Null);
+        let last = page(format!("after={}&limit=2", identifiers[4]));
+        assert_eq!(last["items"], serde_json::json!([]));
+        assert_eq!(last["next_cursor"], serde_json::Value::Null);
+
+        // A cursor could not be combined with an offset.
+        let response = client
+            .get(format!("/persons?after={}&offset=2", identifiers[0]))
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+    }
+
+    #[test]
+    fn test_document_upload_chunked() {
+        let engine = rocket();
+        let mut metadata = {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+            let example = crate::backend::document::Document::create_default(
+                &state.database().expect("database connection"),
+            );
+            serde_json::to_value(example).expect("valid json")
+        };
+        metadata
+            .as_object_mut()
+            .expect("valid object")
+            .remove("document");
+        let foreign_upload = {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+            let database = state.database().expect("database connection");
+            let other = crate::backend::user::User::create_default(&database)
+                .insert(&database)
+                .expect("valid user");
+            crate::backend::upload::Upload::start(&database, other).expect("valid upload")
+        };
+        let client = crate::tests::login(engine);
+
+        // Uploads of other users could not be accessed.
+        let foreign_url = format!("/documents/uploads/{}", foreign_upload);
+        let response = client.get(&foreign_url).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+        let response = client
+            .patch(format!("{}?offset=0", foreign_url))
+            .body("%PDF-1.4\n")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+
+        let response = client.post("/documents/uploads").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let url = response
+            .headers()
+            .get_one("Location")
+            .expect("valid location")
+            .to_string();
+
+        for (offset, chunk) in [(0, "%PDF-1.4\n"), (9, "%%EOF")] {
+            let response = client
+                .patch(format!("{}?offset={}", url, offset))
+                .body(chunk)
+                .dispatch();
+            assert_eq!(response.status(), rocket::http::Status::Ok);
+        }
+        // A repeated chunk is rejected, as the offset moved on.
+        let response = client
+            .patch(format!("{}?offset=0", url))
+            .body("%PDF-1.4\n")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Conflict);
+        let response = client.get(&url).dispatch();
+        assert_eq!(
+            response.into_string().expect("valid str"),
+            r#"{"length":14}"#
+        );
+
+        let response = client
+            .post(format!("{}/complete", url))
+            .json(&metadata)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let identifier: i64 = response
+            .headers()
+            .get_one("Location")
+            .and_then(|location| location.strip_prefix("/documents/"))
+            .and_then(|identifier| identifier.parse().ok())
+            .expect("valid location");
+
+        let database = client
+            .rocket()
+            .state::<Config>()
+            .expect("valid database")
+            .database()
+            .expect("database connection");
+        assert_eq!(
+            crate::backend::document::Document::load_into_memory(
+                &database,
+                PrimaryKey::from(identifier)
+            ),
+            Ok(b"%PDF-1.4\n%%EOF".to_vec())
+        );
+        drop(database);
+
+        // The upload is gone afterwards.
+        let response = client.get(&url).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+    }
+
+    #[test]
+    fn test_document_replace_file_missing() {
+        let client = crate::tests::login(rocket());
+        let response = client.put("/documents/42/file").body("%PDF-1.7").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+    }
+
+    #[test]
+    fn test_non_public_sort_column_rejected() {
+        let client = crate::tests::login(rocket());
+        let response = client.get("/users?sort_by=id").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        let response = client.get("/users?sort_by=creation_date").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+    }
+
+    #[test]
+    fn test_person_fields() {
+        let engine = rocket();
+        {
+            let state: &State<Config> = State::get(&engine).expect("valid database");
+            let database = state.database().expect("database connection");
+            Person {
+                name: String::from("Max"),
+                email: Some(
+                    crate::backend::Email::try_from("max@example.com").expect("valid address"),
+                ),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person");
+        }
+        let client = crate::tests::login(engine);
+
+        let response = client
+            .get("/persons/1?fields=name,email")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert_eq!(
+            response.into_string().expect("valid str"),
+            r#"{"email":"max@example.com","name":"Max"}"#
+        );
+
+        let response = client
+            .get("/persons?fields=name,email")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(
+            response.into_string().expect("valid str"),
+            r#"[{"email":"max@example.com","name":"Max"}]"#
+        );
+
+        let response = client
+            .get("/persons/1?fields=name,unknown")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+
+        // Fields are checked against the entity instead of its entries ...
+        let response = client
+            .get("/groups?fields=unknown")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        let response = client
+            .get("/groups?fields=description,created_at")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.into_string().expect("valid str"), "[]");
+
+        // ... and repeated ones are kept once.
+        let response = client
+            .get("/persons/1?fields=name,name")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(
+            response.into_string().expect("valid str"),
+            r#"{"name":"Max"}"#
+        );
+    }
+
+    #[test]
+    fn test_person_columns() {
+        let client = crate::tests::login(rocket());
+        let response = client.get("/persons/columns").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+
+        let columns: Vec<rocket::serde::json::Value> =
+            rocket::serde::json::from_str(&response.into_string().expect("valid str"))
+                .expect("valid json");
+        assert!(columns.contains(&rocket::serde::json::json!({"name": "name", "type": "TEXT"})));
+        assert!(!columns
+            .contains(&rocket::serde::json::json!({"name": "birthday", "type": "DATETIME"})));
+    }
+
+    #[test]
+    fn test_person_future_birthday() {
+        let client = crate::tests::login(rocket());
+        let response = client
+            .post("/persons")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Max", "address": "Street 1", "birthday": "3000-01-01"}"#)
+            .dispatch();
+        // Rocket reports well-formed JSON with invalid values as unprocessable.
+        assert_eq!(response.status(), rocket::http::Status::UnprocessableEntity);
+
+        let response = client.get("/persons").header(ContentType::JSON).dispatch();
+        assert_eq!(response.into_string().expect("valid str"), "[]");
+    }
+
+    #[test]
+    fn test_person_invalid_email() {
+        let client = crate::tests::login(rocket());
+        let response = client
+            .post("/persons")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Max", "address": "Street 1", "email": "not-an-email"}"#)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::UnprocessableEntity);
+
+        let response = client
+            .post("/persons")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Max", "address": "Street 1", "email": "max@example.com"}"#)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+
+        let response = client
+            .get("/persons/1?fields=email")
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(
+            response.into_string().expect("valid str"),
+            r#"{"email":"max@example.com"}"#
+        );
+    }
+
+    #[test]
+    fn test_person_name_length() {
+        let client = crate::tests::login(rocket());
+        for (length, status) in [
+            (
+                Config::DEFAULT_MAX_STRING_LENGTH,
+                rocket::http::Status::Created,
+            ),
+            (
+                Config::DEFAULT_MAX_STRING_LENGTH + 1,
+                rocket::http::Status::BadRequest,
+            ),
+        ] {
+            let person = crate::backend::person::Person {
+                name: "ä".repeat(length),
+                ..Default::default()
+            };
+            let response = client.post("/persons").json(&person).dispatch();
+            assert_eq!(response.status(), status);
+        }
+    }
+
+    #[test]
+    fn test_membership_insert() {
+        let engine = rocket();
+        let (person, group) = generate_everything_for_memmbership(&engine);
+        let client = crate::tests::login(engine);
+
+        // Check there is no member before the insertion ...
+        {
+            let state = client.rocket().state::<Config>().expect("valid database");
+            assert_eq!(
+                Membership::find_all_members(
+                    &state.database().expect("database connection"),
+                    group
+                )
+                .unwrap()
+                .len(),
+                0
+            )
+        }
+
+        let creation_response = client
+            .post(format!("/groups/{}/{}", group.0, person.0))
+            .dispatch();
+        assert_eq!(creation_response.status(), rocket::http::Status::Created);
+        let key: (PrimaryKey<Person>, PrimaryKey<Group>) =
+            rocket::serde::json::from_str(&creation_response.into_string().expect("valid str"))
+                .expect("valid key");
+        assert_eq!(key, (person, group));
+
+        // ... and some afterwards.
+        {
+            let memberships = {
+                let state = client.rocket().state::<Config>().expect("valid database");
+                Membership::find_all_members(&state.database().expect("database connection"), group)
+                    .unwrap()
+            };
+            assert_eq!(memberships.len(), 1);
+            assert_eq!(memberships.get(0).unwrap().person, person);
+        }
+    }
+
+    #[test]
+    fn test_membership_insert_twice() {
+        let engine = rocket();
+        let (person, group) = generate_everything_for_memmbership(&engine);
+        let client = crate::tests::login(engine);
+
+        let url = format!("/groups/{}/{}", group.0, person.0);
+        let creation_response = client.post(&url).dispatch();
+        assert_eq!(creation_response.status(), rocket::http::Status::Created);
+
+        // Adding the person again is fine and returns the existing membership ...
+        let response = client.post(&url).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = response.into_string().expect("valid str");
+        assert!(response.contains(&format!("\"/persons/{}\"", person.0)));
+
+        // ... as long as no strict handling is requested.
+        let response = client.post(format!("{}?strict=true", url)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Conflict);
+
+        let state = client.rocket().state::<Config>().expect("valid database");
+        assert_eq!(
+            Membership::find_all_members(&state.database().expect("database connection"), group)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_membership_insert_unauthorized() {
+        let engine = rocket();
+        let (person, group) = generate_everything_for_memmbership(&engine);
+
+        // No login here
+        let client = Client::tracked(engine).expect("valid client");
+        let creation_response = client
+            .post(format!("/groups/{}/{}", person.0, group.0))
+            .dispatch();
+        assert_eq!(
+            creation_response.status(),
+            rocket::http::Status::Unauthorized
+        );
+    }
+
+    #[test]
+    fn test_membership_delete() {
+        let engine = rocket();
+        let (person, group) = generate_everything_for_memmbership(&engine);
+        let client = crate::tests::login(engine);
+
+        // Add a new membership ...
+        {
+            let database = client
+                .rocket()
+                .state::<Config>()
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Membership {
+                person,
+                group,
+                updated: None,
+                comment: None,
+            }
+            .insert(&database)
+            .expect("insertion sucessfull");
+        }
+
+        // A dry run reports the removal without applying it ...
+        let dry_run_response = client
+            .delete(format!("/groups/{}/{}?dry_run=true", group.0, person.0))
+            .dispatch();
+        assert_eq!(dry_run_response.status(), rocket::http::Status::Ok);
+        let changes: serde_json::Value =
+            serde_json::from_str(&dry_run_response.into_string().expect("valid str"))
+                .expect("valid json");
+        assert_eq!(
+            changes,
+            serde_json::json!({ "dry_run": true, "removed": 1 })
+        );
+        {
+            let state = client.rocket().state::<Config>().expect("valid database");
+            assert_eq!(
+                Membership::find_all_members(
+                    &state.database().expect("database connection"),
+                    group
+                )
+                .unwrap()
+                .len(),
+                1
+            );
+        }
+
+        let creation_response = client
+            .delete(format!("/groups/{}/{}", group.0, person.0))
+            .dispatch();
+        assert_eq!(creation_response.status(), rocket::http::Status::Ok);
+        assert_eq!(
+            creation_response.into_string().as_deref(),
+            Some(r#"{"dry_run":false,"removed":1}"#)
+        );
+
+        // ... and check it is missing after delete.
+        {
+            let memberships = {
+                let state = client.rocket().state::<Config>().expect("valid database");
+                Membership::find_all_members(&state.database().expect("database connection"), group)
+                    .unwrap()
+            };
+            assert_eq!(memberships.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_joinable_groups() {
+        use crate::backend::database::Record;
+
+        let engine = rocket();
+        let (person, joined) = generate_everything_for_memmbership(&engine);
+        let available = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Membership {
+                person,
+                group: joined,
+                updated: None,
+                comment: None,
+            }
+            .insert(&database)
+            .expect("valid membership");
+            Group::create_default(&database)
+                .insert(&database)
+                .expect("valid group")
+        };
+        let client = crate::tests::login(engine);
+
+        let response = client
+            .get(format!("/persons/{}/joinable-groups", person.0))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let groups: Vec<Record<Group>> =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        let groups: Vec<_> = groups.into_iter().map(|group| group.identifier).collect();
+        assert_eq!(groups, vec![available]);
+    }
+
+    #[test]
+    fn test_import_persons() {
+        let client = crate::tests::login(rocket());
+        let count_persons = || {
+            let response = client.get("/persons").header(ContentType::JSON).dispatch();
+            serde_json::from_str::<Vec<serde_json::Value>>(
+                &response.into_string().expect("valid str"),
+            )
+            .expect("valid json")
+            .len()
+        };
+
+        let response = client
+            .post("/persons/import")
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"[{{"name": "Jane", "address": "Street 1"}}, {{"name": "{}", "address": "Street 2"}}]"#,
+                "x".repeat(Config::DEFAULT_MAX_STRING_LENGTH + 1)
+            ))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        assert!(response
+            .into_string()
+            .expect("valid str")
+            .contains("person 1 is invalid"));
+        assert_eq!(count_persons(), 0);
+
+        let response = client
+            .post("/persons/import")
+            .header(ContentType::JSON)
+            .body(r#"[{"name": "Jane", "address": "Street 1"}, {"name": "John", "address": "Street 2"}]"#)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let identifiers: Vec<PrimaryKey<Person>> =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        assert_eq!(identifiers.len(), 2);
+        assert_eq!(count_persons(), 2);
+    }
+
+    #[test]
+    fn test_sort_by_recency() {
+        let engine = rocket();
+        let first = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let first = Person {
+                name: String::from("First"),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person");
+            // The timestamps are precise to milliseconds.
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Person {
+                name: String::from("Second"),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person");
+            first
+        };
+        let client = crate::tests::login(engine);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let response = client
+            .put(format!("/persons/{}", first.0))
+            .header(ContentType::JSON)
+            .body(r#"{"name": "First", "address": "Street 1"}"#)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+
+        let load = |uri: &str| {
+            let response = client.get(uri).header(ContentType::JSON).dispatch();
+            assert_eq!(response.status(), rocket::http::Status::Ok);
+            serde_json::from_str::<Vec<serde_json::Value>>(
+                &response.into_string().expect("valid str"),
+            )
+            .expect("valid json")
+        };
+        let names = |persons: Vec<serde_json::Value>| {
+            persons
+                .into_iter()
+                .map(|person| person["name"].as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()
+                .expect("valid names")
+        };
+        assert_eq!(
+            names(load("/persons?sort_by=created_at&order=desc")),
+            vec!["Second", "First"]
+        );
+        assert_eq!(
+            names(load("/persons?sort_by=updated_at&order=desc")),
+            vec!["First", "Second"]
+        );
+
+        let persons = load("/persons?sort_by=created_at&order=asc");
+        assert!(
+            persons[0]["created_at"].as_str().expect("valid timestamp")
+                < persons[0]["updated_at"].as_str().expect("valid timestamp")
+        );
+        assert_eq!(persons[1]["created_at"], persons[1]["updated_at"]);
+    }
+
+    #[test]
+    fn test_archive_person() {
+        let engine = rocket();
+        let identifier = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Person {
+                name: String::from("Archived Person"),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person")
+        };
+        let client = crate::tests::login(engine);
+        let count_persons = |uri: &str| {
+            let response = client.get(uri).header(ContentType::JSON).dispatch();
+            serde_json::from_str::<Vec<serde_json::Value>>(
+                &response.into_string().expect("valid str"),
+            )
+            .expect("valid json")
+            .len()
+        };
+        let get_status = |uri: String| client.get(uri).dispatch().status();
+
+        let archive = format!("/persons/{}/archive", identifier.0);
+        assert_eq!(
+            client.post(&archive).dispatch().status(),
+            rocket::http::Status::NoContent
+        );
+        assert_eq!(count_persons("/persons"), 0);
+        assert_eq!(count_persons("/persons?include_archived=true"), 1);
+
+        // The browser listing applies the same filter.
+        let list_html = |uri: &str| client.get(uri).dispatch().into_string().expect("valid str");
+        assert!(!list_html("/persons").contains("Archived Person"));
+        assert!(list_html("/persons?include_archived=true").contains("Archived Person"));
+        assert!(
+            !list_html("/persons?include_archived=true&filter_column=name&filter_value=Other")
+                .contains("Archived Person")
+        );
+
+        // So does the export.
+        assert!(!list_html("/persons/csv").contains("Archived Person"));
+        assert!(list_html("/persons/csv?include_archived=true").contains("Archived Person"));
+        assert_eq!(
+            get_status(format!("/persons/{}", identifier.0)),
+            rocket::http::Status::NotFound
+        );
+        assert_eq!(
+            get_status(format!("/persons/{}?include_archived=true", identifier.0)),
+            rocket::http::Status::Ok
+        );
+
+        assert_eq!(
+            client.delete(&archive).dispatch().status(),
+            rocket::http::Status::NoContent
+        );
+        assert_eq!(count_persons("/persons"), 1);
+
+        // Groups are not archived, but removed.
+        assert_eq!(
+            client.post("/groups/1/archive").dispatch().status(),
+            rocket::http::Status::NotFound
+        );
+    }
+
+    #[test]
+    fn test_person_vcard() {
+        let engine = rocket();
+        let identifier = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Person {
+                name: String::from("Jane \"Doe\""),
+                email: Some(
+                    crate::backend::Email::try_from("jane@example.com").expect("valid address"),
+                ),
+                ..Default::default()
+            }
+            .insert(&database)
+            .expect("valid person")
+        };
+        let client = crate::tests::login(engine);
+
+        let response = client
+            .get(format!("/persons/{}/vcard", identifier.0))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert_eq!(
+            response.content_type(),
+            Some(rocket::http::ContentType::new("text", "vcard"))
+        );
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("attachment; filename=\"Jane Doe.vcf\"")
+        );
+        let vcard = response.into_string().expect("valid str");
+        assert!(vcard.starts_with("BEGIN:VCARD\r\n"));
+        assert!(vcard.contains("EMAIL;TYPE=INTERNET:jane@example.com\r\n"));
+
+        let response = client.get("/persons/vcard").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let vcards = response.into_string().expect("valid str");
+        assert!(vcards.contains(&vcard));
+        assert_eq!(
+            vcards.matches("BEGIN:VCARD").count(),
+            vcards.matches("END:VCARD").count()
+        );
+
+        let response = client
+            .get(format!("/persons/{}/vcard", identifier.0 + 1000))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+
+        // Archived persons are hidden unless requested explicitly.
+        client
+            .post(format!("/persons/{}/archive", identifier.0))
+            .dispatch();
+        let status = |uri: String| client.get(uri).dispatch().status();
+        assert_eq!(
+            status(format!("/persons/{}/vcard", identifier.0)),
+            rocket::http::Status::NotFound
+        );
+        assert_eq!(
+            status(format!(
+                "/persons/{}/vcard?include_archived=true",
+                identifier.0
+            )),
+            rocket::http::Status::Ok
+        );
+        let vcards = |uri: &str| client.get(uri).dispatch().into_string().expect("valid str");
+        assert!(!vcards("/persons/vcard").contains(&vcard));
+        assert!(vcards("/persons/vcard?include_archived=true").contains(&vcard));
+    }
+
+    #[test]
+    fn test_search_persons() {
+        use crate::backend::database::Record;
+
+        let engine = rocket();
+        {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            for name in ["Jane Doe", "John Doe", "Max Mustermann"] {
+                Person {
+                    name: String::from(name),
+                    ..Default::default()
+                }
+                .insert(&database)
+                .expect("valid person");
+            }
+        }
+        let client = crate::tests::login(engine);
+
+        let search = |uri: &str| {
+            let response = client.get(uri).dispatch();
+            assert_eq!(response.status(), rocket::http::Status::Ok);
+            let persons: Vec<Record<Person>> =
+                serde_json::from_str(&response.into_string().expect("valid str"))
+                    .expect("valid json");
+            persons
+                .into_iter()
+                .map(|person| person.value.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            search("/persons/search?query=doe"),
+            vec!["John Doe", "Jane Doe"]
+        );
+        assert_eq!(
+            search("/persons/search?query=doe&order=asc&limit=1&offset=1"),
+            vec!["John Doe"]
+        );
+        assert_eq!(search("/persons/search?query=%25"), Vec::<String>::new());
+
+        // Archived persons are only found on request.
+        client.post("/persons/2/archive").dispatch();
+        assert_eq!(search("/persons/search?query=doe"), vec!["Jane Doe"]);
+        assert_eq!(
+            search("/persons/search?query=doe&include_archived=true"),
+            vec!["John Doe", "Jane Doe"]
+        );
+
+        for uri in ["/persons/search", "/persons/search?query="] {
+            assert_eq!(
+                client.get(uri).dispatch().status(),
+                rocket::http::Status::BadRequest
+            );
+        }
+    }
+
+    #[test]
+    fn test_group_delete() {
+        let engine = rocket();
+        let (person, group) = generate_everything_for_memmbership(&engine);
+        let empty = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Membership {
+                person,
+                group,
+                updated: None,
+                comment: None,
+            }
+            .insert(&database)
+            .expect("valid membership");
+            Group::create_default(&database)
+                .insert(&database)
+                .expect("valid group")
+        };
+        let client = crate::tests::login(engine);
+
+        // An empty group is removed right away ...
+        let response = client.delete(format!("/groups/{}", empty.0)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = client.delete(format!("/groups/{}", empty.0)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+
+        // ... while a group with members is kept ...
+        let response = client.delete(format!("/groups/{}", group.0)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Conflict);
+        {
+            let state = client.rocket().state::<Config>().expect("valid database");
+            assert_eq!(
+                Membership::find_all_members(
+                    &state.database().expect("database connection"),
+                    group
+                )
+                .unwrap()
+                .len(),
+                1
+            );
+        }
+
+        // ... unless it is forced, which could be tried first ...
+        let response = client
+            .delete(format!("/groups/{}?force=true&dry_run=true", group.0))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert_eq!(
+            response.into_string().as_deref(),
+            Some(r#"{"dry_run":true,"removed":1,"memberships":1}"#)
+        );
+        {
+            let state = client.rocket().state::<Config>().expect("valid database");
+            assert_eq!(
+                Membership::find_all_members(
+                    &state.database().expect("database connection"),
+                    group
+                )
+                .unwrap()
+                .len(),
+                1
+            );
+        }
+
+        // ... and then applied.
+        let response = client
+            .delete(format!("/groups/{}?force=true", group.0))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let state = client.rocket().state::<Config>().expect("valid database");
+        assert_eq!(
+            Membership::find_all_memberships(
+                &state.database().expect("database connection"),
+                person
+            ),
+            Ok(vec![])
+        );
+        assert_eq!(
+            Group::try_select(
+                &state.database().expect("database connection"),
+                group.raw_index()
+            ),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    fn test_memberships() {
+        let engine = rocket();
+        let (person, group) = generate_everything_for_memmbership(&engine);
+        let client = crate::tests::login(engine);
+        let membership = serde_json::json!({
+            "person": person,
+            "group": group,
+            "comment": "Treasurer"
+        });
+
+        // A membership is created once ...
+        let response = client.post("/memberships").json(&membership).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let url = format!("/memberships/{}/{}", person.0, group.0);
+        assert_eq!(response.headers().get_one("Location"), Some(url.as_str()));
+        let response = client.post("/memberships").json(&membership).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+
+        // ... listed for the person ...
+        let response = client
+            .get(format!("/persons/{}/memberships", person.0))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let memberships: Vec<Membership> =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        assert_eq!(
+            memberships,
+            vec![Membership {
+                person,
+                group,
+                updated: None,
+                comment: Some(String::from("Treasurer"))
+            }]
+        );
+        let response = client.get("/persons/42/memberships").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+
+        // ... and removed once, which could be tried first.
+        let response = client.delete(format!("{}?dry_run=true", url)).dispatch();
+        assert_eq!(
+            response.into_string().as_deref(),
+            Some(r#"{"dry_run":true,"removed":1}"#)
+        );
+        let response = client.delete(&url).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = client.delete(&url).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+    }
+
+    #[test]
+    fn test_membership_delete_unauthorized() {
+        let engine = rocket();
+        let (person, group) = generate_everything_for_memmbership(&engine);
+
+        // No login here
+        let client = Client::tracked(engine).expect("valid client");
+        let creation_response = client
+            .delete(format!("/groups/{}/{}", person.0, group.0))
+            .dispatch();
+        assert_eq!(
+            creation_response.status(),
+            rocket::http::Status::Unauthorized
+        );
+    }
+
+    #[test]
+    fn test_entries_by_reference() {
+        use crate::backend::{accounting::Entry, database::Record};
+
+        let engine = rocket();
+        let entry = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Entry::create_default(&database)
+                .insert(&database)
+                .expect("valid entry");
+
+            let mut entry = Entry::create_default(&database);
+            entry.reference_number = Some(String::from("RF-2024-001"));
+            entry.insert(&database).expect("valid entry")
+        };
+        let client = crate::tests::login(engine);
+
+        let response = client.get("/entries?reference=RF-2024-001").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = response.into_string().expect("valid str");
+        let entries: Vec<Record<Entry>> =
+            rocket::serde::json::from_str(&response).expect("valid json");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].identifier, entry);
+    }
+
+    #[test]
+    fn test_monthly_cost_center_totals() {
+        use crate::backend::{
+            accounting::{Amount, Currency, Entry},
+            document::Document,
+            Date,
+        };
+
+        // A fixed date keeps the test independent of the clock, i.e. around the turn of a month.
+        let engine = rocket();
+        let cost_center = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let mut evidence = Document::create_default(&database);
+            evidence.processed = Date::try_from("2024-03-15").expect("valid date");
+
+            let mut entry = Entry::create_default(&database);
+            entry.evidence = evidence.insert(&database).expect("valid evidence");
+            entry.insert(&database).expect("valid entry");
+            entry.cost_center
+        };
+        let client = crate::tests::login(engine);
+
+        let response = client
+            .get(format!(
+                "/cost_centers/{}/monthly?year=2024",
+                cost_center.raw_index()
+            ))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = response.into_string().expect("valid str");
+        let totals: Vec<(u32, Currency, Amount)> =
+            rocket::serde::json::from_str(&response).expect("valid json");
+        assert_eq!(totals, vec![(3, Currency::Eur, Amount::from(32))]);
+    }
+
+    #[test]
+    fn test_merge_persons() {
+        let engine = rocket();
+        let (keep, duplicate) = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            (
+                Person::default().insert(&database).expect("valid person"),
+                Person::default().insert(&database).expect("valid person"),
+            )
+        };
+        let client = crate::tests::login(engine);
+
+        let url = |duplicate: PrimaryKey<Person>| {
+            format!(
+                "/persons/{}/merge/{}",
+                keep.raw_index(),
+                duplicate.raw_index()
+            )
+        };
+        let response = client
+            .post(format!("{}?dry_run=true", url(duplicate)))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let changes: serde_json::Value =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        assert_eq!(changes["dry_run"], true);
         assert_eq!(
-            response.headers().get_one("Content-Disposition"),
-            Some("inline")
+            changes["removed"],
+            serde_json::to_value(duplicate).expect("valid json")
         );
-        assert_eq!(response.content_type(), Some(ContentType::PDF));
-        assert_eq!(response.into_bytes().expect("valid bytes"), example_data);
+
+        let response = client.post(url(duplicate)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = client.post(url(duplicate)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+        let response = client.post(url(keep)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
     }
 
     #[test]
-    fn test_membership_insert() {
+    fn test_entry_account_allowed_for_cost_center() {
+        use crate::backend::accounting::{Account, Entry};
+
         let engine = rocket();
-        let (person, group) = generate_everything_for_memmbership(&engine);
+        let (allowed, disallowed) = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let allowed = Entry::create_default(&database);
+            let mut disallowed = allowed.clone();
+            disallowed.account = Account::create_default(&database)
+                .insert(&database)
+                .expect("valid account");
+            (allowed, disallowed)
+        };
         let client = crate::tests::login(engine);
 
-        // Check there is no member before the insertion ...
-        {
-            let state = client.rocket().state::<Config>().expect("valid database");
-            assert_eq!(
-                Membership::find_all_members(&state.database(), group)
-                    .unwrap()
-                    .len(),
-                0
-            )
-        }
+        let response = client
+            .post(format!(
+                "/cost_centers/{}/accounts/{}",
+                allowed.cost_center.raw_index(),
+                allowed.account.raw_index()
+            ))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
 
-        let creation_response = client
-            .post(format!("/groups/{}/{}", group.0, person.0))
+        let response = client.post("/entries").json(&allowed).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let response = client
+            .post("/entries")
+            .header(rocket::http::ContentType::JSON)
+            .json(&disallowed)
             .dispatch();
-        assert_eq!(creation_response.status(), rocket::http::Status::Created);
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        assert!(response
+            .into_string()
+            .expect("valid str")
+            .contains("not allowed for the cost center"));
 
-        // ... and some afterwards.
-        {
-            let memberships = {
-                let state = client.rocket().state::<Config>().expect("valid database");
-                Membership::find_all_members(&state.database(), group).unwrap()
-            };
-            assert_eq!(memberships.len(), 1);
-            assert_eq!(memberships.get(0).unwrap().person, person);
-        }
+        // Disallowing the account again could be tried first.
+        let url = format!(
+            "/cost_centers/{}/accounts/{}",
+            allowed.cost_center.raw_index(),
+            allowed.account.raw_index()
+        );
+        let response = client.delete(format!("{}?dry_run=true", url)).dispatch();
+        assert_eq!(
+            response.into_string().as_deref(),
+            Some(r#"{"dry_run":true,"removed":1}"#)
+        );
+        assert_eq!(
+            client.delete(&url).dispatch().status(),
+            rocket::http::Status::Ok
+        );
+        assert_eq!(
+            client.delete(&url).dispatch().status(),
+            rocket::http::Status::NotFound
+        );
     }
 
     #[test]
-    fn test_membership_insert_unauthorized() {
+    fn test_entry_allocations() {
+        use crate::backend::accounting::{CostCenter, Entry};
+
         let engine = rocket();
-        let (person, group) = generate_everything_for_memmbership(&engine);
+        let (entry, cost_center, other_cost_center) = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let entry = Entry::create_default(&database);
+            let other_cost_center = CostCenter::default()
+                .insert(&database)
+                .expect("valid cost center");
+            (
+                entry.clone().insert(&database).expect("valid entry"),
+                entry.cost_center,
+                other_cost_center,
+            )
+        };
+        let client = crate::tests::login(engine);
+        let url = format!("/entries/{}/allocations", entry.raw_index());
 
-        // No login here
-        let client = Client::tracked(engine).expect("valid client");
-        let creation_response = client
-            .post(format!("/groups/{}/{}", person.0, group.0))
+        let response = client
+            .put(&url)
+            .json(&serde_json::json!([
+                { "cost_center": cost_center, "amount": "20.00" },
+                { "cost_center": other_cost_center, "amount": "10.00" }
+            ]))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        assert!(response
+            .into_string()
+            .expect("valid str")
+            .contains("must sum up to 32.00"));
+
+        let response = client
+            .put(&url)
+            .json(&serde_json::json!([
+                { "cost_center": cost_center, "amount": "20.00" },
+                { "cost_center": other_cost_center, "amount": "12.00" }
+            ]))
             .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NoContent);
+
+        let response = client.get(&url).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
         assert_eq!(
-            creation_response.status(),
-            rocket::http::Status::Unauthorized
+            response
+                .into_json::<Vec<serde_json::Value>>()
+                .map(|value| value.len()),
+            Some(2)
         );
+
+        let response = client
+            .put("/entries/42/allocations")
+            .json(&serde_json::json!([]))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
     }
 
     #[test]
-    fn test_membership_delete() {
+    fn test_move_cost_center() {
+        use crate::backend::accounting::CostCenter;
+
         let engine = rocket();
-        let (person, group) = generate_everything_for_memmbership(&engine);
+        let (department, team) = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            (
+                CostCenter::default()
+                    .insert(&database)
+                    .expect("valid cost center"),
+                CostCenter::default()
+                    .insert(&database)
+                    .expect("valid cost center"),
+            )
+        };
         let client = crate::tests::login(engine);
 
-        // Add a new membership ...
-        {
-            let database = client
-                .rocket()
-                .state::<Config>()
+        let response = client
+            .put(format!("/cost_centers/{}/parent", team.raw_index()))
+            .json(&department)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NoContent);
+
+        let response = client
+            .put(format!("/cost_centers/{}/parent", department.raw_index()))
+            .json(&team)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+
+        let response = client
+            .put("/cost_centers/42/parent")
+            .json(&serde_json::Value::Null)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+    }
+
+    #[test]
+    fn test_delete_referenced() {
+        use crate::backend::document::Document;
+
+        let engine = rocket();
+        let (person, document) = {
+            let database = State::<Config>::get(&engine)
                 .expect("valid database")
-                .database();
-            Membership {
-                person,
-                group,
-                updated: None,
-                comment: None,
+                .database()
+                .expect("database connection");
+            let document = Document::create_default(&database);
+            let person = document.from_person;
+            (person, document.insert(&database).expect("valid document"))
+        };
+        let client = crate::tests::login(engine);
+
+        // The person is kept as long as the document refers to it ...
+        let response = client
+            .delete(format!("/persons/{}", person.raw_index()))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+
+        // ... but could be removed afterwards.
+        let response = client
+            .delete(format!("/documents/{}", document.raw_index()))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = client
+            .delete(format!("/persons/{}", person.raw_index()))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+    }
+
+    #[test]
+    fn test_update_cost_center_cycle() {
+        use crate::backend::accounting::CostCenter;
+
+        let engine = rocket();
+        let (department, team) = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let department = CostCenter::default()
+                .insert(&database)
+                .expect("valid cost center");
+            let team = CostCenter {
+                description: String::from("Team"),
+                parent: Some(department),
             }
             .insert(&database)
-            .expect("insertion sucessfull");
-        }
+            .expect("valid cost center");
+            (department, team)
+        };
+        let client = crate::tests::login(engine);
 
-        let creation_response = client
-            .delete(format!("/groups/{}/{}", group.0, person.0))
+        let response = client
+            .put(format!("/cost_centers/{}", department.raw_index()))
+            .json(&CostCenter {
+                description: String::from("Department"),
+                parent: Some(team),
+            })
             .dispatch();
-        assert_eq!(creation_response.status(), rocket::http::Status::NoContent);
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+    }
 
-        // ... and check it is missing after delete.
+    #[test]
+    fn test_update_constraint_violation() {
+        use crate::backend::accounting::Budget;
+
+        let engine = rocket();
+        let (budget, mut value) = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let value = Budget::create_default(&database);
+            (value.insert(&database).expect("valid budget"), value)
+        };
+        let client = crate::tests::login(engine);
+
+        value.cost_center = PrimaryKey::from(42);
+        let response = client
+            .put(format!("/budgets/{}", budget.raw_index()))
+            .json(&value)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+    }
+
+    #[test]
+    fn test_update_user_password() {
+        use crate::backend::user::User;
+
+        let (client, user) = crate::tests::login_with_callback(rocket(), |database| {
+            User::select_by_name(database, "Chris")
+                .expect("valid query")
+                .expect("user exists")
+                .identifier
+        });
+        let url = format!("/users/{}", user.raw_index());
+        let update = |username: &str, password: Option<&str>| {
+            serde_json::json!({
+                "username": username,
+                "password": password,
+                "active": true,
+                "creation_date": crate::backend::Date::today(),
+                "related_to": null,
+                "is_admin": true
+            })
+        };
+        let password_hash = || {
+            let state = client.rocket().state::<Config>().expect("valid database");
+            User::select_record(&state.database().expect("database connection"), user)
+                .expect("valid query")
+                .expect("user exists")
+                .into_inner()
+                .password_hash
+        };
+
+        // Without a new password, the existing one is kept ...
+        let response = client.put(&url).json(&update("Chris", None)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert!(password_hash().matches("Chris", "test1234"));
+
+        // ... which is not possible for a new name.
+        let response = client.put(&url).json(&update("Max", None)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        let response = client
+            .put(&url)
+            .json(&update("Max", Some("secret")))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert!(password_hash().matches("Max", "secret"));
+    }
+
+    #[test]
+    fn test_list_not_modified() {
+        let engine = rocket();
         {
-            let memberships = {
-                let state = client.rocket().state::<Config>().expect("valid database");
-                Membership::find_all_members(&state.database(), group).unwrap()
-            };
-            assert_eq!(memberships.len(), 0);
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Person::default().insert(&database).expect("valid person");
+        }
+        let client = crate::tests::login(engine);
+
+        let response = client
+            .get("/persons")
+            .header(rocket::http::ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let last_modified = response
+            .headers()
+            .get_one("Last-Modified")
+            .expect("last modification available")
+            .to_string();
+
+        let response = client
+            .get("/persons")
+            .header(rocket::http::ContentType::JSON)
+            .header(rocket::http::Header::new(
+                "If-Modified-Since",
+                last_modified.clone(),
+            ))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotModified);
+        assert_eq!(
+            response.headers().get_one("Last-Modified"),
+            Some(last_modified.as_str())
+        );
+
+        let response = client
+            .get("/persons")
+            .header(rocket::http::ContentType::JSON)
+            .header(rocket::http::Header::new(
+                "If-Modified-Since",
+                "Sun, 06 Nov 1994 08:49:37 GMT",
+            ))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+
+        // The entity tag notices changes within the same second, too.
+        let entity_tag = response
+            .headers()
+            .get_one("ETag")
+            .expect("entity tag available")
+            .to_string();
+        let if_none_match = |entity_tag: &str| {
+            client
+                .get("/persons")
+                .header(rocket::http::ContentType::JSON)
+                .header(rocket::http::Header::new(
+                    "If-None-Match",
+                    entity_tag.to_string(),
+                ))
+                .dispatch()
+                .status()
+        };
+        assert_eq!(
+            if_none_match(&entity_tag),
+            rocket::http::Status::NotModified
+        );
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        Person::default()
+            .insert(
+                &client
+                    .rocket()
+                    .state::<Config>()
+                    .expect("valid database")
+                    .database()
+                    .expect("database connection"),
+            )
+            .expect("valid person");
+        assert_eq!(if_none_match(&entity_tag), rocket::http::Status::Ok);
+    }
+
+    #[test]
+    fn test_backup() {
+        let client = crate::tests::login(rocket());
+        let response = client.get("/admin/backup").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert_eq!(
+            response.content_type(),
+            Some(rocket::http::ContentType::Binary)
+        );
+        let content = response.into_bytes().expect("valid bytes");
+        assert!(content.starts_with(b"SQLite format 3\0"));
+
+        let client = Client::tracked(rocket()).expect("valid client");
+        let response = client.get("/admin/backup").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_admin_routes() {
+        use crate::backend::{database::Updatable, user::User};
+
+        let (client, _) = crate::tests::login_with_callback(rocket(), |database| {
+            let mut user = User::select_by_name(database, "Chris")
+                .expect("valid query")
+                .expect("existing user");
+            user.value.is_admin = false;
+            user.value
+                .update(database, user.identifier)
+                .expect("valid update");
+        });
+        let anonymous = Client::tracked(rocket()).expect("valid client");
+        for url in [
+            "/admin/orphans",
+            "/admin/integrity",
+            "/admin/export.zip",
+            "/admin/backup",
+        ] {
+            let response = client.get(url).dispatch();
+            assert_eq!(
+                response.status(),
+                rocket::http::Status::Forbidden,
+                "{}",
+                url
+            );
+            let response = anonymous.get(url).dispatch();
+            assert_eq!(
+                response.status(),
+                rocket::http::Status::Unauthorized,
+                "{}",
+                url
+            );
         }
     }
 
     #[test]
-    fn test_membership_delete_unauthorized() {
+    fn test_audit_log() {
+        use crate::backend::{audit::AuditEntry, database::Record};
+
+        let (client, user) = crate::tests::login_with_callback(rocket(), |database| {
+            database
+                .connection
+                .query_row("SELECT MAX(id) FROM users", (), |row| row.get::<_, i64>(0))
+                .expect("valid user")
+        });
+        let response = client
+            .post("/persons")
+            .header(ContentType::JSON)
+            .body(r#"{"name": "Max", "address": "Street 1"}"#)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let person: i64 = response
+            .headers()
+            .get_one("Location")
+            .and_then(|location| location.strip_prefix("/persons/"))
+            .expect("valid location")
+            .parse()
+            .expect("valid id");
+
+        let response = client.get("/audit").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let entries: Vec<Record<AuditEntry>> =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value.user, PrimaryKey::from(user));
+        assert_eq!(entries[0].value.table_name, "persons");
+        assert_eq!(entries[0].value.row_id, person);
+        assert_eq!(entries[0].value.action, AuditEntry::INSERT);
+    }
+
+    #[test]
+    fn test_audit_log_filtered() {
+        use crate::backend::{audit::AuditEntry, database::Record, user::User};
+
+        let (client, (chris, max)) = crate::tests::login_with_callback(rocket(), |database| {
+            let chris = User::select_by_name(database, "Chris")
+                .expect("valid query")
+                .expect("existing user")
+                .identifier;
+            let mut max = User::create_default(database);
+            max.username = String::from("Max");
+            let max = max.insert(database).expect("valid user");
+            AuditEntry::record(database, chris, "persons", 1, AuditEntry::INSERT)
+                .expect("valid record");
+            AuditEntry::record(database, max, "persons", 2, AuditEntry::UPDATE)
+                .expect("valid record");
+            AuditEntry::record(database, max, "groups", 3, AuditEntry::DELETE)
+                .expect("valid record");
+            (chris, max)
+        });
+        let row_ids = |url: String| {
+            let response = client.get(url).dispatch();
+            assert_eq!(response.status(), rocket::http::Status::Ok);
+            let entries: Vec<Record<AuditEntry>> =
+                serde_json::from_str(&response.into_string().expect("valid str"))
+                    .expect("valid json");
+            entries
+                .into_iter()
+                .map(|entry| entry.value.row_id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            row_ids(format!("/audit?user={}", chris.raw_index())),
+            vec![1]
+        );
+        assert_eq!(
+            row_ids(format!("/audit?user={}", max.raw_index())),
+            vec![3, 2]
+        );
+        assert_eq!(
+            row_ids(format!("/audit?user={}&table=persons", max.raw_index())),
+            vec![2]
+        );
+        let today = crate::backend::FutureDate::today();
+        assert_eq!(
+            row_ids(format!("/audit?from={}&to={}", today, today)),
+            vec![3, 2, 1]
+        );
+        assert!(row_ids(String::from("/audit?to=2000-01-01")).is_empty());
+
+        let response = client
+            .get("/audit?from=2024-02-01&to=2024-01-01")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        let response = client.get("/audit?from=yesterday").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::BadRequest);
+    }
+
+    #[test]
+    fn test_audit_log_custom_routes() {
+        use crate::backend::{audit::AuditEntry, database::Record};
+
         let engine = rocket();
         let (person, group) = generate_everything_for_memmbership(&engine);
+        let client = crate::tests::login(engine);
 
-        // No login here
-        let client = Client::tracked(engine).expect("valid client");
-        let creation_response = client
-            .delete(format!("/groups/{}/{}", person.0, group.0))
+        let response = client
+            .post("/persons/import")
+            .json(&serde_json::json!([{"name": "Max", "address": "Street 1"}]))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let response = client
+            .post("/memberships")
+            .json(&serde_json::json!({ "person": person, "group": group }))
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Created);
+        let response = client
+            .delete(format!("/memberships/{}/{}", person.0, group.0))
             .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response = client
+            .post("/users/password")
+            .header(ContentType::Form)
+            .body("password=test1234&new_password=changed5678")
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NoContent);
+
+        let response = client.get("/audit").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let entries: Vec<Record<AuditEntry>> =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        let actions: Vec<_> = entries
+            .iter()
+            .rev()
+            .map(|entry| {
+                (
+                    entry.value.table_name.as_str(),
+                    entry.value.action.as_str(),
+                    entry.value.details.as_deref(),
+                )
+            })
+            .collect();
+        let membership = format!("membership of person {}", person.0);
         assert_eq!(
-            creation_response.status(),
-            rocket::http::Status::Unauthorized
+            actions,
+            vec![
+                ("persons", AuditEntry::INSERT, None),
+                ("groups", AuditEntry::INSERT, Some(membership.as_str())),
+                ("groups", AuditEntry::DELETE, Some(membership.as_str())),
+                ("users", AuditEntry::UPDATE, Some("changed the password")),
+            ]
         );
     }
+
+    #[test]
+    fn test_check_integrity() {
+        let client = crate::tests::login(rocket());
+        let response = client.get("/admin/integrity").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        assert_eq!(response.into_string().expect("valid str"), r#""ok""#);
+
+        let client = Client::tracked(rocket()).expect("valid client");
+        let response = client.get("/admin/integrity").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_budget_vs_actual() {
+        use crate::backend::accounting::{Budget, Entry};
+        use chrono::Datelike;
+
+        let year = chrono::Utc::now().year() as u32;
+        let engine = rocket();
+        let cost_center = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let entry = Entry::create_default(&database);
+            entry.insert(&database).expect("valid entry");
+            Budget {
+                cost_center: entry.cost_center,
+                year,
+                target: 100i64.into(),
+                currency: Default::default(),
+            }
+            .insert(&database)
+            .expect("valid budget");
+            entry.cost_center
+        };
+        let client = crate::tests::login(engine);
+
+        let url = |year| {
+            format!(
+                "/cost_centers/{}/budget-vs-actual?year={}",
+                cost_center.raw_index(),
+                year
+            )
+        };
+        let response = client.get(url(year)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response: rocket::serde::json::Value =
+            rocket::serde::json::from_str(&response.into_string().expect("valid str"))
+                .expect("valid json");
+        assert_eq!(response["currency"], "EUR");
+        assert_eq!(response["actual"], "32.00");
+        assert_eq!(response["variance"], "68.00");
+        assert_eq!(response["utilization"], "32.00%");
+
+        let response = client.get(url(year - 1)).dispatch();
+        assert_eq!(response.status(), rocket::http::Status::NotFound);
+    }
+
+    #[test]
+    fn test_entries_expanded() {
+        use crate::backend::accounting::Entry;
+
+        let engine = rocket();
+        {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            Entry::create_default(&database)
+                .insert(&database)
+                .expect("valid entry");
+        }
+        let client = crate::tests::login(engine);
+
+        let response = client.get("/entries?expand=account").dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let response: rocket::serde::json::Value =
+            rocket::serde::json::from_str(&response.into_string().expect("valid str"))
+                .expect("valid json");
+        assert_eq!(response[0]["account"]["name"], "Example account");
+        assert_eq!(response[0]["account"]["id"], "/accounts/1");
+        assert!(response[0]["cost_center"].is_string());
+    }
+
+    #[test]
+    fn test_filter_column() {
+        use crate::backend::{accounting::Account, database::Record};
+
+        let engine = rocket();
+        let (category, expected) = {
+            let database = State::<Config>::get(&engine)
+                .expect("valid database")
+                .database()
+                .expect("database connection");
+            let first = Account::create_default(&database);
+            let category = first.category;
+            let first = first.insert(&database).expect("valid account");
+            let mut second = Account::create_default(&database);
+            second.code = 1801;
+            second.insert(&database).expect("valid account");
+            (category, first)
+        };
+        let client = crate::tests::login(engine);
+
+        let response = client
+            .get(format!(
+                "/accounts?filter_column=category&filter_value={}",
+                category.raw_index()
+            ))
+            .header(ContentType::JSON)
+            .dispatch();
+        assert_eq!(response.status(), rocket::http::Status::Ok);
+        let accounts: Vec<Record<Account>> =
+            serde_json::from_str(&response.into_string().expect("valid str")).expect("valid json");
+        let accounts: Vec<_> = accounts
+            .into_iter()
+            .map(|account| account.identifier)
+            .collect();
+        assert_eq!(accounts, vec![expected]);
+
+        for uri in [
+            "/accounts?filter_column=unknown&filter_value=1",
+            "/accounts?filter_column=category",
+            "/accounts?filter_value=1",
+        ] {
+            let response = client.get(uri).header(ContentType::JSON).dispatch();
+            assert_eq!(response.status(), rocket::http::Status::BadRequest);
+        }
+    }
 }
@@ -0,0 +1,51 @@
+use rocket::{
+    http::{ContentType, Header},
+    response::{self, Responder},
+    Request, Response,
+};
+
+use crate::backend::database::Database;
+
+/// A snapshot of the whole database which is written into a temporary file and streamed to the client afterwards.
+#[derive(Debug)]
+pub struct BackupOutput {
+    file: std::fs::File,
+}
+
+impl BackupOutput {
+    /// Copy the database. The caller holds the connection, so no other change is made meanwhile.
+    pub fn create(database: &Database) -> Result<Self, crate::Error> {
+        let mut random = [0u8; 8];
+        getrandom::getrandom(&mut random).or(Err(rocket::http::Status::InternalServerError))?;
+        let path = std::env::temp_dir().join(format!(
+            "shelby-{}.db",
+            random.map(|value| format!("{:02x}", value)).concat()
+        ));
+
+        let result = database
+            .backup_to(&path)
+            .map_err(crate::Error::from)
+            .and_then(|_| Ok(std::fs::File::open(&path)?));
+        // The open handle keeps the content available after the file is removed.
+        for suffix in ["", "-shm", "-wal"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+        Ok(BackupOutput { file: result? })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for BackupOutput {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::Binary)
+            .header(Header::new(
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"shelby-{}.db\"",
+                    crate::backend::Date::today()
+                ),
+            ))
+            .streamed_body(rocket::tokio::fs::File::from_std(self.file))
+            .ok()
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::{DateTime, SubsecRound, Utc};
+use rocket::{
+    http::{Header, Status},
+    request::{FromRequest, Outcome},
+    response::{self, Responder},
+    Request, Response,
+};
+
+/// The format of dates in HTTP headers like "Last-Modified".
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// The version of a resource a client fetched before. Invalid dates are ignored.
+/// The "If-Modified-Since" header is limited to seconds, while the entity tags of "If-None-Match" are exact and take precedence.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IfModifiedSince {
+    fetched: Option<DateTime<Utc>>,
+    entity_tags: Option<String>,
+}
+
+impl IfModifiedSince {
+    /// Generate the response only if the resource changed since the client fetched it.
+    /// Without any known modification, the response is always generated.
+    pub fn respond<R, E>(
+        &self,
+        last_modified: Option<DateTime<Utc>>,
+        response: impl FnOnce() -> Result<R, E>,
+    ) -> Result<Conditional<R>, E> {
+        let unchanged = match (last_modified, &self.entity_tags, self.fetched) {
+            (Some(last_modified), Some(entity_tags), _) => {
+                let current = entity_tag(last_modified);
+                entity_tags.split(',').map(str::trim).any(|entity_tag| {
+                    entity_tag == "*" || entity_tag.trim_start_matches("W/") == current
+                })
+            }
+            (Some(last_modified), None, Some(fetched)) => last_modified.trunc_subsecs(0) <= fetched,
+            _ => false,
+        };
+        let response = match unchanged {
+            true => None,
+            false => Some(response()?),
+        };
+        Ok(Conditional {
+            last_modified,
+            response,
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfModifiedSince {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let headers = request.headers();
+        Outcome::Success(IfModifiedSince {
+            fetched: headers
+                .get_one("If-Modified-Since")
+                .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+                .map(|value| value.with_timezone(&Utc)),
+            entity_tags: headers.get_one("If-None-Match").map(String::from),
+        })
+    }
+}
+
+/// A response which is replaced by "304 Not Modified" if the client has the current version already.
+#[derive(Debug)]
+pub struct Conditional<R> {
+    last_modified: Option<DateTime<Utc>>,
+    response: Option<R>,
+}
+
+impl<'r, 'o: 'r, R: Responder<'r, 'o>> Responder<'r, 'o> for Conditional<R> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'o> {
+        let mut response = match self.response {
+            Some(response) => response.respond_to(request)?,
+            None => Response::build().status(Status::NotModified).finalize(),
+        };
+        if let Some(last_modified) = self.last_modified {
+            response.set_header(Header::new(
+                "Last-Modified",
+                last_modified.format(HTTP_DATE_FORMAT).to_string(),
+            ));
+            response.set_header(Header::new("ETag", entity_tag(last_modified)));
+        }
+        Ok(response)
+    }
+}
+
+/// The entity tag of a resource, which is derived from its last modification in milliseconds.
+fn entity_tag(last_modified: DateTime<Utc>) -> String {
+    format!("\"{}\"", last_modified.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entity_tag, IfModifiedSince, HTTP_DATE_FORMAT};
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[test]
+    fn test_respond() {
+        let fetched = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let if_modified_since = IfModifiedSince {
+            fetched: Some(fetched),
+            entity_tags: None,
+        };
+        let respond = |last_modified| {
+            if_modified_since
+                .respond(last_modified, || Ok::<_, ()>(42))
+                .expect("valid response")
+                .response
+        };
+
+        assert_eq!(respond(Some(fetched)), None);
+        assert_eq!(respond(Some(fetched - chrono::Duration::days(1))), None);
+        assert_eq!(
+            respond(Some(fetched + chrono::Duration::seconds(1))),
+            Some(42)
+        );
+        assert_eq!(respond(None), Some(42));
+
+        // Dates are limited to seconds ...
+        assert_eq!(
+            respond(Some(fetched + chrono::Duration::milliseconds(500))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_respond_entity_tag() {
+        let fetched = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let changed = fetched + chrono::Duration::milliseconds(500);
+        let respond = |entity_tags: &str, last_modified| {
+            IfModifiedSince {
+                fetched: Some(changed),
+                entity_tags: Some(String::from(entity_tags)),
+            }
+            .respond(Some(last_modified), || Ok::<_, ()>(42))
+            .expect("valid response")
+            .response
+        };
+
+        // ... while entity tags are exact and take precedence.
+        assert_eq!(respond(&entity_tag(fetched), fetched), None);
+        assert_eq!(respond(&entity_tag(fetched), changed), Some(42));
+        assert_eq!(
+            respond(&format!("\"1\", W/{}", entity_tag(changed)), changed),
+            None
+        );
+        assert_eq!(respond("*", changed), None);
+    }
+
+    #[test]
+    fn test_http_date() {
+        let date = Utc.with_ymd_and_hms(1994, 11, 6, 8, 49, 37).unwrap();
+        let formatted = date.format(HTTP_DATE_FORMAT).to_string();
+        assert_eq!(formatted, "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(
+            DateTime::parse_from_rfc2822(&formatted).map(|value| value.with_timezone(&Utc)),
+            Ok(date)
+        );
+    }
+}
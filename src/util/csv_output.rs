@@ -0,0 +1,37 @@
+use rocket::{
+    http::{ContentType, Header},
+    response::{self, Responder},
+    Request, Response,
+};
+
+/// A CSV file which is downloaded instead of viewed.
+#[derive(Debug, Clone)]
+pub struct CsvOutput {
+    content: Vec<u8>,
+    filename: &'static str,
+}
+
+impl CsvOutput {
+    /// Create the file by writing its content within the callback.
+    pub fn create(
+        filename: &'static str,
+        callback: impl FnOnce(&mut Vec<u8>) -> Result<(), crate::Error>,
+    ) -> Result<Self, crate::Error> {
+        let mut content = Vec::new();
+        callback(&mut content)?;
+        Ok(CsvOutput { content, filename })
+    }
+}
+
+impl<'r> Responder<'r, 'static> for CsvOutput {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::CSV)
+            .header(Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .sized_body(self.content.len(), std::io::Cursor::new(self.content))
+            .ok()
+    }
+}
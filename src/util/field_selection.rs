@@ -0,0 +1,76 @@
+use serde::Serialize;
+
+use crate::backend::database::Selectable;
+
+/// An output which is restricted to the fields requested by the client, i.e. `?fields=name,email`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldSelection<T> {
+    /// All the fields are kept as they are.
+    All(T),
+    /// Only the requested fields of the serialized output are kept.
+    Selected(serde_json::Value),
+}
+
+impl<T: Serialize> FieldSelection<T> {
+    /// Select the comma-separated fields of an output or of all outputs within a list of the entity.
+    /// Each field must be an output field of the entity or one of its timestamps, if tracked. Repeated fields are kept once.
+    pub fn new<E: Selectable>(value: T, fields: Option<&str>) -> Result<Self, crate::Error> {
+        let fields = match fields {
+            Some(fields) => fields,
+            None => return Ok(FieldSelection::All(value)),
+        };
+        let mut selected: Vec<&str> = Vec::new();
+        for field in fields.split(',').map(str::trim) {
+            let known = E::OUTPUT_FIELDS.contains(&field)
+                || (E::TIMESTAMPED && ["created_at", "updated_at"].contains(&field));
+            if !known {
+                return Err(crate::Error::InvalidValue(format!(
+                    "unknown field '{}'",
+                    field
+                )));
+            }
+            if !selected.contains(&field) {
+                selected.push(field);
+            }
+        }
+
+        let value =
+            serde_json::to_value(value).map_err(|_| rocket::http::Status::InternalServerError)?;
+        Ok(FieldSelection::Selected(match value {
+            serde_json::Value::Array(values) => serde_json::Value::Array(
+                values
+                    .into_iter()
+                    .map(|value| Self::select(value, &selected))
+                    .collect(),
+            ),
+            value => Self::select(value, &selected),
+        }))
+    }
+
+    /// Keep the fields of an object. Fields omitted in the output, i.e. missing timestamps, remain omitted.
+    fn select(value: serde_json::Value, fields: &[&str]) -> serde_json::Value {
+        let mut value = match value {
+            serde_json::Value::Object(value) => value,
+            value => return value,
+        };
+        serde_json::Value::Object(
+            fields
+                .iter()
+                .filter_map(|&field| {
+                    value
+                        .remove(field)
+                        .map(|value| (String::from(field), value))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<T: Serialize> Serialize for FieldSelection<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            FieldSelection::All(value) => value.serialize(serializer),
+            FieldSelection::Selected(value) => value.serialize(serializer),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    backend::{
+        audit::AuditEntry,
+        database::{PrimaryKey, Selectable},
+        user::User,
+        Column, FutureDate, WhereClause,
+    },
+    Error,
+};
+
+/// The query parameters restricting a listing of entries, i.e. `?filter_column=category&filter_value=2`.
+#[derive(Debug, Clone, Default, FromForm)]
+pub struct ListFilter<'r> {
+    /// The comma-separated fields to keep in each entry.
+    pub fields: Option<&'r str>,
+    pub filter_column: Option<&'r str>,
+    pub filter_value: Option<String>,
+    /// The first date of the range, if the entries have a date.
+    pub from: Option<&'r str>,
+    /// The last date of the range, if the entries have a date.
+    pub to: Option<&'r str>,
+    pub include_archived: Option<bool>,
+    /// Report the total number of entries matching the filter.
+    pub with_count: Option<bool>,
+}
+
+impl<'r> ListFilter<'r> {
+    /// Check the parameters and turn them into the conditions the entries must satisfy.
+    pub fn where_clause<T: Selectable>(&self) -> Result<WhereClause<T>, Error> {
+        let mut where_clause = WhereClause::new();
+        match (self.filter_column, &self.filter_value) {
+            (Some(column), Some(value)) => {
+                let column = Column::<T>::parse_public(column)
+                    .map_err(|_| Error::InvalidValue(format!("unknown column '{}'", column)))?;
+                where_clause = where_clause.equals(column, value.clone());
+            }
+            (None, None) => {}
+            _ => {
+                return Err(Error::InvalidValue(String::from(
+                    "a filter requires both a column and a value",
+                )))
+            }
+        }
+
+        if self.from.is_some() || self.to.is_some() {
+            let column = T::RANGE_COLUMN
+                .and_then(|column| Column::<T>::try_from(column).ok())
+                .ok_or_else(|| {
+                    Error::InvalidValue(String::from(
+                        "the entries have no date to select a range of",
+                    ))
+                })?;
+            let (from, to) = parse_range(self.from, self.to)?;
+            where_clause = where_clause.within(column, from, to);
+        }
+
+        if !self.include_archived.unwrap_or(false) {
+            where_clause = where_clause.without_archived();
+        }
+        Ok(where_clause)
+    }
+}
+
+/// The query parameters restricting the audit log, i.e. `?user=2&table=persons&from=2024-01-01`.
+#[derive(Debug, Clone, Default, FromForm)]
+pub struct AuditFilter<'r> {
+    pub user: Option<i64>,
+    pub table: Option<&'r str>,
+    /// The first day of the changes.
+    pub from: Option<&'r str>,
+    /// The last day of the changes.
+    pub to: Option<&'r str>,
+}
+
+impl<'r> AuditFilter<'r> {
+    /// Check the parameters and turn them into the conditions the changes must satisfy.
+    pub fn where_clause(&self) -> Result<WhereClause<AuditEntry>, Error> {
+        let (from, to) = parse_range(self.from, self.to)?;
+        Ok(AuditEntry::filter(
+            self.user.map(PrimaryKey::<User>::from),
+            self.table,
+            from,
+            to,
+        ))
+    }
+}
+
+/// Parse the dates of a range, which must not end before it starts.
+fn parse_range(
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<(Option<FutureDate>, Option<FutureDate>), Error> {
+    let parse = |value: Option<&str>| {
+        value
+            .map(|value| {
+                FutureDate::try_from(value)
+                    .map_err(|_| Error::InvalidValue(format!("invalid date '{}'", value)))
+            })
+            .transpose()
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+    if matches!((from, to), (Some(from), Some(to)) if from > to) {
+        return Err(Error::InvalidValue(String::from(
+            "the range ends before it starts",
+        )));
+    }
+    Ok((from, to))
+}
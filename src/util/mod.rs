@@ -1,7 +1,23 @@
+mod backup_output;
+mod conditional;
+mod csv_output;
 mod expected_file_type;
+mod field_selection;
 mod flexible_input;
+mod list_filter;
+mod page;
 mod pdf_output;
+mod vcard_output;
+mod zip_output;
 
+pub use self::backup_output::BackupOutput;
+pub use self::conditional::{Conditional, IfModifiedSince};
+pub use self::csv_output::CsvOutput;
 pub use self::expected_file_type::{ExpectedFileType, Html, Json};
+pub use self::field_selection::FieldSelection;
 pub use self::flexible_input::{FlexibleInput, FormInputType};
+pub use self::list_filter::{AuditFilter, ListFilter};
+pub use self::page::Page;
 pub use self::pdf_output::PdfOutput;
+pub use self::vcard_output::VcardOutput;
+pub use self::zip_output::ZipOutput;
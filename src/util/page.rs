@@ -0,0 +1,107 @@
+use serde::Serialize;
+
+use crate::backend::{database::Selectable, Pagination};
+
+/// A page of outputs. On request, i.e. `?with_count=true`, it is wrapped together with its position within all matching elements.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Page<T> {
+    /// The outputs are kept as they are.
+    Items(T),
+    /// The outputs are accompanied by the information required for showing a page indicator.
+    Counted {
+        items: T,
+        total: usize,
+        offset: usize,
+        limit: usize,
+        has_more: bool,
+    },
+    /// The outputs of a page selected by a cursor, i.e. `?after=42`, together with the cursor of the next page if there might be one.
+    Cursor {
+        items: T,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        total: Option<usize>,
+        limit: usize,
+        next_cursor: Option<i64>,
+    },
+}
+
+impl<T> Page<T> {
+    /// Wrap the outputs of the page together with the total number of elements matched.
+    pub fn counted<E: Selectable>(items: T, total: usize, pagination: &Pagination<E>) -> Self {
+        Page::Counted {
+            items,
+            total,
+            offset: pagination.offset,
+            limit: pagination.limit.into(),
+            has_more: pagination.end_offset() < total,
+        }
+    }
+
+    /// Wrap the outputs of a page selected by a cursor. The next cursor is only set if more elements follow the page, see `Pagination::has_more`.
+    pub fn cursor<E: Selectable>(
+        items: T,
+        primary_keys: &[i64],
+        total: Option<usize>,
+        has_more: bool,
+        pagination: &Pagination<E>,
+    ) -> Self {
+        Page::Cursor {
+            items,
+            total,
+            limit: pagination.limit.into(),
+            next_cursor: match has_more {
+                true => primary_keys.last().copied(),
+                false => None,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Page;
+    use crate::backend::{person::Person, Limit, Order, Pagination};
+
+    #[test]
+    fn test_serialize() {
+        assert_eq!(
+            serde_json::to_value(Page::Items(vec![1, 2])).expect("valid json"),
+            serde_json::json!([1, 2])
+        );
+
+        let pagination = Pagination::<Person>::new("id", 2, Limit::from(2), Order::Ascending)
+            .expect("valid pagination");
+        assert_eq!(
+            serde_json::to_value(Page::counted(vec![3, 4], 5, &pagination)).expect("valid json"),
+            serde_json::json!({"items": [3, 4], "total": 5, "offset": 2, "limit": 2, "has_more": true})
+        );
+        assert_eq!(
+            serde_json::to_value(Page::counted(vec![3, 4], 4, &pagination)).expect("valid json")
+                ["has_more"],
+            false
+        );
+    }
+
+    #[test]
+    fn test_serialize_cursor() {
+        let pagination = Pagination::<Person>::from_cursor(2, Limit::from(2));
+        assert_eq!(
+            serde_json::to_value(Page::cursor(vec![3, 4], &[3, 4], None, true, &pagination))
+                .expect("valid json"),
+            serde_json::json!({"items": [3, 4], "limit": 2, "next_cursor": 4})
+        );
+        assert_eq!(
+            serde_json::to_value(Page::cursor(vec![3], &[3], Some(3), false, &pagination))
+                .expect("valid json"),
+            serde_json::json!({"items": [3], "total": 3, "limit": 2, "next_cursor": null})
+        );
+
+        // A full page is not followed by another one if nothing is left.
+        assert_eq!(
+            serde_json::to_value(Page::cursor(vec![3, 4], &[3, 4], None, false, &pagination))
+                .expect("valid json")["next_cursor"],
+            serde_json::Value::Null
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::backend::{
     database::{Database, PrimaryKey},
-    document::Document,
+    document::{Document, DocumentType},
 };
 use rocket::{
     http::{ContentType, Header},
@@ -9,26 +9,50 @@ use rocket::{
 };
 
 #[derive(Debug, Clone)]
-pub struct PdfOutput(Vec<u8>);
+pub struct PdfOutput {
+    content: Vec<u8>,
+    /// The file name without extension if the file should be downloaded instead of viewed.
+    attachment: Option<String>,
+}
 
 impl PdfOutput {
     pub fn new(
         database: &Database,
         document_id: PrimaryKey<Document>,
     ) -> Result<Self, crate::Error> {
-        Ok(PdfOutput(Document::load_into_memory(
-            database,
-            document_id,
-        )?))
+        Ok(PdfOutput {
+            content: Document::load_into_memory(database, document_id)?,
+            attachment: None,
+        })
+    }
+
+    /// Force a download with the given file name. The extension is added according to the content.
+    pub fn as_attachment(mut self, name: impl Into<String>) -> Self {
+        self.attachment = Some(name.into());
+        self
     }
 }
 
 impl<'r> Responder<'r, 'r> for PdfOutput {
     fn respond_to(self, _: &'r Request<'_>) -> response::Result<'r> {
+        // Documents were PDF files only in the past, so they are the fallback.
+        let document_type =
+            DocumentType::detect(DocumentType::ALL, &self.content).unwrap_or(DocumentType::Pdf);
+        let content_type =
+            ContentType::parse_flexible(document_type.media_type()).unwrap_or(ContentType::PDF);
+        let disposition = match self.attachment {
+            Some(name) => format!(
+                "attachment; filename=\"{}{}\"",
+                name.replace(['"', '\\'], ""),
+                document_type.extension()
+            ),
+            None => String::from("inline"),
+        };
+
         Response::build()
-            .header(ContentType::PDF)
-            .header(Header::new("Content-Disposition", "inline"))
-            .sized_body(self.0.len(), std::io::Cursor::new(self.0))
+            .header(content_type)
+            .header(Header::new("Content-Disposition", disposition))
+            .sized_body(self.content.len(), std::io::Cursor::new(self.content))
             .ok()
     }
 }
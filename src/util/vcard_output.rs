@@ -0,0 +1,38 @@
+use rocket::{
+    http::{ContentType, Header},
+    response::{self, Responder},
+    Request, Response,
+};
+
+/// One or more vCards which are downloaded for importing them into an address book.
+#[derive(Debug, Clone)]
+pub struct VcardOutput {
+    content: String,
+    /// The file name without extension.
+    name: String,
+}
+
+impl VcardOutput {
+    pub fn new(content: String, name: impl Into<String>) -> Self {
+        VcardOutput {
+            content,
+            name: name.into(),
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for VcardOutput {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::new("text", "vcard"))
+            .header(Header::new(
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"{}.vcf\"",
+                    self.name.replace(['"', '\\'], "")
+                ),
+            ))
+            .sized_body(self.content.len(), std::io::Cursor::new(self.content))
+            .ok()
+    }
+}
@@ -0,0 +1,78 @@
+use std::io::{Seek, Write};
+
+use rocket::{
+    http::{ContentType, Header},
+    response::{self, Responder},
+    Request, Response,
+};
+use zip::{write::FileOptions, ZipWriter};
+
+/// A ZIP archive which is written into a temporary file and streamed to the client afterwards.
+#[derive(Debug)]
+pub struct ZipOutput {
+    file: std::fs::File,
+    filename: &'static str,
+}
+
+impl ZipOutput {
+    /// Create the archive by adding all the files within the callback.
+    pub fn create(
+        filename: &'static str,
+        callback: impl FnOnce(&mut ZipArchive) -> Result<(), crate::Error>,
+    ) -> Result<Self, crate::Error> {
+        let mut archive = ZipArchive(ZipWriter::new(Self::temporary_file()?));
+        callback(&mut archive)?;
+
+        let mut file = archive.0.finish().map_err(crate::Error::from_zip)?;
+        file.rewind()?;
+        Ok(ZipOutput { file, filename })
+    }
+
+    /// Create an anonymous file which is removed as soon as the handle is closed.
+    fn temporary_file() -> Result<std::fs::File, crate::Error> {
+        let mut random = [0u8; 8];
+        getrandom::getrandom(&mut random).or(Err(rocket::http::Status::InternalServerError))?;
+
+        let path = std::env::temp_dir().join(format!(
+            "shelby-{}.zip",
+            random.map(|value| format!("{:02x}", value)).concat()
+        ));
+        let file = std::fs::File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        std::fs::remove_file(&path)?;
+        Ok(file)
+    }
+}
+
+/// The archive while it is created.
+pub struct ZipArchive(ZipWriter<std::fs::File>);
+
+impl ZipArchive {
+    /// Add a new file to the archive and write its content.
+    pub fn add_file(
+        &mut self,
+        name: &str,
+        callback: impl FnOnce(&mut dyn Write) -> Result<(), crate::Error>,
+    ) -> Result<(), crate::Error> {
+        self.0
+            .start_file(name, FileOptions::default())
+            .map_err(crate::Error::from_zip)?;
+        callback(&mut self.0)
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ZipOutput {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::ZIP)
+            .header(Header::new(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", self.filename),
+            ))
+            .streamed_body(rocket::tokio::fs::File::from_std(self.file))
+            .ok()
+    }
+}